@@ -0,0 +1,17 @@
+//! An example of returning a `MainReport` from `main`
+//!
+//! Errors returned from `main` are printed with their `Debug` impl, a `MainReport` prints the
+//! error and each of its sources on its own line instead, along with a backtrace when
+//! `RUST_BACKTRACE` is set
+use errtools::{MainReport, WrapErr, Wrapped};
+
+fn read_config(path: &str) -> Result<String, Wrapped<std::io::Error>> {
+    std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("unable to read config from path: {}", path))
+}
+
+fn main() -> Result<(), MainReport> {
+    let config = read_config("fake_file").wrap_err::<_, Wrapped<_>>("unable to start")?;
+    println!("{}", config);
+    Ok(())
+}
@@ -3,7 +3,7 @@
 //! To use `WrapErr` with your type you have to impl from for (E: Error, String), this lets you
 //! wrap arbitrary errors and add a message to them, it will then construct your type using this
 //! from impl to conveniently create a new type
-use errtools::{ErrTools, WrapErr};
+use errtools::{report, ErrTools, WrapErr};
 use std::error::Error;
 use thiserror::Error;
 
@@ -26,17 +26,6 @@ where
     }
 }
 
-fn report_error(error: &(dyn Error)) {
-    let mut cur_error = Some(error);
-    let mut ind = 0;
-
-    while let Some(error) = cur_error {
-        println!("{}: {}", ind, error);
-        ind += 1;
-        cur_error = error.source();
-    }
-}
-
 fn main() {
     let path = "fake_file";
     let error: PublicEnumError = std::fs::read_to_string(path)
@@ -46,5 +35,6 @@ fn main() {
         .wrap_err("total failure!")
         .unwrap_err();
 
-    report_error(&error.wrap_err::<_, PublicEnumError>("one more thing"));
+    let error = error.wrap_err::<_, PublicEnumError>("one more thing");
+    println!("{}", report::numbered(&error));
 }
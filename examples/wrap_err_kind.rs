@@ -1,4 +1,4 @@
-use errtools::WrapErr;
+use errtools::{report, WrapErr};
 use std::error::Error;
 use thiserror::Error;
 
@@ -28,17 +28,6 @@ where
     }
 }
 
-fn report_error(error: &(dyn Error)) {
-    let mut cur_error = Some(error);
-    let mut ind = 0;
-
-    while let Some(error) = cur_error {
-        println!("{}: {}", ind, error);
-        ind += 1;
-        cur_error = error.source();
-    }
-}
-
 fn do_thing(path: &str) -> Result<String, PublicErrorStruct> {
     let s = std::fs::read_to_string(path).wrap_err_with::<_, _, PrivateKind>(|| {
         format!("unable to read file from path: {}", path)
@@ -50,5 +39,5 @@ fn do_thing(path: &str) -> Result<String, PublicErrorStruct> {
 fn main() {
     let path = "fake_file";
     let error = do_thing(path).unwrap_err();
-    report_error(&error);
+    println!("{}", report::numbered(&error));
 }
@@ -0,0 +1,95 @@
+//! Returning error chains from `actix-web` handlers as serialized JSON
+//!
+//! ```rust
+//! use errtools::actix::ErrorResponse;
+//!
+//! async fn load_user(id: u32) -> Result<String, ErrorResponse> {
+//!     let name = std::fs::read_to_string(format!("users/{}", id))?;
+//!     Ok(name)
+//! }
+//! ```
+use crate::{ErrTools, Report};
+use actix_web::http::header::ContentType;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use std::error::Error;
+use std::fmt;
+
+/// An error chain as a JSON response
+///
+/// The body is the chain in its usual serialized shape. The status is the one
+/// given to [`ErrorResponse::new`] or, for errors converted with `?`, the one
+/// found by [`ErrTools::http_status_in_chain`], or else
+/// `500 Internal Server Error`.
+///
+/// `Display` writes the chain on one line and `Debug` as a multi-line
+/// [`Report`], which is what `actix-web` logs for failed requests.
+///
+/// [`ErrTools::http_status_in_chain`]: crate::ErrTools::http_status_in_chain
+pub struct ErrorResponse {
+    status: Option<StatusCode>,
+    error: Box<dyn Error + Send + Sync + 'static>,
+}
+
+impl ErrorResponse {
+    /// Respond to `error` with `status`, regardless of its chain
+    pub fn new(
+        status: StatusCode,
+        error: impl Into<Box<dyn Error + Send + Sync + 'static>>,
+    ) -> Self {
+        Self {
+            status: Some(status),
+            error: error.into(),
+        }
+    }
+
+    /// The error being responded with
+    pub fn into_inner(self) -> Box<dyn Error + Send + Sync + 'static> {
+        self.error
+    }
+}
+
+impl<E> From<E> for ErrorResponse
+where
+    E: Into<Box<dyn Error + Send + Sync + 'static>>,
+{
+    fn from(error: E) -> Self {
+        Self {
+            status: None,
+            error: error.into(),
+        }
+    }
+}
+
+impl fmt::Display for ErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Report::new(&*self.error).fmt(f)
+    }
+}
+
+impl fmt::Debug for ErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#}", Report::new(&*self.error))
+    }
+}
+
+impl ResponseError for ErrorResponse {
+    fn status_code(&self) -> StatusCode {
+        self.status.unwrap_or_else(|| {
+            self.error
+                .http_status_in_chain()
+                .and_then(|status| StatusCode::from_u16(status).ok())
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+        })
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let error: &(dyn Error + 'static) = self.error.as_ref();
+        match serde_json::to_vec(&error.serialize()) {
+            Ok(body) => HttpResponse::build(self.status_code())
+                .insert_header(ContentType::json())
+                .body(body),
+            Err(_) => HttpResponse::InternalServerError().finish(),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::location::LocationParts;
+use std::backtrace::Backtrace;
+use std::error::Error;
+use std::fmt;
+
+/// Construct an [`AdhocError`] from a format string
+///
+/// Accepts the same arguments as `format!` and captures a backtrace at the
+/// point of invocation.
+///
+/// ```rust
+/// use errtools::adhoc;
+///
+/// let user_id = 42;
+/// let error: Box<dyn std::error::Error> = adhoc!("user {} has no active plan", user_id).into();
+/// assert_eq!(error.to_string(), "user 42 has no active plan");
+/// ```
+#[macro_export]
+macro_rules! adhoc {
+    ($($arg:tt)*) => {
+        $crate::AdhocError::new(::std::format!($($arg)*))
+    };
+}
+
+/// An error with no underlying cause, built from a message
+///
+/// Useful as the root of an error chain when nothing actually failed but some
+/// validation didn't pass. Usually constructed with the [`adhoc!`] macro.
+///
+/// Panics caught with [`panic::catch`] are also returned as an `AdhocError`,
+/// which then serializes the location of the panic as its `location` field.
+///
+/// [`panic::catch`]: crate::panic::catch
+pub struct AdhocError {
+    msg: String,
+    backtrace: Backtrace,
+    location: Option<LocationParts>,
+}
+
+impl AdhocError {
+    /// Create a new `AdhocError` with the given message, capturing a backtrace
+    pub fn new<D>(msg: D) -> Self
+    where
+        D: fmt::Display,
+    {
+        Self {
+            msg: msg.to_string(),
+            backtrace: Backtrace::capture(),
+            location: None,
+        }
+    }
+
+    pub(crate) fn from_panic(msg: String, location: Option<LocationParts>) -> Self {
+        Self {
+            msg,
+            backtrace: Backtrace::capture(),
+            location,
+        }
+    }
+
+    pub(crate) fn panic_location(&self) -> Option<&LocationParts> {
+        self.location.as_ref()
+    }
+}
+
+impl fmt::Display for AdhocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.msg)
+    }
+}
+
+impl fmt::Debug for AdhocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdhocError")
+            .field("msg", &self.msg)
+            .field("backtrace", &self.backtrace)
+            .field("location", &self.location)
+            .finish()
+    }
+}
+
+impl Error for AdhocError {
+    fn backtrace(&self) -> Option<&Backtrace> {
+        Some(&self.backtrace)
+    }
+}
@@ -0,0 +1,97 @@
+//! Arbitrary error chains for property tests
+//!
+//! With the `proptest` feature enabled, [`deserialize::Error`] implements
+//! [`Arbitrary`], generating chains whose errors have optional type names and
+//! backtraces, and messages ranging from empty through unicode-heavy to several
+//! kilobytes long.
+//!
+//! ```rust
+//! use errtools::arbitrary::ChainParams;
+//! use errtools::deserialize;
+//! use proptest::prelude::*;
+//!
+//! proptest! {
+//!     #[test]
+//!     fn display_never_panics(
+//!         err in any_with::<deserialize::Error>(ChainParams::new().chain_len(1..=3))
+//!     ) {
+//!         let _ = format!("{:#}", err);
+//!     }
+//! }
+//! ```
+use crate::deserialize;
+use proptest::arbitrary::Arbitrary;
+use proptest::prelude::*;
+use std::ops::RangeInclusive;
+
+/// The parameters of the chains generated by the [`Arbitrary`] impl of
+/// [`deserialize::Error`]
+#[derive(Debug, Clone)]
+pub struct ChainParams {
+    chain_len: RangeInclusive<usize>,
+}
+
+impl ChainParams {
+    /// Generate chains of 1 to 8 errors
+    pub fn new() -> Self {
+        Self { chain_len: 1..=8 }
+    }
+
+    /// Generate chains with a number of errors in `len`
+    ///
+    /// Every chain has at least one error, so a range starting at 0 is
+    /// treated as starting at 1.
+    pub fn chain_len(mut self, len: RangeInclusive<usize>) -> Self {
+        let (start, end) = len.into_inner();
+        self.chain_len = start.max(1)..=end.max(1);
+        self
+    }
+}
+
+impl Default for ChainParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Arbitrary for deserialize::Error {
+    type Parameters = ChainParams;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(params: ChainParams) -> Self::Strategy {
+        proptest::collection::vec(node(), params.chain_len)
+            .prop_map(|nodes| {
+                let mut source = None;
+                for (type_name, msg, backtrace) in nodes.into_iter().rev() {
+                    let mut builder = deserialize::Error::builder().msg(msg);
+                    if let Some(type_name) = type_name {
+                        builder = builder.type_name(type_name);
+                    }
+                    if let Some(backtrace) = backtrace {
+                        builder = builder.backtrace(backtrace);
+                    }
+                    if let Some(source) = source {
+                        builder = builder.source(source);
+                    }
+                    source = Some(builder.build());
+                }
+                source.expect("chains have at least one error")
+            })
+            .boxed()
+    }
+}
+
+/// The type name, message, and backtrace of a single error
+fn node() -> impl Strategy<Value = (Option<String>, String, Option<String>)> {
+    let type_name = proptest::option::of("[a-z][a-z0-9_]{0,8}(::[A-Z][A-Za-z0-9]{0,12}){1,3}");
+    let msg = prop_oneof![
+        1 => Just(String::new()),
+        4 => any::<String>(),
+        4 => "\\PC{1,80}",
+        1 => "\\PC{1024,4096}",
+    ];
+    let backtrace =
+        proptest::option::of("( *[0-9]{1,2}: [a-z_]{1,12}(::[a-z_]{1,12}){0,3}\n){1,16}");
+
+    (type_name, msg, backtrace)
+}
@@ -0,0 +1,107 @@
+//! Returning error chains from `axum` handlers as RFC 7807 problem details
+//!
+//! ```rust
+//! use axum::http::StatusCode;
+//! use errtools::axum::ErrorResponse;
+//!
+//! async fn load_user(id: u32) -> Result<String, ErrorResponse> {
+//!     let name = std::fs::read_to_string(format!("users/{}", id))?;
+//!     if name.is_empty() {
+//!         return Err(ErrorResponse::new(StatusCode::NOT_FOUND, "no such user"));
+//!     }
+//!     Ok(name)
+//! }
+//! ```
+use crate::problem::{self, Problem};
+use crate::ErrTools;
+use axum::http::header::{HeaderValue, CONTENT_TYPE};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use std::error::Error;
+use std::fmt;
+
+/// An error chain as an `application/problem+json` response
+///
+/// The body is the [`Problem`] describing the chain, which includes the
+/// chain itself under the `errors` extension member. The status is the one
+/// given to [`ErrorResponse::new`] or, for errors converted with `?`, the one
+/// found by [`ErrTools::http_status_in_chain`], or else
+/// `500 Internal Server Error`.
+///
+/// Like [`MainReport`], it doesn't implement `Error` itself, so that any
+/// error can be converted into it.
+///
+/// [`ErrTools::http_status_in_chain`]: crate::ErrTools::http_status_in_chain
+/// [`MainReport`]: crate::MainReport
+pub struct ErrorResponse {
+    status: Option<StatusCode>,
+    error: Box<dyn Error + Send + Sync + 'static>,
+}
+
+impl ErrorResponse {
+    /// Respond to `error` with `status`, regardless of its chain
+    pub fn new(
+        status: StatusCode,
+        error: impl Into<Box<dyn Error + Send + Sync + 'static>>,
+    ) -> Self {
+        Self {
+            status: Some(status),
+            error: error.into(),
+        }
+    }
+
+    /// The status of the response
+    pub fn status(&self) -> StatusCode {
+        self.status.unwrap_or_else(|| {
+            self.error
+                .http_status_in_chain()
+                .and_then(|status| StatusCode::from_u16(status).ok())
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+        })
+    }
+
+    /// The error being responded with
+    pub fn into_inner(self) -> Box<dyn Error + Send + Sync + 'static> {
+        self.error
+    }
+}
+
+impl<E> From<E> for ErrorResponse
+where
+    E: Into<Box<dyn Error + Send + Sync + 'static>>,
+{
+    fn from(error: E) -> Self {
+        Self {
+            status: None,
+            error: error.into(),
+        }
+    }
+}
+
+impl fmt::Debug for ErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorResponse")
+            .field("status", &self.status())
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl IntoResponse for ErrorResponse {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let problem = Problem::from_error(&*self.error, status);
+        match serde_json::to_vec(&problem) {
+            Ok(body) => (
+                status,
+                [(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static(problem::CONTENT_TYPE),
+                )],
+                body,
+            )
+                .into_response(),
+            Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use crate::chain::Chain;
+use serde::de::{self, Deserialize, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::error::Error;
+use std::fmt;
+
+/// A single frame of a backtrace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// The demangled symbol name
+    pub symbol: String,
+    /// The source file, if known
+    pub file: Option<String>,
+    /// The line number within `file`, if known
+    pub line: Option<u32>,
+}
+
+impl Serialize for Frame {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut f = serializer.serialize_struct("frame", 3)?;
+        f.serialize_field("symbol", &self.symbol)?;
+        f.serialize_field("file", &self.file)?;
+        f.serialize_field("line", &self.line)?;
+        f.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Frame {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct FrameVisitor;
+
+        impl<'de> Visitor<'de> for FrameVisitor {
+            type Value = Frame;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a backtrace frame")
+            }
+
+            fn visit_map<V>(self, mut map: V) -> Result<Frame, V::Error>
+            where
+                V: MapAccess<'de>,
+            {
+                let mut symbol = None;
+                let mut file = None;
+                let mut line = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "symbol" => symbol = Some(map.next_value()?),
+                        "file" => file = map.next_value()?,
+                        "line" => line = map.next_value()?,
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                Ok(Frame {
+                    symbol: symbol.ok_or_else(|| de::Error::missing_field("symbol"))?,
+                    file,
+                    line,
+                })
+            }
+        }
+
+        deserializer.deserialize_struct("frame", &["symbol", "file", "line"], FrameVisitor)
+    }
+}
+
+/// Writes `frames` in the format of `std::backtrace::Backtrace`, which
+/// `parse_frames` reads back
+pub(crate) fn fmt_frames(frames: &[Frame], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (index, frame) in frames.iter().enumerate() {
+        writeln!(f, "{:>4}: {}", index, frame.symbol)?;
+        match (&frame.file, frame.line) {
+            (Some(file), Some(line)) => writeln!(f, "             at {}:{}", file, line)?,
+            (Some(file), None) => writeln!(f, "             at {}", file)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The backtrace of `error`, from `Error::backtrace` or, with the `provide`
+/// feature, from the generic member access API
+///
+/// Errors built with recent versions of `thiserror` only expose their
+/// backtraces through `Error::provide`. Backtraces from targets which can't
+/// capture them are treated as missing, see [`is_supported`].
+pub(crate) fn backtrace_of<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a Backtrace> {
+    #[cfg(feature = "provide")]
+    let backtrace = error
+        .backtrace()
+        .or_else(|| error.request_ref::<Backtrace>());
+    #[cfg(not(feature = "provide"))]
+    let backtrace = error.backtrace();
+
+    backtrace.filter(|backtrace| is_supported(backtrace.status()))
+}
+
+/// Whether a backtrace with `status` is worth rendering
+///
+/// On targets such as `wasm32-unknown-unknown` capturing a backtrace is a
+/// no-op, and every backtrace just says it is unsupported. Those are left
+/// out, so they serialize as `null`, rather than repeating that message for
+/// every error in the chain.
+fn is_supported(status: BacktraceStatus) -> bool {
+    status != BacktraceStatus::Unsupported
+}
+
+/// The backtrace of `error` for rendering, either one it captured or, for
+/// errors read back by the deserializer, the text of the one it was
+/// serialized with
+pub(crate) fn backtrace_display_of<'a>(
+    error: &'a (dyn Error + 'static),
+) -> Option<&'a (dyn fmt::Display + 'static)> {
+    match backtrace_of(error) {
+        Some(backtrace) => Some(backtrace),
+        None => crate::deserialize::recorded_backtrace(error).map(|backtrace| backtrace as _),
+    }
+}
+
+/// The backtrace of the innermost error in the chain of `error` that has one,
+/// which is the one captured closest to where the chain started
+///
+/// Backtraces that weren't captured because capturing is disabled are
+/// skipped.
+pub(crate) fn deepest_backtrace<'a>(
+    error: &'a (dyn Error + 'static),
+) -> Option<&'a (dyn fmt::Display + 'static)> {
+    Chain::new(error)
+        .filter_map(|error| match backtrace_of(error) {
+            Some(backtrace) if backtrace.status() == BacktraceStatus::Captured => {
+                Some(backtrace as _)
+            }
+            Some(_) => None,
+            None => crate::deserialize::recorded_backtrace(error).map(|backtrace| backtrace as _),
+        })
+        .last()
+}
+
+/// The `tracing_error::SpanTrace` captured by `error`, with the `spantrace`
+/// feature
+///
+/// Found through `tracing_error::ExtractSpanTrace`, which recognizes the
+/// errors that `TracedError` puts into the chain.
+pub(crate) fn span_trace_of<'a>(
+    error: &'a (dyn Error + 'static),
+) -> Option<&'a (dyn fmt::Display + 'static)> {
+    #[cfg(feature = "spantrace")]
+    {
+        use tracing_error::ExtractSpanTrace;
+
+        error.span_trace().map(|span_trace| span_trace as _)
+    }
+    #[cfg(not(feature = "spantrace"))]
+    {
+        let _ = error;
+        None
+    }
+}
+
+/// Parse the `Display` output of a `std::backtrace::Backtrace` into frames
+///
+/// Each frame starts with a line of the form `  N: symbol`, optionally followed
+/// by a line of the form `      at file:line:column`.
+pub(crate) fn parse_frames(backtrace: &str) -> Vec<Frame> {
+    let mut frames = Vec::<Frame>::new();
+
+    for line in backtrace.lines() {
+        let line = line.trim();
+
+        if let Some(location) = line.strip_prefix("at ") {
+            if let Some(frame) = frames.last_mut() {
+                let (file, line) = parse_location(location);
+                frame.file = Some(file.to_string());
+                frame.line = line;
+            }
+        } else if let Some(ind) = line.find(": ") {
+            let (index, symbol) = (&line[..ind], &line[ind + 2..]);
+            if !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()) {
+                frames.push(Frame {
+                    symbol: symbol.to_string(),
+                    file: None,
+                    line: None,
+                });
+            }
+        }
+    }
+
+    frames
+}
+
+fn parse_location(location: &str) -> (&str, Option<u32>) {
+    // `file:line:column`, where the column may be missing
+    match split_number(location) {
+        Some((rest, last)) => match split_number(rest) {
+            Some((file, line)) => (file, Some(line)),
+            None => (rest, Some(last)),
+        },
+        None => (location, None),
+    }
+}
+
+fn split_number(s: &str) -> Option<(&str, u32)> {
+    let ind = s.rfind(':')?;
+    let n = s[ind + 1..].parse().ok()?;
+    Some((&s[..ind], n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsupported_backtraces_are_missing() {
+        assert!(!is_supported(BacktraceStatus::Unsupported));
+        assert!(is_supported(BacktraceStatus::Disabled));
+        assert!(is_supported(BacktraceStatus::Captured));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn host_backtraces() {
+        #[derive(Debug)]
+        struct Traced(Option<Backtrace>);
+
+        impl fmt::Display for Traced {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("traced")
+            }
+        }
+
+        impl Error for Traced {
+            fn backtrace(&self) -> Option<&Backtrace> {
+                self.0.as_ref()
+            }
+        }
+
+        let captured = Traced(Some(Backtrace::force_capture()));
+        assert!(backtrace_of(&captured).is_some());
+        assert!(deepest_backtrace(&captured).is_some());
+
+        let missing = Traced(None);
+        assert!(backtrace_of(&missing).is_none());
+        assert!(backtrace_display_of(&missing).is_none());
+        assert!(deepest_backtrace(&missing).is_none());
+    }
+
+    #[test]
+    fn parse_std_backtrace() {
+        let backtrace = "   0: app::main\n             at ./src/main.rs:10:5\n   1: core::ops::function::FnOnce::call_once\n   2: std::rt::lang_start\n             at /rustc/library/std/src/rt.rs:145\n";
+        let frames = parse_frames(backtrace);
+
+        assert_eq!(
+            frames,
+            [
+                Frame {
+                    symbol: "app::main".into(),
+                    file: Some("./src/main.rs".into()),
+                    line: Some(10),
+                },
+                Frame {
+                    symbol: "core::ops::function::FnOnce::call_once".into(),
+                    file: None,
+                    line: None,
+                },
+                Frame {
+                    symbol: "std::rt::lang_start".into(),
+                    file: Some("/rustc/library/std/src/rt.rs".into()),
+                    line: Some(145),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::HashSet;
+use std::error::Error;
+
+/// An error followed by each of its sources, outermost first
+///
+/// The walk ends before the first element which repeats an earlier one, so
+/// that a chain whose `source()` forms a cycle is still finite. Elements are
+/// compared by their address together with their vtable, since an error and
+/// a source stored as its only field share the same address.
+pub(crate) struct Chain<'a> {
+    next: Option<&'a (dyn Error + 'static)>,
+    seen: HashSet<*const dyn Error>,
+    cyclic: bool,
+}
+
+impl<'a> Chain<'a> {
+    pub(crate) fn new(error: &'a (dyn Error + 'static)) -> Self {
+        Self {
+            next: Some(error),
+            seen: HashSet::new(),
+            cyclic: false,
+        }
+    }
+
+    /// The sources of `error`, without `error` itself
+    ///
+    /// Unlike [`Chain::new`] this accepts errors which aren't `'static`,
+    /// whose sources always are.
+    pub(crate) fn sources(error: &'a dyn Error) -> Self {
+        Self {
+            next: error.source(),
+            seen: HashSet::new(),
+            cyclic: false,
+        }
+    }
+
+    /// Whether the walk stopped because the next element repeated an earlier
+    /// one
+    pub(crate) fn is_cyclic(&self) -> bool {
+        self.cyclic
+    }
+}
+
+impl<'a> Iterator for Chain<'a> {
+    type Item = &'a (dyn Error + 'static);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let error = self.next.take()?;
+        if !self.seen.insert(error as *const dyn Error) {
+            self.cyclic = true;
+            return None;
+        }
+
+        self.next = error.source();
+        Some(error)
+    }
+}
@@ -0,0 +1,60 @@
+/// An error which carries a stable, machine readable code
+///
+/// Codes are emitted as a `code` field when the error is serialized and can be
+/// found anywhere in a chain with [`ErrTools::code_in_chain`], once the
+/// implementor is registered with [`registry::register_coded`]. The
+/// [`registry`] docs explain why.
+///
+/// [`ErrTools::code_in_chain`]: crate::ErrTools::code_in_chain
+/// [`registry`]: crate::registry
+/// [`registry::register_coded`]: crate::registry::register_coded
+pub trait Coded {
+    /// The code identifying this error, if it has one
+    fn code(&self) -> Option<&str>;
+}
+
+/// An error which carries advice for the user on how to resolve it
+///
+/// Used by the `miette` integration to fill in the help text of a
+/// diagnostic, for implementors registered with [`registry::register_help`].
+///
+/// [`registry::register_help`]: crate::registry::register_help
+pub trait Help {
+    /// The advice for this error, if it has any
+    fn help(&self) -> Option<&str>;
+}
+
+/// An error which maps to a process exit code
+///
+/// The code of the outermost error in a chain that has one is found with
+/// [`ErrTools::exit_code_in_chain`] and used as the exit code of a `main`
+/// returning a [`MainReport`]. Implementors are found there once registered
+/// with [`registry::register_exit_status`], or, with the `provide` feature,
+/// when they provide themselves as a `dyn ExitStatus` through
+/// `Error::provide`.
+///
+/// [`ErrTools::exit_code_in_chain`]: crate::ErrTools::exit_code_in_chain
+/// [`MainReport`]: crate::MainReport
+/// [`registry::register_exit_status`]: crate::registry::register_exit_status
+pub trait ExitStatus {
+    /// The exit code for this error, if it has one
+    fn exit_code(&self) -> Option<u8>;
+}
+
+/// An error which maps to an HTTP status code
+///
+/// The status of the outermost error in a chain that has one is found with
+/// [`ErrTools::http_status_in_chain`], which the web framework integrations
+/// use to choose the status of a response. Implementors are found there once
+/// registered with [`registry::register_http_status`], or, with the `provide`
+/// feature, when they provide themselves as a `dyn HttpStatus` through
+/// `Error::provide`. Errors of other crates can be given a status with
+/// [`registry::register_http_status_fallback`].
+///
+/// [`ErrTools::http_status_in_chain`]: crate::ErrTools::http_status_in_chain
+/// [`registry::register_http_status`]: crate::registry::register_http_status
+/// [`registry::register_http_status_fallback`]: crate::registry::register_http_status_fallback
+pub trait HttpStatus {
+    /// The HTTP status code for this error, if it has one
+    fn http_status(&self) -> Option<u16>;
+}
@@ -0,0 +1,499 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Options controlling how an error chain is serialized
+///
+/// Built with chained setters starting from [`SerializeConfig::new`], which
+/// matches the behavior of [`ErrTools::serialize`]. Pass it to
+/// [`ErrTools::serialize_with`] to serialize a chain with these settings.
+///
+/// ```rust
+/// use errtools::{BacktraceMode, SerializeConfig};
+///
+/// let config = SerializeConfig::new()
+///     .backtraces(BacktraceMode::Frames)
+///     .max_depth(16)
+///     .include_type_names(false);
+/// ```
+///
+/// Formats which aren't human readable, such as bincode, always receive the
+/// fixed layout described in [`SerializeConfig::fixed_layout`], so that
+/// positional formats can be read back.
+///
+/// [`ErrTools::serialize`]: crate::ErrTools::serialize
+/// [`ErrTools::serialize_with`]: crate::ErrTools::serialize_with
+#[derive(Clone)]
+pub struct SerializeConfig {
+    pub(crate) backtraces: BacktraceMode,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) include_type_names: bool,
+    pub(crate) layout: ChainLayout,
+    pub(crate) redactor: Option<Arc<dyn Redactor>>,
+    pub(crate) max_msg_len: Option<usize>,
+    pub(crate) max_backtrace_len: Option<usize>,
+    pub(crate) include_debug: bool,
+    pub(crate) include_alternate: bool,
+    pub(crate) fixed_layout: bool,
+    pub(crate) include_version: bool,
+    pub(crate) duplicate_backtraces: DuplicateBacktraces,
+    pub(crate) backtrace_prefixes: Vec<(String, String)>,
+}
+
+/// A hook for scrubbing sensitive data from serialized errors
+///
+/// Applied to every message, backtrace, and other free-form string field of
+/// every error in the chain. Implemented for any `Fn(&str) -> Cow<str>`.
+///
+/// ```rust
+/// use errtools::SerializeConfig;
+/// use std::borrow::Cow;
+///
+/// fn scrub_home(text: &str) -> Cow<'_, str> {
+///     if text.contains("/home/jane") {
+///         text.replace("/home/jane", "~").into()
+///     } else {
+///         text.into()
+///     }
+/// }
+///
+/// let config = SerializeConfig::new().redactor(scrub_home);
+/// ```
+pub trait Redactor: Send + Sync {
+    /// Return `text` with any sensitive data removed
+    fn redact<'a>(&self, text: &'a str) -> Cow<'a, str>;
+}
+
+impl<F> Redactor for F
+where
+    F: for<'a> Fn(&'a str) -> Cow<'a, str> + Send + Sync,
+{
+    fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        self(text)
+    }
+}
+
+/// How backtraces are represented in the serialized output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BacktraceMode {
+    /// The backtrace's `Display` output as a single string
+    String,
+    /// An array of `{symbol, file, line}` objects, one per frame
+    Frames,
+    /// Leave the `backtrace` field out of every node entirely
+    Omit,
+}
+
+/// What to write in place of a backtrace which is repeated further down the
+/// chain
+///
+/// When several layers of an error capture or forward the same backtrace only
+/// the deepest copy, the one closest to where it was captured, is written in
+/// full. Backtraces are duplicates if they are the same object or render to
+/// the same string, so genuinely different traces are always kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateBacktraces {
+    /// Write every backtrace in full
+    Keep,
+    /// Write the string `"(see source)"` instead of the repeated backtrace
+    Marker,
+    /// Write `null` instead of the repeated backtrace
+    Null,
+}
+
+impl DuplicateBacktraces {
+    /// The value written in place of a duplicate backtrace
+    pub(crate) fn marker(self) -> Option<&'static str> {
+        match self {
+            DuplicateBacktraces::Marker => Some("(see source)"),
+            DuplicateBacktraces::Keep | DuplicateBacktraces::Null => None,
+        }
+    }
+}
+
+/// The overall shape of a serialized error chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainLayout {
+    /// Each error is an object whose `source` field holds the next error
+    ///
+    /// Chains deeper than 64 errors continue in the flat layout: the `source`
+    /// of the 64th error is a `{"chain": [...]}` object holding the rest.
+    Nested,
+    /// A `{"chain": [...]}` object listing every error, outermost first,
+    /// without `source` fields
+    Flat,
+}
+
+impl SerializeConfig {
+    /// The default configuration, identical to [`ErrTools::serialize`]
+    ///
+    /// Paths in the cargo registry are replaced with `<registry>`, as
+    /// described in [`SerializeConfig::default_backtrace_prefixes`].
+    ///
+    /// [`ErrTools::serialize`]: crate::ErrTools::serialize
+    pub fn new() -> Self {
+        let config = Self {
+            backtraces: BacktraceMode::String,
+            max_depth: None,
+            include_type_names: true,
+            layout: ChainLayout::Nested,
+            redactor: None,
+            max_msg_len: None,
+            max_backtrace_len: None,
+            include_debug: false,
+            include_alternate: false,
+            fixed_layout: false,
+            include_version: true,
+            duplicate_backtraces: DuplicateBacktraces::Keep,
+            backtrace_prefixes: Vec::new(),
+        };
+        match cargo_registry_dir() {
+            Some(registry) => config.backtrace_prefix(registry.to_string_lossy(), "<registry>"),
+            None => config,
+        }
+    }
+
+    /// Select how backtraces are serialized, defaults to [`BacktraceMode::String`]
+    pub fn backtraces(mut self, mode: BacktraceMode) -> Self {
+        self.backtraces = mode;
+        self
+    }
+
+    /// Serialize at most `depth` errors of the chain, starting from the outermost
+    ///
+    /// If errors are cut off, a final marker node is emitted in their place
+    /// whose message says how many were dropped and whose numeric `truncated`
+    /// field holds the count, or, in the [fixed layout], whose `type_name` is
+    /// `errtools::Truncated<count>`. Either way, the deserializer reads the
+    /// count back as [`deserialize::Error::truncated`]. The outermost error is
+    /// always serialized. Unlimited by default, except in the fixed layout,
+    /// which stops at 256.
+    ///
+    /// [fixed layout]: SerializeConfig::fixed_layout
+    /// [`deserialize::Error::truncated`]: crate::deserialize::Error::truncated
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Whether to emit a `type_name` field for errors whose type is known,
+    /// defaults to `true`
+    pub fn include_type_names(mut self, include: bool) -> Self {
+        self.include_type_names = include;
+        self
+    }
+
+    /// Select the overall shape of the output, defaults to [`ChainLayout::Nested`]
+    pub fn layout(mut self, layout: ChainLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Whether to always use the fixed layout, defaults to `false`
+    ///
+    /// In the fixed layout every error is written with exactly the fields
+    /// `type_name`, `msg`, `backtrace`, `errors`, `related`, and `source`, in
+    /// that order, whether it is serialized through a concrete type or a trait
+    /// object. `errors` is empty unless the error is a `MultiError`, and
+    /// `related` unless it is a `Wrapped` with related errors. Formats which
+    /// aren't human readable always use it; this setting extends it to human
+    /// readable ones such as JSON. Options which add or remove fields, such as
+    /// [`layout`] and [`include_debug`], are ignored by it.
+    ///
+    /// Since every error is nested in the one before it, chains deeper than
+    /// 256 errors, counting those aggregated by a `MultiError`, are truncated
+    /// in this layout as if [`max_depth`] were set to 256.
+    ///
+    /// [`layout`]: SerializeConfig::layout
+    /// [`include_debug`]: SerializeConfig::include_debug
+    /// [`max_depth`]: SerializeConfig::max_depth
+    pub fn fixed_layout(mut self, fixed: bool) -> Self {
+        self.fixed_layout = fixed;
+        self
+    }
+
+    /// Scrub every string written during serialization with `redactor`
+    ///
+    /// The errors themselves are left untouched, only their serialized form
+    /// is affected.
+    pub fn redactor<R>(mut self, redactor: R) -> Self
+    where
+        R: Redactor + 'static,
+    {
+        self.redactor = Some(Arc::new(redactor));
+        self
+    }
+
+    /// Cap each error message at `len` bytes
+    ///
+    /// Longer messages are cut at the nearest character boundary and suffixed
+    /// with the number of bytes removed, e.g. `"…[+10432 bytes]"`. Unlimited by
+    /// default.
+    pub fn max_msg_len(mut self, len: usize) -> Self {
+        self.max_msg_len = Some(len);
+        self
+    }
+
+    /// Cap each backtrace at `len` bytes, in the same way as
+    /// [`SerializeConfig::max_msg_len`]
+    ///
+    /// Only applies to [`BacktraceMode::String`]. Unlimited by default.
+    pub fn max_backtrace_len(mut self, len: usize) -> Self {
+        self.max_backtrace_len = Some(len);
+        self
+    }
+
+    /// Whether to emit a `debug` field with each error's `Debug` output,
+    /// defaults to `false`
+    pub fn include_debug(mut self, include: bool) -> Self {
+        self.include_debug = include;
+        self
+    }
+
+    /// Whether to emit a `display_alt` field holding the alternate `Display`
+    /// output (`{:#}`) of each error, defaults to `false`
+    ///
+    /// The field is only written for errors whose alternate output differs
+    /// from their normal one.
+    pub fn include_alternate(mut self, include: bool) -> Self {
+        self.include_alternate = include;
+        self
+    }
+
+    /// Whether to emit a `v` field holding the format version on the
+    /// outermost error, defaults to `true`
+    ///
+    /// The version lets [`deserialize::Error`] keep reading payloads written
+    /// by older versions of this crate after the format changes. Turn it off
+    /// if the output must stay byte for byte identical to earlier releases.
+    ///
+    /// [`deserialize::Error`]: crate::deserialize::Error
+    pub fn include_version(mut self, include: bool) -> Self {
+        self.include_version = include;
+        self
+    }
+
+    /// Select what happens to a backtrace which also belongs to an error
+    /// further down the chain, defaults to [`DuplicateBacktraces::Keep`]
+    pub fn duplicate_backtraces(mut self, duplicates: DuplicateBacktraces) -> Self {
+        self.duplicate_backtraces = duplicates;
+        self
+    }
+
+    /// Replace `prefix` with `marker` in every path of every backtrace
+    ///
+    /// Applies to backtraces serialized as strings and as frames alike, and to
+    /// the file of an error's `location`, before the redactor runs. Prefixes
+    /// only match whole path components, so `/home/jane` leaves `/home/janet`
+    /// alone, and are tried longest first. A trailing `/` on `prefix` is
+    /// ignored so that the marker is followed by one, and adding a prefix
+    /// again replaces its marker.
+    ///
+    /// ```rust
+    /// use errtools::SerializeConfig;
+    ///
+    /// let config = SerializeConfig::new().backtrace_prefix("/home/jane/src/app", "<app>");
+    /// ```
+    pub fn backtrace_prefix(
+        mut self,
+        prefix: impl Into<String>,
+        marker: impl Into<String>,
+    ) -> Self {
+        let mut prefix = prefix.into();
+        while prefix.len() > 1 && prefix.ends_with('/') {
+            prefix.pop();
+        }
+        if !prefix.is_empty() {
+            self.backtrace_prefixes
+                .retain(|(existing, _)| *existing != prefix);
+            self.backtrace_prefixes.push((prefix, marker.into()));
+            self.backtrace_prefixes
+                .sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
+        }
+        self
+    }
+
+    /// Replace the paths of the crate in `crate_dir` and of the cargo registry
+    /// in backtraces with `<crate>` and `<registry>`
+    ///
+    /// Pass `option_env!("CARGO_MANIFEST_DIR")`, which cargo sets while
+    /// compiling the calling crate, so that the directory is known even when
+    /// the binary runs elsewhere. The registry is the `registry/src` directory
+    /// of `CARGO_HOME`, or of `~/.cargo` if that isn't set, and is already
+    /// replaced by [`SerializeConfig::new`].
+    ///
+    /// ```rust
+    /// use errtools::SerializeConfig;
+    ///
+    /// let config = SerializeConfig::new()
+    ///     .default_backtrace_prefixes(option_env!("CARGO_MANIFEST_DIR"));
+    /// ```
+    pub fn default_backtrace_prefixes(self, crate_dir: Option<&str>) -> Self {
+        let mut config = self;
+        if let Some(crate_dir) = crate_dir {
+            config = config.backtrace_prefix(crate_dir, "<crate>");
+        }
+        if let Some(registry) = cargo_registry_dir() {
+            config = config.backtrace_prefix(registry.to_string_lossy(), "<registry>");
+        }
+        config
+    }
+
+    /// Apply the configured `backtrace_prefixes` to a rendered backtrace
+    ///
+    /// Every position is replaced at most once, so a marker is never matched
+    /// by a shorter prefix afterwards.
+    pub(crate) fn strip_backtrace_prefixes<'a>(&self, backtrace: &'a str) -> Cow<'a, str> {
+        let mut stripped = String::new();
+        let mut copied = 0;
+        let mut pos = 0;
+        while let Some(next) = backtrace[pos..].chars().next() {
+            let starts_component = backtrace[..pos]
+                .chars()
+                .next_back()
+                .map_or(true, |prev| !is_path_char(prev));
+            let matched = self
+                .backtrace_prefixes
+                .iter()
+                .filter(|_| starts_component)
+                .find(|(prefix, _)| {
+                    backtrace[pos..].starts_with(prefix.as_str())
+                        && backtrace[pos + prefix.len()..]
+                            .chars()
+                            .next()
+                            .map_or(true, |c| c == '/' || c == '\\' || !is_path_char(c))
+                });
+
+            match matched {
+                Some((prefix, marker)) => {
+                    stripped.push_str(&backtrace[copied..pos]);
+                    stripped.push_str(marker);
+                    pos += prefix.len();
+                    copied = pos;
+                }
+                None => pos += next.len_utf8(),
+            }
+        }
+
+        if copied == 0 {
+            return Cow::Borrowed(backtrace);
+        }
+        stripped.push_str(&backtrace[copied..]);
+        Cow::Owned(stripped)
+    }
+}
+
+/// The `registry/src` directory of `CARGO_HOME`, or of `~/.cargo`
+fn cargo_registry_dir() -> Option<PathBuf> {
+    let cargo_home = std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cargo")))?;
+    Some(cargo_home.join("registry").join("src"))
+}
+
+/// Whether `c` can be part of a path component, as opposed to the text
+/// around a path in a backtrace
+fn is_path_char(c: char) -> bool {
+    c.is_alphanumeric() || "_-.~@+".contains(c) || c == '/' || c == '\\'
+}
+
+impl fmt::Debug for SerializeConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SerializeConfig")
+            .field("backtraces", &self.backtraces)
+            .field("max_depth", &self.max_depth)
+            .field("include_type_names", &self.include_type_names)
+            .field("layout", &self.layout)
+            .field("redactor", &self.redactor.as_ref().map(|_| ".."))
+            .field("max_msg_len", &self.max_msg_len)
+            .field("max_backtrace_len", &self.max_backtrace_len)
+            .field("include_debug", &self.include_debug)
+            .field("include_alternate", &self.include_alternate)
+            .field("fixed_layout", &self.fixed_layout)
+            .field("include_version", &self.include_version)
+            .field("duplicate_backtraces", &self.duplicate_backtraces)
+            .field("backtrace_prefixes", &self.backtrace_prefixes)
+            .finish()
+    }
+}
+
+impl Default for SerializeConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACKTRACE: &str = "\
+   0: app::db::connect
+             at /home/jane/src/secret-project/src/db.rs:42:17
+   1: tokio::runtime::task::harness::poll
+             at /home/jane/.cargo/registry/src/github.com-1ecc6299db9ec823/tokio-1.20.0/src/runtime/task/harness.rs:167:9
+   2: std::rt::lang_start
+             at /rustc/e092d0b6b43f2de967af0887873151bb1c0b18d3/library/std/src/rt.rs:145:17
+";
+
+    #[test]
+    fn strip_prefixes_keeps_line_numbers() {
+        let config = SerializeConfig::new()
+            .backtrace_prefix("/home/jane/src/secret-project/", "<crate>")
+            .backtrace_prefix("/home/jane", "~")
+            .backtrace_prefix("/home/jane/.cargo/registry/src", "<registry>");
+        let stripped = config.strip_backtrace_prefixes(BACKTRACE);
+
+        assert!(!stripped.contains("/home/jane"));
+        assert!(stripped.contains("at <crate>/src/db.rs:42:17"));
+        assert!(stripped.contains(
+            "at <registry>/github.com-1ecc6299db9ec823/tokio-1.20.0/src/runtime/task/harness.rs:167:9"
+        ));
+        assert!(stripped.contains("/rustc/e092d0b6b43f2de967af0887873151bb1c0b18d3/library"));
+    }
+
+    #[test]
+    fn strip_prefixes_on_component_boundaries() {
+        let config = SerializeConfig::new()
+            .backtrace_prefix("/home/jane", "<home>")
+            .backtrace_prefix("/src", "<src>");
+        let strip = |backtrace| config.strip_backtrace_prefixes(backtrace).into_owned();
+
+        assert_eq!(strip("at /home/jane/app/db.rs:4"), "at <home>/app/db.rs:4");
+        assert_eq!(strip("at /home/jane:4"), "at <home>:4");
+        assert_eq!(strip("at /home/janet/db.rs:4"), "at /home/janet/db.rs:4");
+        assert_eq!(strip("at /srv/home/jane/db.rs"), "at /srv/home/jane/db.rs");
+        assert_eq!(
+            strip("at /src/db.rs /home/jane/src"),
+            "at <src>/db.rs <home>/src"
+        );
+    }
+
+    #[test]
+    fn registry_prefix_by_default() {
+        let registry = cargo_registry_dir()
+            .unwrap()
+            .join("serde-1.0.140/src/de.rs");
+        let frame = format!("at {}:12:5", registry.display());
+        assert_eq!(
+            SerializeConfig::new().strip_backtrace_prefixes(&frame),
+            "at <registry>/serde-1.0.140/src/de.rs:12:5"
+        );
+
+        let config = SerializeConfig::new().default_backtrace_prefixes(None);
+        assert_eq!(config.backtrace_prefixes.len(), 1);
+    }
+
+    #[test]
+    fn no_prefixes_borrows() {
+        let config = SerializeConfig {
+            backtrace_prefixes: Vec::new(),
+            ..SerializeConfig::new()
+        };
+        assert!(matches!(
+            config.strip_backtrace_prefixes(BACKTRACE),
+            Cow::Borrowed(_)
+        ));
+    }
+}
@@ -0,0 +1,2800 @@
+//! Deserialization of error chains produced by [`ErrTools::serialize`]
+//!
+//! The serialized form of an error chain can be read back into an
+//! [`Error`], which implements `std::error::Error` and reproduces the messages
+//! of the original chain through `Display` and `source()`.
+//!
+//! [`ErrorRef`] reads the same payloads while borrowing their strings from
+//! the input instead of copying them.
+//!
+//! Chains written with either [`ChainLayout`] are accepted, and the flat
+//! layout is linked back into the same chain the nested one produces.
+//!
+//! An [`Error`] serializes back the same way live chains do, with every field
+//! this crate writes that it was read with, so payloads can be forwarded
+//! without losing type names, codes, metadata, backtraces, or locations.
+//! Fields only other producers write are skipped when reading and so aren't
+//! written back. Deep chains continue in the flat layout past the nesting
+//! limit, and formats that aren't human readable get the fixed layout, which
+//! only has room for type names, messages, backtraces, and the nested chains.
+//!
+//! Payloads carry a `v` field holding the version of the format they were
+//! written in. Payloads without one are treated as version 1. Those with a
+//! version newer than this crate knows about are read like any other payload,
+//! skipping the fields it doesn't know, unless
+//! [`DeserializeConfig::strict`] is enabled, which rejects them.
+//!
+//! [`ErrTools::serialize`]: crate::ErrTools::serialize
+//! [`ChainLayout`]: crate::ChainLayout
+use crate::backtrace::{fmt_frames, Frame};
+use crate::ser::{
+    parse_truncated_type_name, serialize_chain, BacktraceField, Fields, Link, Text, Truncated,
+    DEFAULT_CONFIG, FIELD_NAMES, FIXED_NESTING_LIMIT, FORMAT_VERSION,
+};
+use crate::{registry, MetadataValue, SerializeConfig};
+use serde::de::{
+    self, Deserialize, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor,
+};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// An error chain reconstructed from its serialized form
+///
+/// Every element of the chain is an `Error` of its own, so the data read back
+/// for each one can be reached through [`Error::chain`]. Chains can also be
+/// built by hand, for instance as test fixtures:
+///
+/// ```rust
+/// use errtools::deserialize::Error;
+///
+/// let err = Error::new("unable to load config")
+///     .with_type_name("app::LoadError")
+///     .with_source(Error::new("file not found"));
+///
+/// let messages = err.chain().map(Error::msg).collect::<Vec<_>>();
+/// assert_eq!(messages, ["unable to load config", "file not found"]);
+/// ```
+///
+/// Two errors are equal, and hash the same, when every element of their
+/// chains has the same type name, message, location, aggregated errors, and
+/// related errors.
+/// Backtraces and where the errors were received are left out since they
+/// differ between occurrences of the same error.
+///
+/// Errors whose type implements [`ReconstructibleError`] and was registered
+/// with [`register_reconstructible`] are rebuilt as that type as well, and can
+/// be found again with [`ErrTools::downcast_refchain`].
+///
+/// [`register_reconstructible`]: crate::registry::register_reconstructible
+/// [`ErrTools::downcast_refchain`]: crate::ErrTools::downcast_refchain
+#[derive(Debug, Clone)]
+pub struct Error {
+    type_name: Option<String>,
+    msg: String,
+    location: Option<Location>,
+    details: Details<String>,
+    errors: Vec<Error>,
+    related: Vec<Error>,
+    backtrace: Option<Backtrace>,
+    received: Option<Received>,
+    reconstructed: Option<Arc<dyn std::error::Error + Send + Sync + 'static>>,
+    source: Option<Box<Error>>,
+    /// Whether the payload left the `source` of this error out rather than
+    /// writing it as null, so that serializing it again does the same
+    source_omitted: bool,
+}
+
+impl Error {
+    /// Create an error with the message `msg` and no source
+    pub fn new(msg: impl Into<String>) -> Self {
+        Self {
+            type_name: None,
+            msg: msg.into(),
+            location: None,
+            details: Details::default(),
+            errors: Vec::new(),
+            related: Vec::new(),
+            backtrace: None,
+            received: None,
+            reconstructed: None,
+            source: None,
+            source_omitted: false,
+        }
+    }
+
+    /// Start building an error chain field by field
+    ///
+    /// ```rust
+    /// use errtools::deserialize::Error;
+    ///
+    /// let err = Error::builder()
+    ///     .type_name("app::SyncError")
+    ///     .msg("sync of account 42 failed")
+    ///     .causes(["request to upstream failed", "connection reset by peer"])
+    ///     .build();
+    ///
+    /// assert_eq!(err.chain_len(), 3);
+    /// assert_eq!(err.root().msg(), "connection reset by peer");
+    /// ```
+    pub fn builder() -> ErrorBuilder {
+        ErrorBuilder::default()
+    }
+
+    /// Set the type name of this error
+    pub fn with_type_name(mut self, type_name: impl Into<String>) -> Self {
+        self.type_name = Some(type_name.into());
+        self
+    }
+
+    /// Set the source of this error
+    pub fn with_source(mut self, source: Error) -> Self {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    /// Wrap this chain in a new outermost error with the message `msg`
+    ///
+    /// For adding local context to a chain received from another service
+    /// before logging or forwarding it. The new error has no type name, and
+    /// serializes like any other element of the chain.
+    ///
+    /// ```rust
+    /// use errtools::deserialize::Error;
+    ///
+    /// let err: Error = serde_json::from_str(r#"{"msg": "order not found"}"#).unwrap();
+    /// let err = err.push_context("while handling GET /orders/42");
+    ///
+    /// let messages = err.chain().map(Error::msg).collect::<Vec<_>>();
+    /// assert_eq!(messages, ["while handling GET /orders/42", "order not found"]);
+    /// ```
+    pub fn push_context(self, msg: impl Into<String>) -> Self {
+        Self::new(msg).with_source(self)
+    }
+
+    /// Set the errors aggregated by this error
+    #[cfg(feature = "json")]
+    pub(crate) fn with_errors(mut self, errors: Vec<Error>) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// Add `meta` before the metadata this error already has, whose entries
+    /// with the same keys are then shadowed
+    #[cfg(feature = "json")]
+    pub(crate) fn with_leading_meta(mut self, mut meta: Vec<(String, MetadataValue)>) -> Self {
+        for (key, value) in self.details.meta.take().into_iter().flatten() {
+            if !meta.iter().any(|(seen, _)| *seen == key) {
+                meta.push((key, value));
+            }
+        }
+        self.details.meta = Some(meta);
+        self
+    }
+
+    /// Read an error chain out of an already parsed JSON value
+    ///
+    /// Useful when the error is embedded in a larger document, which can then
+    /// be parsed once instead of turning the error back into a string first.
+    #[cfg(feature = "json")]
+    pub fn from_value(value: serde_json::Value) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(value)
+    }
+
+    /// Read an error chain out of a borrowed JSON value, like
+    /// [`Error::from_value`]
+    #[cfg(feature = "json")]
+    pub fn from_value_ref(value: &serde_json::Value) -> Result<Self, serde_json::Error> {
+        Self::deserialize(value)
+    }
+
+    /// Read an error chain out of an RFC 7807 `application/problem+json`
+    /// document, as described by [`ProblemDetails::into_error`]
+    ///
+    /// [`ProblemDetails::into_error`]: crate::problem::ProblemDetails::into_error
+    #[cfg(feature = "json")]
+    pub fn from_problem_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str::<crate::problem::ProblemDetails>(json)
+            .map(|problem| problem.into_error())
+    }
+
+    /// Read as much of an error chain out of a JSON document as possible
+    ///
+    /// Unlike the `Deserialize` impl, a `source` that can't be read doesn't
+    /// fail the whole chain. It is replaced by an error with a message like
+    /// `<unparseable source: unexpected end of input>` instead, and the errors
+    /// above it are kept. This includes documents that were cut off part way
+    /// through, as long as they were cut off inside a `source`. Errors in the
+    /// outermost error itself are still returned.
+    #[cfg(feature = "json")]
+    pub fn from_json_lossy(json: &str) -> Result<Self, serde_json::Error> {
+        crate::lossy::parse(json)
+    }
+
+    /// The message of this error
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+
+    /// The type name of this error, if it was recorded
+    pub fn type_name(&self) -> Option<&str> {
+        self.type_name.as_deref()
+    }
+
+    /// The source location this error was created at, if it was recorded
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    /// The code of this error, if it was recorded
+    pub fn code(&self) -> Option<&str> {
+        self.details.code.as_deref()
+    }
+
+    /// Iterate over the `meta` fields of this error, in the order they were
+    /// serialized
+    pub fn metadata(&self) -> impl Iterator<Item = (&str, &MetadataValue)> {
+        self.details
+            .meta
+            .iter()
+            .flatten()
+            .map(|(key, value)| (&key[..], value))
+    }
+
+    /// The type URI of the RFC 7807 problem document this error was read from,
+    /// recorded as its `type` metadata by [`ProblemDetails::into_error`]
+    ///
+    /// [`ProblemDetails::into_error`]: crate::problem::ProblemDetails::into_error
+    pub fn problem_type(&self) -> Option<&str> {
+        match self.meta("type")? {
+            MetadataValue::Str(type_uri) => Some(type_uri),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status of the RFC 7807 problem document this error was read
+    /// from, recorded as its `status` metadata by
+    /// [`ProblemDetails::into_error`]
+    ///
+    /// [`ProblemDetails::into_error`]: crate::problem::ProblemDetails::into_error
+    pub fn http_status(&self) -> Option<u16> {
+        match self.meta("status")? {
+            MetadataValue::Int(status) => u16::try_from(*status).ok(),
+            _ => None,
+        }
+    }
+
+    /// The value of the `meta` field `key` of this error
+    fn meta(&self, key: &str) -> Option<&MetadataValue> {
+        self.metadata()
+            .find(|(seen, _)| *seen == key)
+            .map(|(_, value)| value)
+    }
+
+    /// The path of the file this error was about, if it was recorded
+    pub fn path(&self) -> Option<&str> {
+        self.details.path.as_deref()
+    }
+
+    /// The `io::ErrorKind` of this error, as its `Debug` output, if it was an
+    /// `io::Error`
+    pub fn io_kind(&self) -> Option<&str> {
+        self.details.io_kind.as_deref()
+    }
+
+    /// The OS error code of this error, if it was an `io::Error` with one
+    pub fn os_code(&self) -> Option<i32> {
+        self.details.os_code
+    }
+
+    /// The span trace of this error, if it had one
+    pub fn span_trace(&self) -> Option<&str> {
+        self.details.span_trace.as_deref()
+    }
+
+    /// The number of errors this error stands for, if it is the marker written
+    /// in place of those cut off by `max_depth`
+    pub fn truncated(&self) -> Option<usize> {
+        self.details.truncated
+    }
+
+    /// The backtrace of this error as it was serialized, if it had one
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_ref()
+    }
+
+    /// The backtrace of this error, if it was serialized as a string
+    pub fn backtrace_str(&self) -> Option<&str> {
+        match &self.backtrace {
+            Some(Backtrace::Text(backtrace)) => Some(backtrace),
+            _ => None,
+        }
+    }
+
+    /// Record that this error was received from `origin`, at the current time
+    ///
+    /// Meant to be called on a chain read from another service, to tell it
+    /// apart from errors that occurred locally. The origin and time are shown
+    /// after the message by the alternate `Display` format, as in
+    /// `(received from billing-svc at 2024-05-01T12:00:00Z)`, and are written
+    /// as the `received_from` and `received_at` fields when the error is
+    /// serialized again. Marking an error again replaces both.
+    ///
+    /// ```rust
+    /// use errtools::deserialize::Error;
+    ///
+    /// let mut err: Error = serde_json::from_str(r#"{"msg": "card declined"}"#).unwrap();
+    /// err.mark_received("billing-svc");
+    ///
+    /// assert_eq!(err.received_from(), Some("billing-svc"));
+    /// assert!(format!("{:#}", err).starts_with("card declined (received from billing-svc at "));
+    /// assert_eq!(err.to_string(), "card declined");
+    /// ```
+    pub fn mark_received(&mut self, origin: impl Into<String>) {
+        self.received = Some(Received {
+            origin: origin.into(),
+            at: rfc3339(SystemTime::now()),
+        });
+    }
+
+    /// The origin this error was marked as received from, if any
+    pub fn received_from(&self) -> Option<&str> {
+        self.received.as_ref().map(|received| &received.origin[..])
+    }
+
+    /// When this error was marked as received, as an RFC 3339 timestamp in
+    /// UTC
+    pub fn received_at(&self) -> Option<&str> {
+        self.received.as_ref().map(|received| &received.at[..])
+    }
+
+    /// Iterate over the frames of the backtrace of this error, if it was
+    /// serialized as a list of frames
+    ///
+    /// ```rust
+    /// use errtools::deserialize;
+    ///
+    /// let json = r#"{
+    ///     "msg": "boom",
+    ///     "backtrace": [{"symbol": "app::main", "file": "src/main.rs", "line": 4}]
+    /// }"#;
+    /// let err: deserialize::Error = serde_json::from_str(json).unwrap();
+    ///
+    /// let symbols = err.frames().unwrap().map(|frame| &frame.symbol[..]).collect::<Vec<_>>();
+    /// assert_eq!(symbols, ["app::main"]);
+    /// ```
+    pub fn frames(&self) -> Option<impl Iterator<Item = &Frame>> {
+        match &self.backtrace {
+            Some(Backtrace::Frames(frames)) => Some(frames.iter()),
+            _ => None,
+        }
+    }
+
+    /// Iterate over this error and its sources, outermost first
+    pub fn chain(&self) -> impl Iterator<Item = &Error> {
+        std::iter::successors(Some(self), |error| error.source.as_deref())
+    }
+
+    /// Iterate over the sources of this error, outermost first, without the
+    /// error itself
+    pub fn sources(&self) -> impl Iterator<Item = &Error> {
+        self.chain().skip(1)
+    }
+
+    /// The number of errors in the chain, including this one
+    pub fn chain_len(&self) -> usize {
+        self.chain().count()
+    }
+
+    /// The error `depth` levels down the chain, where this error is at depth
+    /// 0, or `None` if the chain isn't that long
+    ///
+    /// ```rust
+    /// use errtools::deserialize::Error;
+    ///
+    /// let err = Error::builder()
+    ///     .msg("sync failed")
+    ///     .causes(["request failed", "connection reset by peer"])
+    ///     .build();
+    ///
+    /// assert_eq!(err.get(2).map(Error::msg), Some("connection reset by peer"));
+    /// assert!(err.get(3).is_none());
+    /// ```
+    pub fn get(&self, depth: usize) -> Option<&Error> {
+        self.chain().nth(depth)
+    }
+
+    /// The innermost error of the chain, which is this error if it has no
+    /// source
+    pub fn root(&self) -> &Error {
+        self.chain().last().unwrap_or(self)
+    }
+
+    /// The errors aggregated by the first error in the chain which aggregates
+    /// any, such as a [`MultiError`], each with its own chain
+    ///
+    /// [`MultiError`]: crate::MultiError
+    pub fn errors(&self) -> &[Error] {
+        self.chain()
+            .map(|error| &*error.errors)
+            .find(|errors| !errors.is_empty())
+            .unwrap_or_default()
+    }
+
+    /// The secondary errors attached to this error, such as with
+    /// [`Wrapped::push_related`], each with its own chain
+    ///
+    /// [`Wrapped::push_related`]: crate::Wrapped::push_related
+    pub fn related(&self) -> &[Error] {
+        &self.related
+    }
+
+    /// The messages of the chain, outermost first
+    pub fn messages(&self) -> Vec<&str> {
+        self.chain().map(Error::msg).collect()
+    }
+
+    /// Take the messages out of the chain, outermost first
+    pub fn into_messages(self) -> Vec<String> {
+        self.into_parts().into_iter().map(|node| node.msg).collect()
+    }
+
+    /// Take the chain apart into its elements, outermost first
+    ///
+    /// Every other field of the elements, such as their locations and the
+    /// errors they aggregate, is dropped.
+    ///
+    /// ```rust
+    /// use errtools::deserialize::Error;
+    ///
+    /// let err = Error::new("unable to load config")
+    ///     .with_type_name("app::LoadError")
+    ///     .with_source(Error::new("file not found"));
+    ///
+    /// let parts = err.into_parts();
+    /// assert_eq!(parts[0].type_name.as_deref(), Some("app::LoadError"));
+    /// assert_eq!(parts[1].msg, "file not found");
+    /// ```
+    pub fn into_parts(mut self) -> Vec<ErrorNode> {
+        let mut parts = vec![];
+        let mut cur_error = Some(&mut self);
+        while let Some(error) = cur_error {
+            parts.push(ErrorNode {
+                type_name: error.type_name.take(),
+                msg: std::mem::take(&mut error.msg),
+                backtrace: error.backtrace.take(),
+            });
+            cur_error = error.source.as_deref_mut();
+        }
+        parts
+    }
+}
+
+/// A single element of a chain taken apart by [`Error::into_parts`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorNode {
+    /// The type name of the error, if it was recorded
+    pub type_name: Option<String>,
+    /// The message of the error
+    pub msg: String,
+    /// The backtrace of the error, if it had one
+    pub backtrace: Option<Backtrace>,
+}
+
+/// A builder for [`Error`], created by [`Error::builder`]
+///
+/// The message defaults to an empty string and every other field to being
+/// absent.
+#[derive(Debug, Default)]
+pub struct ErrorBuilder {
+    type_name: Option<String>,
+    msg: String,
+    backtrace: Option<String>,
+    source: Option<Box<Error>>,
+}
+
+impl ErrorBuilder {
+    /// Set the type name of the outermost error
+    pub fn type_name(mut self, type_name: impl Into<String>) -> Self {
+        self.type_name = Some(type_name.into());
+        self
+    }
+
+    /// Set the message of the outermost error
+    pub fn msg(mut self, msg: impl Into<String>) -> Self {
+        self.msg = msg.into();
+        self
+    }
+
+    /// Set the backtrace of the outermost error
+    pub fn backtrace(mut self, backtrace: impl Into<String>) -> Self {
+        self.backtrace = Some(backtrace.into());
+        self
+    }
+
+    /// Set the source of the outermost error, replacing any causes added so
+    /// far
+    pub fn source(mut self, source: impl Into<Error>) -> Self {
+        self.source = Some(Box::new(source.into()));
+        self
+    }
+
+    /// Append an error with the message `msg` to the innermost end of the
+    /// chain
+    pub fn push_cause(mut self, msg: impl Into<String>) -> Self {
+        let mut slot = &mut self.source;
+        while let Some(source) = slot {
+            slot = &mut source.source;
+        }
+        *slot = Some(Box::new(Error::new(msg)));
+        self
+    }
+
+    /// Append an error for each message in `msgs`, in order, like
+    /// [`ErrorBuilder::push_cause`]
+    pub fn causes<I>(self, msgs: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        msgs.into_iter().fold(self, ErrorBuilder::push_cause)
+    }
+
+    /// Finish the chain
+    pub fn build(self) -> Error {
+        Error {
+            type_name: self.type_name,
+            msg: self.msg,
+            location: None,
+            details: Details::default(),
+            errors: Vec::new(),
+            related: Vec::new(),
+            backtrace: self.backtrace.map(Backtrace::Text),
+            received: None,
+            reconstructed: None,
+            source: self.source,
+            source_omitted: false,
+        }
+    }
+}
+
+impl From<ErrorBuilder> for Error {
+    fn from(builder: ErrorBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// The source location an error was created at, read from its `location`
+/// field
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Location {
+    file: String,
+    line: u32,
+    column: u32,
+}
+
+impl Location {
+    /// The source file
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The line number within the file
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The column within the line
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// The type name recorded for `error`, if it was read back by the deserializer
+/// and its type name was serialized
+///
+/// Equivalent to downcasting `error` to [`Error`] and calling
+/// [`Error::type_name`], for use while walking a chain through `source()`.
+///
+/// ```rust
+/// # fn main() -> Result<(), serde_json::Error> {
+/// use errtools::deserialize;
+/// use std::error::Error;
+///
+/// let json = r#"{
+///     "type_name": "app::LoadError",
+///     "msg": "unable to load config",
+///     "source": {"type_name": "std::io::Error", "msg": "not found", "source": null}
+/// }"#;
+/// let err: deserialize::Error = serde_json::from_str(json)?;
+///
+/// let source = err.source().unwrap();
+/// assert_eq!(deserialize::type_name_of(source), Some("std::io::Error"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn type_name_of<'a>(error: &'a (dyn std::error::Error + 'static)) -> Option<&'a str> {
+    error.downcast_ref::<Error>().and_then(Error::type_name)
+}
+
+/// Deserialize an error chain straight into a boxed error
+///
+/// Meant for `#[serde(deserialize_with = "errtools::deserialize::boxed")]` on
+/// fields which are only ever read. [`Error`] is `Send + Sync`, so outside of
+/// serde it converts into either kind of boxed error with `into()`.
+///
+/// ```rust
+/// use serde::Deserialize;
+/// use std::error::Error;
+///
+/// #[derive(Deserialize)]
+/// struct JobResult {
+///     ok: bool,
+///     #[serde(deserialize_with = "errtools::deserialize::boxed")]
+///     error: Box<dyn Error + Send + Sync>,
+/// }
+/// ```
+pub fn boxed<'de, D>(
+    deserializer: D,
+) -> Result<Box<dyn std::error::Error + Send + Sync + 'static>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Error::deserialize(deserializer).map(Into::into)
+}
+
+/// An error type which can be rebuilt from its serialized form
+///
+/// Once registered with [`register_reconstructible`], every error the
+/// deserializer reads whose `type_name` is that of the type is passed to
+/// [`ReconstructibleError::from_parts`], and the value it returns is kept
+/// alongside the generic [`Error`] for [`ErrTools::downcast_refchain`] to
+/// find.
+///
+/// ```rust
+/// use errtools::deserialize::{self, ReconstructibleError};
+/// use errtools::ErrTools;
+/// use std::fmt;
+///
+/// #[derive(Debug)]
+/// struct NotFound(String);
+///
+/// impl fmt::Display for NotFound {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         write!(f, "{} not found", self.0)
+///     }
+/// }
+///
+/// impl std::error::Error for NotFound {}
+///
+/// impl ReconstructibleError for NotFound {
+///     fn from_parts(msg: &str) -> Option<Self> {
+///         msg.strip_suffix(" not found").map(|name| NotFound(name.to_string()))
+///     }
+/// }
+///
+/// errtools::registry::register_reconstructible::<NotFound>();
+///
+/// let json = serde_json::to_string(&NotFound("user 7".to_string()).serialize()).unwrap();
+/// let err: deserialize::Error = serde_json::from_str(&json).unwrap();
+/// assert_eq!(err.downcast_refchain::<NotFound>().unwrap().0, "user 7");
+/// ```
+///
+/// [`register_reconstructible`]: crate::registry::register_reconstructible
+/// [`ErrTools::downcast_refchain`]: crate::ErrTools::downcast_refchain
+pub trait ReconstructibleError: std::error::Error + Send + Sync + Sized + 'static {
+    /// Rebuild the error from its message, or return `None` if the message
+    /// doesn't describe a value of this type
+    fn from_parts(msg: &str) -> Option<Self>;
+}
+
+/// The value rebuilt for `error` if it was read back by the deserializer and
+/// its type is a registered [`ReconstructibleError`]
+pub(crate) fn reconstructed_of<'a>(
+    error: &'a (dyn std::error::Error + 'static),
+) -> Option<&'a (dyn std::error::Error + 'static)> {
+    error
+        .downcast_ref::<Error>()
+        .and_then(|error| error.reconstructed.as_deref())
+        .map(|error| error as &(dyn std::error::Error + 'static))
+}
+
+/// The backtrace recorded for `error` if it was read back by the deserializer,
+/// so that serializing it again reproduces the backtrace
+pub(crate) fn recorded_backtrace<'a>(
+    error: &'a (dyn std::error::Error + 'static),
+) -> Option<&'a Backtrace> {
+    error
+        .downcast_ref::<Error>()
+        .and_then(|error| error.backtrace.as_ref())
+}
+
+/// Where a chain read from another service was received, recorded by
+/// [`Error::mark_received`]
+#[derive(Debug, Clone)]
+struct Received {
+    origin: String,
+    /// An RFC 3339 timestamp, kept as text so that it is written back exactly
+    /// as it was read
+    at: String,
+}
+
+/// The fields of an error which are only kept so that it serializes again as
+/// it was read
+#[derive(Debug, Clone, Default)]
+struct Details<S> {
+    code: Option<S>,
+    meta: Option<Vec<(S, MetadataValue)>>,
+    debug: Option<S>,
+    display_alt: Option<S>,
+    path: Option<S>,
+    io_kind: Option<S>,
+    os_code: Option<i32>,
+    span_trace: Option<S>,
+    /// How many errors were cut off, if this error is the marker written in
+    /// their place
+    truncated: Option<usize>,
+}
+
+impl Details<Cow<'_, str>> {
+    fn into_owned(self) -> Details<String> {
+        Details {
+            code: self.code.map(Cow::into_owned),
+            meta: self.meta.map(|meta| {
+                meta.into_iter()
+                    .map(|(key, value)| (key.into_owned(), value))
+                    .collect()
+            }),
+            debug: self.debug.map(Cow::into_owned),
+            display_alt: self.display_alt.map(Cow::into_owned),
+            path: self.path.map(Cow::into_owned),
+            io_kind: self.io_kind.map(Cow::into_owned),
+            os_code: self.os_code,
+            span_trace: self.span_trace.map(Cow::into_owned),
+            truncated: self.truncated,
+        }
+    }
+}
+
+/// Format `time` as an RFC 3339 timestamp in UTC with second precision
+fn rfc3339(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let (days, secs) = (secs / 86_400, secs % 86_400);
+
+    // The proleptic Gregorian date of `days` since 1970-01-01, computed over
+    // 400 year eras that start on March 1st
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + (month <= 2) as u64;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Write `msg`, followed by where it was received and `backtrace` in the
+/// alternate format
+fn fmt_with_backtrace(
+    msg: &str,
+    received: Option<&Received>,
+    backtrace: Option<&Backtrace>,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    f.write_str(msg)?;
+    if !f.alternate() {
+        return Ok(());
+    }
+
+    if let Some(received) = received {
+        write!(f, " (received from {} at {})", received.origin, received.at)?;
+    }
+    match backtrace {
+        Some(backtrace) => write!(f, "\n\nStack backtrace:\n{}", backtrace),
+        None => Ok(()),
+    }
+}
+
+impl Drop for Error {
+    fn drop(&mut self) {
+        // Unlink the chain so that each source is dropped by this loop, rather
+        // than by the destructor of the error holding it, which would recurse
+        // once per element of the chain
+        let mut source = self.source.take();
+        while let Some(mut error) = source {
+            source = error.source.take();
+        }
+    }
+}
+
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        self.chain_len() == other.chain_len()
+            && self.chain().zip(other.chain()).all(|(a, b)| {
+                a.type_name == b.type_name
+                    && a.msg == b.msg
+                    && a.location == b.location
+                    && a.errors == b.errors
+                    && a.related == b.related
+            })
+    }
+}
+
+impl Eq for Error {}
+
+impl Hash for Error {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for error in self.chain() {
+            error.type_name.hash(state);
+            error.msg.hash(state);
+            error.location.hash(state);
+            error.errors.hash(state);
+            error.related.hash(state);
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_with_backtrace(
+            &self.msg,
+            self.received.as_ref(),
+            self.backtrace.as_ref(),
+            f,
+        )
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn std::error::Error + 'static))
+    }
+}
+
+/// An error chain read without copying its strings out of the input
+///
+/// The borrowed counterpart of [`Error`], for reading payloads in bulk where
+/// allocating every message and type name would dominate. Strings are
+/// borrowed from the input whenever the format allows it, as
+/// `serde_json::from_str` and `bincode::deserialize` do unless a string needs
+/// unescaping, and copied otherwise. The same payloads are accepted as by
+/// [`Error`], which [`ErrorRef::to_owned`] converts the chain into.
+///
+/// ```rust
+/// use errtools::deserialize::{Error, ErrorRef};
+///
+/// let json = r#"{"msg": "sync failed", "source": {"msg": "timed out"}}"#;
+/// let err: ErrorRef<'_> = serde_json::from_str(json).unwrap();
+///
+/// let messages = err.chain().map(ErrorRef::msg).collect::<Vec<_>>();
+/// assert_eq!(messages, ["sync failed", "timed out"]);
+/// assert_eq!(err.to_owned(), serde_json::from_str::<Error>(json).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ErrorRef<'de>(Parts<'de, ErrorRef<'de>>);
+
+impl<'de> ErrorRef<'de> {
+    /// The message of this error
+    pub fn msg(&self) -> &str {
+        &self.0.msg
+    }
+
+    /// The type name of this error, if it was recorded
+    pub fn type_name(&self) -> Option<&str> {
+        self.0.type_name.as_deref()
+    }
+
+    /// The source location this error was created at, if it was recorded
+    pub fn location(&self) -> Option<&Location> {
+        self.0.location.as_ref()
+    }
+
+    /// The backtrace of this error, if it was serialized as a string
+    pub fn backtrace_str(&self) -> Option<&str> {
+        match &self.0.backtrace {
+            Some(RawBacktrace::Text(backtrace)) => Some(backtrace),
+            _ => None,
+        }
+    }
+
+    /// Iterate over this error and its sources, outermost first
+    pub fn chain(&self) -> impl Iterator<Item = &ErrorRef<'de>> {
+        std::iter::successors(Some(self), |error| error.0.source.as_deref())
+    }
+
+    /// Iterate over the sources of this error, outermost first, without the
+    /// error itself
+    pub fn sources(&self) -> impl Iterator<Item = &ErrorRef<'de>> {
+        self.chain().skip(1)
+    }
+
+    /// The number of errors in the chain, including this one
+    pub fn chain_len(&self) -> usize {
+        self.chain().count()
+    }
+
+    /// The innermost error of the chain, which is this error if it has no
+    /// source
+    pub fn root(&self) -> &ErrorRef<'de> {
+        self.chain().last().unwrap_or(self)
+    }
+
+    /// Copy the chain into an owned [`Error`]
+    pub fn to_owned(&self) -> Error {
+        let mut owned = None;
+        for error in self.chain().collect::<Vec<_>>().into_iter().rev() {
+            let parts = &error.0;
+            owned = Some(Error::from_parts(Parts {
+                type_name: parts.type_name.clone(),
+                msg: parts.msg.clone(),
+                location: parts.location.clone(),
+                details: parts.details.clone(),
+                received: parts.received.clone(),
+                errors: parts.errors.iter().map(ErrorRef::to_owned).collect(),
+                related: parts.related.iter().map(ErrorRef::to_owned).collect(),
+                backtrace: parts.backtrace.clone(),
+                source: owned.map(Box::new),
+                source_omitted: parts.source_omitted,
+            }));
+        }
+        owned.expect("chains have at least one error")
+    }
+}
+
+impl<'de> Node<'de> for ErrorRef<'de> {
+    fn from_parts(parts: Parts<'de, Self>) -> Self {
+        ErrorRef(parts)
+    }
+
+    fn has_source(&self) -> bool {
+        self.0.source.is_some()
+    }
+
+    fn link(&mut self, source: Option<Box<Self>>) {
+        self.0.source = source;
+        self.0.source_omitted = false;
+    }
+}
+
+impl Drop for ErrorRef<'_> {
+    fn drop(&mut self) {
+        // Unlinked like `Error`, so that dropping a long chain doesn't recurse
+        let mut source = self.0.source.take();
+        while let Some(mut error) = source {
+            source = error.0.source.take();
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorRef<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        DeserializeConfig::new().deserialize_error_ref(deserializer)
+    }
+}
+
+/// The error chains of a batch request, one for each item that failed
+///
+/// Deserializes from either a bare sequence of chains or an envelope object
+/// holding them under `errors`, whose other fields are ignored:
+///
+/// ```rust
+/// use errtools::deserialize::ErrorBatch;
+///
+/// let json = r#"{"errors": [{"msg": "item 3 is invalid"}, {"msg": "item 7 is invalid"}]}"#;
+/// let batch: ErrorBatch = serde_json::from_str(json).unwrap();
+///
+/// assert_eq!(batch.len(), 2);
+/// assert_eq!(
+///     batch.to_string(),
+///     "2 errors occurred:\n    0: item 3 is invalid\n    1: item 7 is invalid"
+/// );
+/// ```
+///
+/// Telling the two shapes apart needs a self-describing format such as JSON.
+///
+/// The batch is an error of its own without a source, so that it can be
+/// wrapped like any other error. `Display` writes how many errors it holds,
+/// followed by the messages of each chain on a numbered line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorBatch {
+    errors: Vec<Error>,
+}
+
+impl ErrorBatch {
+    /// The chains of the batch, in the order they were listed
+    pub fn iter(&self) -> std::slice::Iter<'_, Error> {
+        self.errors.iter()
+    }
+
+    /// The number of chains in the batch
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Whether the batch holds no chains
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Take the chains out of the batch
+    pub fn into_vec(self) -> Vec<Error> {
+        self.errors
+    }
+}
+
+impl<'a> IntoIterator for &'a ErrorBatch {
+    type Item = &'a Error;
+    type IntoIter = std::slice::Iter<'a, Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl From<Vec<Error>> for ErrorBatch {
+    fn from(errors: Vec<Error>) -> Self {
+        Self { errors }
+    }
+}
+
+impl fmt::Display for ErrorBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.errors.len() {
+            0 => return write!(f, "no errors occurred"),
+            1 => write!(f, "1 error occurred:")?,
+            n => write!(f, "{} errors occurred:", n)?,
+        }
+
+        for (ind, error) in self.errors.iter().enumerate() {
+            write!(f, "\n    {}: {}", ind, error.msg)?;
+            for error in error.sources() {
+                write!(f, ": {}", error.msg)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ErrorBatch {}
+
+impl<'de> Deserialize<'de> for ErrorBatch {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BatchVisitor;
+
+        impl<'de> Visitor<'de> for BatchVisitor {
+            type Value = ErrorBatch;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a sequence of errors or an object with `errors`")
+            }
+
+            fn visit_seq<V>(self, mut seq: V) -> Result<ErrorBatch, V::Error>
+            where
+                V: SeqAccess<'de>,
+            {
+                let mut errors = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(64));
+                while let Some(error) = seq.next_element()? {
+                    errors.push(error);
+                }
+                Ok(ErrorBatch { errors })
+            }
+
+            fn visit_map<V>(self, mut map: V) -> Result<ErrorBatch, V::Error>
+            where
+                V: MapAccess<'de>,
+            {
+                let mut errors = None;
+                while let Some(key) = map.next_key::<String>()? {
+                    if key != "errors" {
+                        map.next_value::<IgnoredAny>()?;
+                    } else if errors.is_some() {
+                        return Err(de::Error::duplicate_field("errors"));
+                    } else {
+                        errors = Some(map.next_value()?);
+                    }
+                }
+
+                let errors = errors.ok_or_else(|| de::Error::missing_field("errors"))?;
+                Ok(ErrorBatch { errors })
+            }
+        }
+
+        deserializer.deserialize_any(BatchVisitor)
+    }
+}
+
+/// The backtrace of a deserialized error, in the form it was serialized in
+///
+/// Which form that is depends on the [`BacktraceMode`] the chain was
+/// serialized with. Either one is written back as it was read when the error
+/// is serialized again, and `Display` formats both like
+/// `std::backtrace::Backtrace`.
+///
+/// [`BacktraceMode`]: crate::BacktraceMode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Backtrace {
+    /// The backtrace's `Display` output
+    Text(String),
+    /// The backtrace's frames, outermost first
+    Frames(Vec<Frame>),
+}
+
+impl Backtrace {
+    /// The frames of the backtrace, if it was serialized as a list of frames
+    pub fn frames(&self) -> Option<&[Frame]> {
+        match self {
+            Backtrace::Text(_) => None,
+            Backtrace::Frames(frames) => Some(frames),
+        }
+    }
+}
+
+impl fmt::Display for Backtrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backtrace::Text(backtrace) => f.write_str(backtrace),
+            Backtrace::Frames(frames) => fmt_frames(frames, f),
+        }
+    }
+}
+
+impl Serialize for Backtrace {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Backtrace::Text(backtrace) => backtrace.serialize(serializer),
+            Backtrace::Frames(frames) => frames.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Backtrace {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        RawBacktrace::deserialize(deserializer).map(RawBacktrace::into_owned)
+    }
+}
+
+/// A [`Backtrace`] whose text may be borrowed from the input
+#[derive(Debug, Clone)]
+enum RawBacktrace<'de> {
+    Text(Cow<'de, str>),
+    Frames(Vec<Frame>),
+}
+
+impl RawBacktrace<'_> {
+    fn into_owned(self) -> Backtrace {
+        match self {
+            RawBacktrace::Text(text) => Backtrace::Text(text.into_owned()),
+            RawBacktrace::Frames(frames) => Backtrace::Frames(frames),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RawBacktrace<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BacktraceVisitor;
+
+        impl<'de> Visitor<'de> for BacktraceVisitor {
+            type Value = RawBacktrace<'de>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a backtrace string or a sequence of frames")
+            }
+
+            fn visit_borrowed_str<E>(self, value: &'de str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(RawBacktrace::Text(Cow::Borrowed(value)))
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(RawBacktrace::Text(Cow::Owned(value.to_string())))
+            }
+
+            fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(RawBacktrace::Text(Cow::Owned(value)))
+            }
+
+            fn visit_seq<V>(self, mut seq: V) -> Result<Self::Value, V::Error>
+            where
+                V: SeqAccess<'de>,
+            {
+                let mut frames = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(256));
+                while let Some(frame) = seq.next_element()? {
+                    frames.push(frame);
+                }
+                Ok(RawBacktrace::Frames(frames))
+            }
+        }
+
+        deserializer.deserialize_any(BacktraceVisitor)
+    }
+}
+
+/// Options controlling how error chains are deserialized
+///
+/// The [`Deserialize`] impl of [`Error`] uses [`DeserializeConfig::new`]. To
+/// read a payload with other settings, pass the config to a deserializer
+/// through [`deserialize_error`], or as a [`DeserializeSeed`]:
+///
+/// ```rust
+/// use errtools::deserialize::DeserializeConfig;
+/// use serde::de::DeserializeSeed;
+///
+/// let json = r#"{"msg": "boom", "trace_id": "4bf92f35", "source": null}"#;
+///
+/// let mut deserializer = serde_json::Deserializer::from_str(json);
+/// assert!(DeserializeConfig::new().deserialize(&mut deserializer).is_ok());
+///
+/// let mut deserializer = serde_json::Deserializer::from_str(json);
+/// let config = DeserializeConfig::new().strict(true);
+/// assert!(config.deserialize_error(&mut deserializer).is_err());
+/// ```
+///
+/// [`deserialize_error`]: DeserializeConfig::deserialize_error
+#[derive(Debug, Clone, Copy)]
+pub struct DeserializeConfig {
+    strict: bool,
+    max_depth: usize,
+    aliases: bool,
+    drop_backtraces: bool,
+}
+
+impl DeserializeConfig {
+    /// The settings used by the `Deserialize` impl of [`Error`]
+    pub const fn new() -> Self {
+        Self {
+            strict: false,
+            // The fixed layout nests errors down to the limit, and then the node
+            // standing in for those `max_depth` truncated
+            max_depth: FIXED_NESTING_LIMIT + 1,
+            aliases: true,
+            drop_backtraces: false,
+        }
+    }
+
+    /// Reject errors with fields this crate doesn't know about
+    ///
+    /// By default unknown fields are skipped, so that payloads written by a
+    /// newer version of the serializer, or by other producers, still load.
+    /// Enable this to enforce the schema instead, which also rejects payloads
+    /// whose `v` is newer than this crate's format version. Field aliases such as
+    /// `message` are still accepted unless [`aliases`] is disabled.
+    ///
+    /// [`aliases`]: DeserializeConfig::aliases
+    pub const fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Reject payloads with errors nested more than `depth` levels deep
+    ///
+    /// Every source, and every error aggregated by a `MultiError`, is one
+    /// level below the error holding it, while the errors of a flat `chain`
+    /// all count as one level below the object holding the chain. Since each
+    /// level is read by a nested call, this keeps a hostile payload from
+    /// overflowing the stack. Defaults to 257, as deep as the fixed layout
+    /// nests, which a thread with the default 2 MiB stack can read even in a
+    /// debug build. Only raise it for threads with larger stacks.
+    pub const fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Accept the other names of fields, such as `type`, `message`, and
+    /// `cause`
+    ///
+    /// Enabled by default. When disabled, only the names this crate writes
+    /// are recognized, and the others are treated as unknown fields.
+    pub const fn aliases(mut self, aliases: bool) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Discard the backtrace of every error after reading it
+    ///
+    /// Backtraces are usually the largest part of a payload. Enable this when
+    /// only the messages are of interest, to avoid keeping them in memory.
+    pub const fn drop_backtraces(mut self, drop_backtraces: bool) -> Self {
+        self.drop_backtraces = drop_backtraces;
+        self
+    }
+
+    /// Read an error from `deserializer` with these settings
+    ///
+    /// The same as using the config as a [`DeserializeSeed`], without having
+    /// to import the trait.
+    pub fn deserialize_error<'de, D>(self, deserializer: D) -> Result<Error, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        DeserializeSeed::deserialize(self, deserializer)
+    }
+
+    /// Read an error from `deserializer` with these settings, borrowing its
+    /// strings from the input where possible
+    pub fn deserialize_error_ref<'de, D>(self, deserializer: D) -> Result<ErrorRef<'de>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        ErrorSeed::new(self).deserialize(deserializer)
+    }
+}
+
+impl Default for DeserializeConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const FIELDS: &[&str] = &[
+    "type_name",
+    "msg",
+    "backtrace",
+    "errors",
+    "related",
+    "source",
+];
+
+enum Field {
+    Version,
+    TypeName,
+    Code,
+    Msg,
+    Meta,
+    Debug,
+    DisplayAlt,
+    Path,
+    IoKind,
+    OsCode,
+    Errors,
+    Related,
+    Location,
+    ReceivedFrom,
+    ReceivedAt,
+    SpanTrace,
+    Truncated,
+    Backtrace,
+    Source,
+    Chain,
+    Ignore,
+}
+
+/// Reads the name of a field, rejecting unknown ones when `strict` is set and
+/// only recognizing the canonical names unless `aliases` is set
+#[derive(Clone, Copy)]
+struct FieldSeed {
+    strict: bool,
+    aliases: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for FieldSeed {
+    type Value = Field;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Field, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl<'de> Visitor<'de> for FieldSeed {
+    type Value = Field;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("the name of a field of an error")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Field, E>
+    where
+        E: de::Error,
+    {
+        // Whenever a version of the format renames a field, the names it had
+        // in every earlier version stay listed here, so that older payloads
+        // keep loading. Aliases map onto the same `Field`, so a payload using
+        // both spellings of a field is rejected as a duplicate.
+        match value {
+            "v" => Ok(Field::Version),
+            "type_name" => Ok(Field::TypeName),
+            "code" => Ok(Field::Code),
+            "msg" => Ok(Field::Msg),
+            "meta" => Ok(Field::Meta),
+            "debug" => Ok(Field::Debug),
+            "display_alt" => Ok(Field::DisplayAlt),
+            "path" => Ok(Field::Path),
+            "io_kind" => Ok(Field::IoKind),
+            "os_code" => Ok(Field::OsCode),
+            "errors" => Ok(Field::Errors),
+            "related" => Ok(Field::Related),
+            "location" => Ok(Field::Location),
+            "received_from" => Ok(Field::ReceivedFrom),
+            "received_at" => Ok(Field::ReceivedAt),
+            "span_trace" => Ok(Field::SpanTrace),
+            "truncated" => Ok(Field::Truncated),
+            "backtrace" => Ok(Field::Backtrace),
+            "source" => Ok(Field::Source),
+            "chain" => Ok(Field::Chain),
+            // `type` is the name older versions of the serializer used, and
+            // the one most other languages use
+            "type" if self.aliases => Ok(Field::TypeName),
+            // `message` and `cause` are common in payloads written by other
+            // languages
+            "message" if self.aliases => Ok(Field::Msg),
+            "cause" if self.aliases => Ok(Field::Source),
+            _ if self.strict => Err(de::Error::unknown_field(value, FIELD_NAMES)),
+            _ => Ok(Field::Ignore),
+        }
+    }
+}
+
+/// Reject payloads written in a newer version of the format when reading
+/// strictly
+fn check_version<E>(version: u32, strict: bool) -> Result<(), E>
+where
+    E: de::Error,
+{
+    if strict && version > FORMAT_VERSION {
+        return Err(de::Error::custom(format_args!(
+            "unsupported error format version {}, expected at most {}",
+            version, FORMAT_VERSION
+        )));
+    }
+
+    Ok(())
+}
+
+impl<'de> Deserialize<'de> for Error {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        DeserializeConfig::new().deserialize(deserializer)
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for DeserializeConfig {
+    type Value = Error;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Error, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        ErrorSeed::new(self).deserialize(deserializer)
+    }
+}
+
+/// The fields read for a single error, before they are assembled into an
+/// [`Error`] or an [`ErrorRef`]
+#[derive(Debug, Clone)]
+struct Parts<'de, N> {
+    type_name: Option<Cow<'de, str>>,
+    msg: Cow<'de, str>,
+    location: Option<Location>,
+    details: Details<Cow<'de, str>>,
+    /// The origin and time the error was received at
+    received: Option<(Cow<'de, str>, Cow<'de, str>)>,
+    errors: Vec<N>,
+    related: Vec<N>,
+    backtrace: Option<RawBacktrace<'de>>,
+    source: Option<Box<N>>,
+    source_omitted: bool,
+}
+
+/// An element of a chain that can be assembled from its [`Parts`]
+trait Node<'de>: Sized {
+    fn from_parts(parts: Parts<'de, Self>) -> Self;
+
+    fn has_source(&self) -> bool;
+
+    /// Make `source` the source of this error, as written by the nested
+    /// layout
+    fn link(&mut self, source: Option<Box<Self>>);
+}
+
+impl<'de> Node<'de> for Error {
+    fn from_parts(parts: Parts<'de, Self>) -> Self {
+        let type_name = parts.type_name.map(Cow::into_owned);
+        let msg = parts.msg.into_owned();
+
+        Error {
+            reconstructed: registry::reconstruct(type_name.as_deref(), &msg),
+            type_name,
+            msg,
+            location: parts.location,
+            details: parts.details.into_owned(),
+            received: parts.received.map(|(origin, at)| Received {
+                origin: origin.into_owned(),
+                at: at.into_owned(),
+            }),
+            errors: parts.errors,
+            related: parts.related,
+            backtrace: parts.backtrace.map(RawBacktrace::into_owned),
+            source: parts.source,
+            source_omitted: parts.source_omitted,
+        }
+    }
+
+    fn has_source(&self) -> bool {
+        self.source.is_some()
+    }
+
+    fn link(&mut self, source: Option<Box<Self>>) {
+        self.source = source;
+        self.source_omitted = false;
+    }
+}
+
+/// Reads a single error at `depth` within the whole tree, `nesting` levels
+/// below the outermost error of the payload
+struct ErrorSeed<N> {
+    config: DeserializeConfig,
+    depth: usize,
+    nesting: usize,
+    node: PhantomData<fn() -> N>,
+}
+
+impl<N> Clone for ErrorSeed<N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<N> Copy for ErrorSeed<N> {}
+
+impl<N> ErrorSeed<N> {
+    /// The seed for the outermost error
+    fn new(config: DeserializeConfig) -> Self {
+        Self {
+            config,
+            depth: 0,
+            nesting: 0,
+            node: PhantomData,
+        }
+    }
+
+    /// The seed for the source of this error and the errors it aggregates
+    fn nested(self) -> Self {
+        Self {
+            depth: self.depth + 1,
+            nesting: self.nesting + 1,
+            ..self
+        }
+    }
+
+    /// The seed for the error following this one in a flat `chain`, which
+    /// is read at the same level
+    fn following(self) -> Self {
+        Self {
+            depth: self.depth + 1,
+            ..self
+        }
+    }
+
+    /// Reads the string field `field` of this error
+    fn text(self, field: &'static str, nullable: bool) -> TextSeed {
+        TextSeed {
+            field,
+            nullable,
+            depth: self.depth,
+        }
+    }
+
+    /// The error for a field of this error appearing twice
+    fn duplicate<E: de::Error>(self, field: &'static str) -> E {
+        de::Error::custom(format_args!(
+            "duplicate field `{}` in the error at depth {}",
+            field, self.depth
+        ))
+    }
+
+    /// The error for a required field of this error being absent
+    fn missing<E: de::Error>(self, field: &'static str) -> E {
+        de::Error::custom(format_args!(
+            "missing field `{}` in the error at depth {}",
+            field, self.depth
+        ))
+    }
+}
+
+impl<'de, N: Node<'de>> DeserializeSeed<'de> for ErrorSeed<N> {
+    type Value = N;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<N, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // Checked before reading any further, so that a deeply nested payload
+        // is rejected before it can exhaust the stack
+        if self.nesting >= self.config.max_depth {
+            return Err(de::Error::custom(format_args!(
+                "error chain is nested more than {} levels deep",
+                self.config.max_depth
+            )));
+        }
+
+        deserializer.deserialize_struct("error", FIELDS, self)
+    }
+}
+
+impl<'de, N: Node<'de>> Visitor<'de> for ErrorSeed<N> {
+    type Value = N;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "an error at depth {}", self.depth)
+    }
+
+    fn visit_seq<V>(self, mut seq: V) -> Result<N, V::Error>
+    where
+        V: SeqAccess<'de>,
+    {
+        let type_name = seq
+            .next_element_seed(self.text("type_name", true))?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let msg = seq
+            .next_element_seed(self.text("msg", false))?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?
+            .unwrap_or_default();
+        let backtrace = seq
+            .next_element_seed(self.text("backtrace", true))?
+            .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        let errors = seq
+            .next_element_seed(ErrorsSeed(self.nested()))?
+            .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+        let related = seq
+            .next_element_seed(ErrorsSeed(self.nested()))?
+            .ok_or_else(|| de::Error::invalid_length(4, &self))?;
+        let source = seq
+            .next_element_seed(SourceSeed(self.nested()))?
+            .ok_or_else(|| de::Error::invalid_length(5, &self))?;
+
+        // The fixed layout has no `truncated` field, so markers carry their
+        // count in their type name instead
+        let truncated = type_name.as_deref().and_then(parse_truncated_type_name);
+        Ok(N::from_parts(Parts {
+            type_name: type_name.filter(|_| truncated.is_none()),
+            msg,
+            location: None,
+            details: Details {
+                truncated,
+                ..Details::default()
+            },
+            received: None,
+            errors,
+            related,
+            backtrace: backtrace
+                .filter(|_| !self.config.drop_backtraces)
+                .map(RawBacktrace::Text),
+            source,
+            source_omitted: false,
+        }))
+    }
+
+    fn visit_map<V>(self, mut map: V) -> Result<N, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        let mut type_name = None;
+        let mut msg = None;
+        let mut details = Details::default();
+        let mut location = None;
+        let mut received_from = None;
+        let mut received_at = None;
+        let mut errors = None;
+        let mut related = None;
+        let mut backtrace = None;
+        let mut source = None;
+        let mut chain = None;
+
+        let fields = FieldSeed {
+            strict: self.config.strict,
+            aliases: self.config.aliases,
+        };
+        while let Some(key) = map.next_key_seed(fields)? {
+            match key {
+                Field::Version => check_version(map.next_value()?, self.config.strict)?,
+                Field::TypeName => {
+                    if type_name.is_some() {
+                        return Err(self.duplicate("type_name"));
+                    }
+                    type_name = Some(map.next_value_seed(self.text("type_name", true))?);
+                }
+                Field::Code => {
+                    if details.code.is_some() {
+                        return Err(self.duplicate("code"));
+                    }
+                    details.code = map.next_value_seed(CodeSeed(self.text("code", false)))?;
+                }
+                Field::Msg => {
+                    if msg.is_some() {
+                        return Err(self.duplicate("msg"));
+                    }
+                    msg = map.next_value_seed(self.text("msg", false))?;
+                }
+                Field::Meta => {
+                    if details.meta.is_some() {
+                        return Err(self.duplicate("meta"));
+                    }
+                    details.meta = Some(map.next_value_seed(MetaSeed(self.text("meta", false)))?);
+                }
+                Field::Debug => {
+                    if details.debug.is_some() {
+                        return Err(self.duplicate("debug"));
+                    }
+                    details.debug = map.next_value_seed(self.text("debug", false))?;
+                }
+                Field::DisplayAlt => {
+                    if details.display_alt.is_some() {
+                        return Err(self.duplicate("display_alt"));
+                    }
+                    details.display_alt = map.next_value_seed(self.text("display_alt", false))?;
+                }
+                Field::Path => {
+                    if details.path.is_some() {
+                        return Err(self.duplicate("path"));
+                    }
+                    details.path = map.next_value_seed(self.text("path", false))?;
+                }
+                Field::IoKind => {
+                    if details.io_kind.is_some() {
+                        return Err(self.duplicate("io_kind"));
+                    }
+                    details.io_kind = map.next_value_seed(self.text("io_kind", false))?;
+                }
+                Field::OsCode => {
+                    if details.os_code.is_some() {
+                        return Err(self.duplicate("os_code"));
+                    }
+                    details.os_code = Some(map.next_value()?);
+                }
+                Field::Location => {
+                    if location.is_some() {
+                        return Err(self.duplicate("location"));
+                    }
+                    location = Some(map.next_value()?);
+                }
+                Field::ReceivedFrom => {
+                    if received_from.is_some() {
+                        return Err(self.duplicate("received_from"));
+                    }
+                    received_from = map.next_value_seed(self.text("received_from", false))?;
+                }
+                Field::ReceivedAt => {
+                    if received_at.is_some() {
+                        return Err(self.duplicate("received_at"));
+                    }
+                    received_at = map.next_value_seed(self.text("received_at", false))?;
+                }
+                Field::Errors => {
+                    if errors.is_some() {
+                        return Err(self.duplicate("errors"));
+                    }
+                    errors = Some(map.next_value_seed(ErrorsSeed(self.nested()))?);
+                }
+                Field::Related => {
+                    if related.is_some() {
+                        return Err(self.duplicate("related"));
+                    }
+                    related = Some(map.next_value_seed(ErrorsSeed(self.nested()))?);
+                }
+                Field::SpanTrace => {
+                    if details.span_trace.is_some() {
+                        return Err(self.duplicate("span_trace"));
+                    }
+                    details.span_trace = map.next_value_seed(self.text("span_trace", false))?;
+                }
+                Field::Truncated => {
+                    if details.truncated.is_some() {
+                        return Err(self.duplicate("truncated"));
+                    }
+                    details.truncated = Some(map.next_value()?);
+                }
+                Field::Backtrace => {
+                    if backtrace.is_some() {
+                        return Err(self.duplicate("backtrace"));
+                    }
+                    backtrace = Some(map.next_value::<Option<RawBacktrace<'de>>>()?);
+                }
+                Field::Source => {
+                    if source.is_some() {
+                        return Err(self.duplicate("source"));
+                    }
+                    source = Some(map.next_value_seed(SourceSeed(self.nested()))?);
+                }
+                Field::Chain => {
+                    if chain.is_some() {
+                        return Err(self.duplicate("chain"));
+                    }
+                    chain = Some(map.next_value_seed(ChainSeed(self))?);
+                }
+                Field::Ignore => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        // The flat layout, which lists the whole chain instead of nesting it
+        if let Some(chain) = chain {
+            let nested = type_name.is_some()
+                || msg.is_some()
+                || details.code.is_some()
+                || details.meta.is_some()
+                || details.debug.is_some()
+                || details.display_alt.is_some()
+                || details.path.is_some()
+                || details.io_kind.is_some()
+                || details.os_code.is_some()
+                || details.span_trace.is_some()
+                || details.truncated.is_some()
+                || location.is_some()
+                || received_from.is_some()
+                || received_at.is_some()
+                || errors.is_some()
+                || related.is_some()
+                || backtrace.is_some()
+                || source.is_some();
+            if nested {
+                return Err(de::Error::custom(
+                    "`chain` can't be combined with the fields of a single error",
+                ));
+            }
+            return Ok(chain);
+        }
+
+        let msg = msg.ok_or_else(|| self.missing("msg"))?;
+        // Leaf errors written by other producers often leave `source` out
+        let source_omitted = source.is_none();
+        let received = match (received_from, received_at) {
+            (Some(origin), Some(at)) => Some((origin, at)),
+            (None, None) => None,
+            (Some(_), None) => return Err(self.missing("received_at")),
+            (None, Some(_)) => return Err(self.missing("received_from")),
+        };
+
+        Ok(N::from_parts(Parts {
+            type_name: type_name.unwrap_or(None),
+            msg,
+            location,
+            details,
+            received,
+            errors: errors.unwrap_or_default(),
+            related: related.unwrap_or_default(),
+            backtrace: backtrace.flatten().filter(|_| !self.config.drop_backtraces),
+            source: source.unwrap_or(None),
+            source_omitted,
+        }))
+    }
+}
+
+/// Reads the `chain` array of the flat layout, where the error holding it is
+/// the first element, and links its elements into a single chain
+struct ChainSeed<N>(ErrorSeed<N>);
+
+impl<'de, N: Node<'de>> DeserializeSeed<'de> for ChainSeed<N> {
+    type Value = N;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<N, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, N: Node<'de>> Visitor<'de> for ChainSeed<N> {
+    type Value = N;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "a non-empty sequence of errors starting at depth {}",
+            self.0.depth
+        )
+    }
+
+    fn visit_seq<V>(self, mut seq: V) -> Result<N, V::Error>
+    where
+        V: SeqAccess<'de>,
+    {
+        let mut chain = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(64));
+        let mut seed = ErrorSeed {
+            nesting: self.0.nesting + 1,
+            ..self.0
+        };
+        while let Some(error) = seq.next_element_seed(seed)? {
+            if Node::has_source(&error) {
+                return Err(de::Error::custom(
+                    "the elements of a `chain` can't have a `source`",
+                ));
+            }
+            chain.push(error);
+            seed = seed.following();
+        }
+
+        chain
+            .into_iter()
+            .rev()
+            .fold(None, |source, mut error: N| {
+                // The elements of a flat chain never have a `source` field,
+                // and the nested layout they are serialized in always does
+                error.link(source.map(Box::new));
+                Some(error)
+            })
+            .ok_or_else(|| de::Error::invalid_length(0, &self))
+    }
+}
+
+/// Reads a string field of the error at `depth`, which may only be null when
+/// `nullable` is set, borrowing it from the input when the format allows
+#[derive(Clone, Copy)]
+struct TextSeed {
+    field: &'static str,
+    nullable: bool,
+    depth: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for TextSeed {
+    type Value = Option<Cow<'de, str>>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if self.nullable {
+            deserializer.deserialize_option(self)
+        } else {
+            deserializer.deserialize_str(self)
+        }
+    }
+}
+
+impl<'de> Visitor<'de> for TextSeed {
+    type Value = Option<Cow<'de, str>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "a string for `{}` of the error at depth {}",
+            self.field, self.depth
+        )?;
+        if self.nullable {
+            formatter.write_str(" or null")?;
+        }
+        Ok(())
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Some(Cow::Borrowed(v)))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Some(Cow::Owned(v.to_string())))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Some(Cow::Owned(v)))
+    }
+
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        std::str::from_utf8(v)
+            .map(|v| Some(Cow::Borrowed(v)))
+            .map_err(|_| de::Error::invalid_value(de::Unexpected::Bytes(v), &self))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        std::str::from_utf8(v)
+            .map(|v| Some(Cow::Owned(v.to_string())))
+            .map_err(|_| de::Error::invalid_value(de::Unexpected::Bytes(v), &self))
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+}
+
+/// Reads the `code` of an error, which other producers often write as a
+/// number rather than a string
+struct CodeSeed(TextSeed);
+
+impl<'de> DeserializeSeed<'de> for CodeSeed {
+    type Value = Option<Cow<'de, str>>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for CodeSeed {
+    type Value = Option<Cow<'de, str>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.expecting(formatter)?;
+        formatter.write_str(" or an integer")
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.0.visit_borrowed_str(v)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.0.visit_str(v)
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.0.visit_string(v)
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Some(Cow::Owned(v.to_string())))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Some(Cow::Owned(v.to_string())))
+    }
+}
+
+/// Reads the `meta` object of an error, keeping its entries in order
+///
+/// Only the kinds of values [`MetadataValue`] can hold are kept, and the
+/// others, such as the floats and nested objects of other producers, are
+/// skipped. Like the serializer, only the first value of a repeated key is
+/// kept.
+struct MetaSeed(TextSeed);
+
+impl<'de> DeserializeSeed<'de> for MetaSeed {
+    type Value = Vec<(Cow<'de, str>, MetadataValue)>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for MetaSeed {
+    type Value = Vec<(Cow<'de, str>, MetadataValue)>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "an object for `meta` of the error at depth {}",
+            self.0.depth
+        )
+    }
+
+    fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        let mut meta: Self::Value = Vec::with_capacity(map.size_hint().unwrap_or(0).min(64));
+        while let Some(key) = map.next_key_seed(self.0)? {
+            let MetaValue(value) = map.next_value()?;
+            if let (Some(key), Some(value)) = (key, value) {
+                if !meta.iter().any(|(seen, _)| *seen == key) {
+                    meta.push((key, value));
+                }
+            }
+        }
+        Ok(meta)
+    }
+}
+
+/// A value of a `meta` object, or `None` if it isn't one a [`MetadataValue`]
+/// can hold
+struct MetaValue(Option<MetadataValue>);
+
+impl<'de> Deserialize<'de> for MetaValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct MetaValueVisitor;
+
+        impl<'de> Visitor<'de> for MetaValueVisitor {
+            type Value = MetaValue;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("any value")
+            }
+
+            fn visit_bool<E>(self, v: bool) -> Result<MetaValue, E>
+            where
+                E: de::Error,
+            {
+                Ok(MetaValue(Some(MetadataValue::Bool(v))))
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<MetaValue, E>
+            where
+                E: de::Error,
+            {
+                Ok(MetaValue(Some(MetadataValue::Int(v))))
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<MetaValue, E>
+            where
+                E: de::Error,
+            {
+                Ok(MetaValue(i64::try_from(v).ok().map(MetadataValue::Int)))
+            }
+
+            fn visit_f64<E>(self, _: f64) -> Result<MetaValue, E>
+            where
+                E: de::Error,
+            {
+                Ok(MetaValue(None))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<MetaValue, E>
+            where
+                E: de::Error,
+            {
+                Ok(MetaValue(Some(MetadataValue::Str(v.to_string()))))
+            }
+
+            fn visit_string<E>(self, v: String) -> Result<MetaValue, E>
+            where
+                E: de::Error,
+            {
+                Ok(MetaValue(Some(MetadataValue::Str(v))))
+            }
+
+            fn visit_none<E>(self) -> Result<MetaValue, E>
+            where
+                E: de::Error,
+            {
+                Ok(MetaValue(None))
+            }
+
+            fn visit_unit<E>(self) -> Result<MetaValue, E>
+            where
+                E: de::Error,
+            {
+                Ok(MetaValue(None))
+            }
+
+            fn visit_some<D>(self, deserializer: D) -> Result<MetaValue, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                MetaValue::deserialize(deserializer)
+            }
+
+            fn visit_seq<V>(self, mut seq: V) -> Result<MetaValue, V::Error>
+            where
+                V: SeqAccess<'de>,
+            {
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(MetaValue(None))
+            }
+
+            fn visit_map<V>(self, mut map: V) -> Result<MetaValue, V::Error>
+            where
+                V: MapAccess<'de>,
+            {
+                while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+                Ok(MetaValue(None))
+            }
+        }
+
+        deserializer.deserialize_any(MetaValueVisitor)
+    }
+}
+
+/// Reads the optional source of an error
+struct SourceSeed<N>(ErrorSeed<N>);
+
+impl<'de, N: Node<'de>> DeserializeSeed<'de> for SourceSeed<N> {
+    type Value = Option<Box<N>>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_option(self)
+    }
+}
+
+impl<'de, N: Node<'de>> Visitor<'de> for SourceSeed<N> {
+    type Value = Option<Box<N>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "an error at depth {} or null", self.0.depth)
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.0
+            .deserialize(deserializer)
+            .map(|error| Some(Box::new(error)))
+    }
+}
+
+/// Reads the errors aggregated by a `MultiError`
+struct ErrorsSeed<N>(ErrorSeed<N>);
+
+impl<'de, N: Node<'de>> DeserializeSeed<'de> for ErrorsSeed<N> {
+    type Value = Vec<N>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, N: Node<'de>> Visitor<'de> for ErrorsSeed<N> {
+    type Value = Vec<N>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "a sequence of errors at depth {}", self.0.depth)
+    }
+
+    fn visit_seq<V>(self, mut seq: V) -> Result<Self::Value, V::Error>
+    where
+        V: SeqAccess<'de>,
+    {
+        let mut errors = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(64));
+        while let Some(error) = seq.next_element_seed(self.0)? {
+            errors.push(error);
+        }
+        Ok(errors)
+    }
+}
+
+impl Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_chain(
+            Wire {
+                error: self,
+                depth: 0,
+            },
+            serializer,
+        )
+    }
+}
+
+/// An element of a deserialized chain being serialized again
+///
+/// Written by the same code as live chains, so deep chains switch to the flat
+/// layout rather than nesting without bound, and formats that aren't human
+/// readable get the fixed layout.
+#[derive(Clone, Copy)]
+struct Wire<'a> {
+    error: &'a Error,
+    depth: usize,
+}
+
+impl<'a> Wire<'a> {
+    /// The chains starting at each of `errors`, one level below this element
+    fn nested(&self, errors: &'a [Error]) -> Option<Vec<Self>> {
+        if errors.is_empty() {
+            return None;
+        }
+
+        let depth = self.depth + 1;
+        Some(errors.iter().map(|error| Wire { error, depth }).collect())
+    }
+}
+
+impl<'a> Link for Wire<'a> {
+    type Text = Text<'a>;
+
+    fn config(&self) -> &SerializeConfig {
+        &DEFAULT_CONFIG
+    }
+
+    fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn source(&self) -> Option<Self> {
+        self.error.source.as_deref().map(|error| Wire {
+            error,
+            depth: self.depth + 1,
+        })
+    }
+
+    fn cut(&self, _nested: bool, _fixed: bool) -> Option<Truncated> {
+        // Markers for truncated chains were read as errors of their own
+        None
+    }
+
+    fn fields(&self, fixed: bool) -> Cow<'_, Fields<Text<'a>>> {
+        let error = self.error;
+        let details = &error.details;
+        let backtrace = match &error.backtrace {
+            None => BacktraceField::Text(None),
+            Some(Backtrace::Text(backtrace)) => BacktraceField::Text(Some(Text::Str(backtrace))),
+            // Frames only have a place in the fixed layout as text
+            Some(backtrace) if fixed => BacktraceField::Text(Some(Text::Display(backtrace))),
+            Some(Backtrace::Frames(frames)) => BacktraceField::Frames(Some(frames.clone())),
+        };
+        let mut fields = Fields {
+            type_name: error.type_name.as_deref().map(Text::Str),
+            code: None,
+            msg: Text::Str(&error.msg),
+            meta: None,
+            debug: None,
+            display_alt: None,
+            path: None,
+            io_kind: None,
+            os_code: None,
+            location: None,
+            received: None,
+            span_trace: None,
+            truncated: details.truncated,
+            backtrace,
+        };
+        if fixed {
+            return Cow::Owned(fields);
+        }
+
+        fields.code = details.code.as_deref().map(Text::Str);
+        fields.meta = details.meta.as_ref().map(|meta| {
+            meta.iter()
+                .map(|(key, value)| (Text::Str(key), value.clone()))
+                .collect()
+        });
+        fields.debug = details.debug.as_deref().map(Text::Str);
+        fields.display_alt = details.display_alt.as_deref().map(Text::Str);
+        fields.path = details.path.as_deref().map(Text::Str);
+        fields.io_kind = details.io_kind.as_deref().map(Text::Str);
+        fields.os_code = details.os_code;
+        fields.location = error.location.as_ref().map(Into::into);
+        fields.received = error
+            .received
+            .as_ref()
+            .map(|received| (Text::Str(&received.origin), Text::Str(&received.at)));
+        fields.span_trace = details.span_trace.as_deref().map(Text::Str);
+        Cow::Owned(fields)
+    }
+
+    fn errors(&self) -> Option<Vec<Self>> {
+        self.nested(&self.error.errors)
+    }
+
+    fn related(&self) -> Option<Vec<Self>> {
+        self.nested(&self.error.related)
+    }
+
+    fn omits_source(&self) -> bool {
+        self.error.source_omitted
+    }
+}
+
+impl Serialize for Location {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut l = serializer.serialize_struct("location", 3)?;
+        l.serialize_field("file", &self.file)?;
+        l.serialize_field("line", &self.line)?;
+        l.serialize_field("column", &self.column)?;
+        l.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Location {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct LocationVisitor;
+
+        impl<'de> Visitor<'de> for LocationVisitor {
+            type Value = Location;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("struct Location")
+            }
+
+            fn visit_map<V>(self, mut map: V) -> Result<Location, V::Error>
+            where
+                V: MapAccess<'de>,
+            {
+                let mut file = None;
+                let mut line = None;
+                let mut column = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "file" => file = Some(map.next_value()?),
+                        "line" => line = Some(map.next_value()?),
+                        "column" => column = Some(map.next_value()?),
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                Ok(Location {
+                    file: file.ok_or_else(|| de::Error::missing_field("file"))?,
+                    line: line.ok_or_else(|| de::Error::missing_field("line"))?,
+                    column: column.ok_or_else(|| de::Error::missing_field("column"))?,
+                })
+            }
+        }
+
+        deserializer.deserialize_struct("location", &["file", "line", "column"], LocationVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrTools;
+    use std::error::Error as _;
+
+    #[derive(Debug)]
+    struct RootError;
+
+    impl fmt::Display for RootError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "root cause")
+        }
+    }
+
+    impl std::error::Error for RootError {}
+
+    #[derive(Debug)]
+    struct SecondError(RootError);
+
+    impl fmt::Display for SecondError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "second error")
+        }
+    }
+
+    impl std::error::Error for SecondError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[test]
+    fn round_trip_concrete() {
+        let err = SecondError(RootError);
+        let json = serde_json::to_string(&err.serialize()).unwrap();
+        let err: Error = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(err.type_name(), Some(std::any::type_name::<SecondError>()));
+        assert_eq!(err.msg, "second error");
+        assert_eq!(err.source().unwrap().to_string(), "root cause");
+        assert!(err.source().unwrap().source().is_none());
+    }
+
+    #[test]
+    fn round_trip_dyn() {
+        let err = SecondError(RootError);
+        let err: &dyn std::error::Error = &err;
+        let json = serde_json::to_string(&err.serialize()).unwrap();
+        let err: Error = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(err.type_name(), None);
+        assert_eq!(err.msg, "second error");
+        assert_eq!(err.source().unwrap().to_string(), "root cause");
+    }
+
+    #[test]
+    fn accepts_legacy_type_field() {
+        let json = r#"{"type": "my::Error", "msg": "boom", "backtrace": null, "source": null}"#;
+        let err: Error = serde_json::from_str(json).unwrap();
+
+        assert_eq!(err.type_name(), Some("my::Error"));
+        assert_eq!(err.msg, "boom");
+    }
+
+    #[test]
+    fn accepts_field_aliases() {
+        let json = r#"{
+            "type": "my::Error",
+            "message": "boom",
+            "cause": {"message": "root", "cause": null}
+        }"#;
+        let err: Error = serde_json::from_str(json).unwrap();
+
+        assert_eq!(err.type_name(), Some("my::Error"));
+        assert_eq!(err.msg, "boom");
+        assert_eq!(err.source().unwrap().to_string(), "root");
+        assert!(err.source().unwrap().source().is_none());
+    }
+
+    #[test]
+    fn rejects_alias_with_canonical_name() {
+        let json = r#"{"msg": "boom", "message": "boom", "source": null}"#;
+        let err = serde_json::from_str::<Error>(json).unwrap_err();
+        assert!(err.to_string().contains("duplicate field `msg`"));
+
+        let json = r#"{"msg": "boom", "source": null, "cause": null}"#;
+        let err = serde_json::from_str::<Error>(json).unwrap_err();
+        assert!(err.to_string().contains("duplicate field `source`"));
+
+        let json = r#"{"msg": "outer", "source": {"msg": "a", "message": "b", "source": null}}"#;
+        let err = serde_json::from_str::<Error>(json).unwrap_err();
+        assert!(err.to_string().contains("duplicate field `msg`"));
+    }
+
+    #[test]
+    fn missing_source_is_none() {
+        let payloads = [
+            r#"{"msg": "x"}"#,
+            r#"{"msg": "x", "source": null}"#,
+            r#"{"type_name": null, "msg": "x", "backtrace": null, "source": null}"#,
+        ];
+
+        for json in &payloads {
+            let err: Error = serde_json::from_str(json).unwrap();
+            assert_eq!(err, Error::new("x"));
+            assert!(err.source().is_none());
+        }
+
+        let json = r#"{"msg": "outer", "source": {"msg": "x"}}"#;
+        let err: Error = serde_json::from_str(json).unwrap();
+        assert_eq!(err, Error::new("outer").with_source(Error::new("x")));
+
+        assert!(serde_json::from_str::<Error>(r#"{"source": null}"#).is_err());
+        assert!(serde_json::from_str::<Error>(r#"{"msg": "x", "source": 1}"#).is_err());
+    }
+
+    #[test]
+    fn skips_unknown_fields() {
+        let json = r#"{
+            "trace_id": "4bf92f35",
+            "msg": "outer",
+            "fields": {"account": 42, "tags": ["sync"]},
+            "source": {"msg": "root", "retryable": true, "source": null}
+        }"#;
+        let err: Error = serde_json::from_str(json).unwrap();
+
+        assert_eq!(err, Error::new("outer").with_source(Error::new("root")));
+    }
+
+    #[test]
+    fn recognize_every_written_field() {
+        let fields = FieldSeed {
+            strict: true,
+            aliases: false,
+        };
+        for name in FIELD_NAMES {
+            let field = fields.visit_str::<de::value::Error>(name).unwrap();
+            assert!(!matches!(field, Field::Ignore), "`{}` is ignored", name);
+        }
+    }
+
+    #[test]
+    fn strict_rejects_unknown_fields() {
+        let strict = |json: &str| {
+            let mut deserializer = serde_json::Deserializer::from_str(json);
+            DeserializeConfig::new()
+                .strict(true)
+                .deserialize(&mut deserializer)
+        };
+
+        let json = r#"{"type": "my::Error", "message": "boom", "cause": null}"#;
+        assert_eq!(strict(json).unwrap().msg(), "boom");
+
+        let json = r#"{"msg": "boom", "trace_id": "4bf92f35", "source": null}"#;
+        let err = strict(json).unwrap_err();
+        assert!(err.to_string().contains("unknown field `trace_id`"));
+
+        let json = r#"{"msg": "outer", "source": {"msg": "root", "fields": {}, "source": null}}"#;
+        let err = strict(json).unwrap_err();
+        assert!(err.to_string().contains("unknown field `fields`"));
+
+        let json = r#"{"msg": "outer", "errors": [{"msg": "a", "retryable": true}]}"#;
+        let err = strict(json).unwrap_err();
+        assert!(err.to_string().contains("unknown field `retryable`"));
+    }
+
+    #[test]
+    fn format_rfc3339() {
+        let at = |secs| rfc3339(UNIX_EPOCH + std::time::Duration::from_secs(secs));
+
+        assert_eq!(at(0), "1970-01-01T00:00:00Z");
+        assert_eq!(at(1_714_564_800), "2024-05-01T12:00:00Z");
+        assert_eq!(at(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(at(4_102_444_799), "2099-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn diagnostics_name_the_failing_error() {
+        let json = r#"{"msg": 5, "source": null}"#;
+        let err = serde_json::from_str::<Error>(json).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("expected a string for `msg` of the error at depth 0"));
+
+        let json = r#"{"msg": "a", "source": {"msg": "b", "source": {"type_name": "x"}}}"#;
+        let err = serde_json::from_str::<Error>(json).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("missing field `msg` in the error at depth 2"));
+
+        let json = r#"{"msg": "a", "source": {"msg": "b", "message": "b"}}"#;
+        let err = serde_json::from_str::<Error>(json).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("duplicate field `msg` in the error at depth 1"));
+
+        let json = r#"{"msg": "a", "source": 1}"#;
+        let err = serde_json::from_str::<Error>(json).unwrap_err();
+        assert!(err.to_string().contains("expected an error at depth 1"));
+    }
+
+    #[test]
+    fn round_trip_nested_type_names() {
+        // Registering a type affects every test, so these are only used here
+        // rather than registering the shared fixtures
+        #[derive(Debug)]
+        struct RegisteredRoot;
+
+        impl fmt::Display for RegisteredRoot {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "root cause")
+            }
+        }
+
+        impl std::error::Error for RegisteredRoot {}
+
+        #[derive(Debug)]
+        struct RegisteredSecond(RegisteredRoot);
+
+        impl fmt::Display for RegisteredSecond {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "second error")
+            }
+        }
+
+        impl std::error::Error for RegisteredSecond {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        crate::registry::register_type::<RegisteredRoot>();
+        crate::registry::register_type::<RegisteredSecond>();
+        let err = RegisteredSecond(RegisteredRoot);
+        let expected = [
+            Some(std::any::type_name::<RegisteredSecond>()),
+            Some(std::any::type_name::<RegisteredRoot>()),
+        ];
+
+        let json = serde_json::to_string(&err.serialize()).unwrap();
+        let err_json: Error = serde_json::from_str(&json).unwrap();
+        let bytes = bincode::serialize(&err.serialize()).unwrap();
+        let err_bincode: Error = bincode::deserialize(&bytes).unwrap();
+
+        for err in &[err_json, err_bincode] {
+            let err: &(dyn std::error::Error + 'static) = err;
+            let type_names = std::iter::successors(Some(err), |&err| err.source())
+                .map(type_name_of)
+                .collect::<Vec<_>>();
+            assert_eq!(type_names, expected);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! An `eyre` handler which keeps the serialized form of every report
+//!
+//! Once [`install`]ed, every `eyre::Report` carries a [`SerializingHandler`]
+//! holding a [`SerializedError`] copy of its chain, ready to be shipped as
+//! JSON, and is printed with this crate's [`Report`] format.
+//!
+//! ```rust
+//! use eyre::{eyre, WrapErr};
+//!
+//! errtools::eyre::install().unwrap();
+//!
+//! let mut report = eyre!("connection reset").wrap_err("unable to sync");
+//! let serialized = errtools::eyre::serialized(&mut report).unwrap();
+//! let json = serde_json::to_value(serialized).unwrap();
+//! assert_eq!(json["msg"], "unable to sync");
+//! assert_eq!(json["source"]["msg"], "connection reset");
+//! ```
+//!
+//! [`Report`]: crate::Report
+use crate::chain::Chain;
+use crate::{registry, SerializedError};
+use eyre::EyreHandler;
+use std::error::Error;
+use std::fmt;
+
+/// An `eyre` handler which copies the chain of its report into a
+/// [`SerializedError`]
+///
+/// The copy is taken when the report is created. `eyre` moves the handler
+/// into the new report when a report is wrapped with `wrap_err`, without
+/// telling it, so use [`serialized`] to read the copy of a report that may
+/// have been wrapped since; it brings the copy up to date first.
+///
+/// Its `Debug` output is the multi-line [`Report`] of the chain, and the
+/// alternate `{:#?}` form is the `Debug` output of the error itself.
+///
+/// [`Report`]: crate::Report
+#[derive(Debug)]
+pub struct SerializingHandler {
+    serialized: SerializedError,
+    chain_len: usize,
+}
+
+impl SerializingHandler {
+    /// Create a handler holding a copy of the chain of `error`
+    pub fn new(error: &(dyn Error + 'static)) -> Self {
+        Self {
+            serialized: SerializedError::new(error, registry::type_name_of(error)),
+            chain_len: chain_len(error),
+        }
+    }
+
+    /// The copy of the chain taken when the handler was created or last
+    /// brought up to date
+    pub fn serialized(&self) -> &SerializedError {
+        &self.serialized
+    }
+}
+
+impl EyreHandler for SerializingHandler {
+    fn debug(&self, error: &(dyn Error + 'static), f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            return fmt::Debug::fmt(error, f);
+        }
+
+        write!(f, "{:#}", crate::Report::new(error))
+    }
+}
+
+/// Install [`SerializingHandler`] as the handler for every `eyre::Report`
+/// created afterwards
+///
+/// Like `color_eyre::install`, this fails if a handler was already
+/// installed.
+pub fn install() -> Result<(), eyre::InstallError> {
+    eyre::set_hook(Box::new(|error| Box::new(SerializingHandler::new(error))))
+}
+
+/// The serialized form of `report`'s chain
+///
+/// The copy is only taken again if the report was wrapped since it was last
+/// taken. Returns `None` if the report's handler isn't a
+/// [`SerializingHandler`].
+pub fn serialized(report: &mut eyre::Report) -> Option<&SerializedError> {
+    let error: &(dyn Error + 'static) = report.as_ref();
+    let len = chain_len(error);
+    let handler = report.handler().downcast_ref::<SerializingHandler>()?;
+
+    if handler.chain_len != len {
+        let error: &(dyn Error + 'static) = report.as_ref();
+        let refreshed = SerializingHandler::new(error);
+        *report.handler_mut().downcast_mut::<SerializingHandler>()? = refreshed;
+    }
+
+    report
+        .handler()
+        .downcast_ref::<SerializingHandler>()
+        .map(SerializingHandler::serialized)
+}
+
+fn chain_len(error: &(dyn Error + 'static)) -> usize {
+    Chain::new(error).count()
+}
@@ -0,0 +1,60 @@
+use crate::chain::Chain;
+use crate::registry;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// The number of nested `.source` segments used by
+/// [`ErrTools::to_field_map`] before switching to indexes
+///
+/// [`ErrTools::to_field_map`]: crate::ErrTools::to_field_map
+pub(crate) const DEFAULT_MAX_NESTING: usize = 3;
+
+/// Flatten an error chain into dotted keys for structured loggers
+///
+/// The error at depth `d` (`0` for the outermost error) uses the prefix
+/// `error` followed by `d` repetitions of `.source` while `d <= max_nesting`,
+/// and `error.chain.<d>` past that. Each prefix gets a `.msg` key and, when
+/// the type name is known, a `.type` key. `error.root.msg`, `error.root.type`,
+/// and `error.chain_len` are always added for convenience.
+///
+/// A cyclic chain ends with the last error before the first repeated one,
+/// which is treated as the root.
+pub(crate) fn field_map(
+    error: &(dyn Error + 'static),
+    type_name: Option<&'static str>,
+    max_nesting: usize,
+) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    let mut root = None;
+    let mut depth = 0;
+
+    for error in Chain::new(error) {
+        let type_name = match depth {
+            0 => type_name,
+            _ => registry::type_name_of(error),
+        };
+        let prefix = if depth <= max_nesting {
+            format!("error{}", ".source".repeat(depth))
+        } else {
+            format!("error.chain.{}", depth)
+        };
+
+        fields.insert(format!("{}.msg", prefix), error.to_string());
+        if let Some(type_name) = type_name {
+            fields.insert(format!("{}.type", prefix), type_name.to_string());
+        }
+
+        root = Some((error, type_name));
+        depth += 1;
+    }
+
+    if let Some((error, type_name)) = root {
+        fields.insert("error.root.msg".to_string(), error.to_string());
+        if let Some(type_name) = type_name {
+            fields.insert("error.root.type".to_string(), type_name.to_string());
+        }
+    }
+
+    fields.insert("error.chain_len".to_string(), depth.to_string());
+    fields
+}
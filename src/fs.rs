@@ -0,0 +1,91 @@
+//! Wrappers around `std::fs` that record the path in the error
+//!
+//! Each function behaves exactly like its `std::fs` counterpart but returns an
+//! [`FsError`] which remembers which operation failed and on which path, with
+//! the underlying `io::Error` available as its source.
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// An `io::Error` annotated with the filesystem operation and path that caused it
+#[derive(Debug)]
+pub struct FsError {
+    operation: &'static str,
+    path: PathBuf,
+    source: io::Error,
+}
+
+impl FsError {
+    fn new(operation: &'static str, path: &Path, source: io::Error) -> Self {
+        Self {
+            operation,
+            path: path.to_owned(),
+            source,
+        }
+    }
+
+    /// A short description of the operation that failed, e.g. `"read file"`
+    pub fn operation(&self) -> &'static str {
+        self.operation
+    }
+
+    /// The path the operation was performed on
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Consume the `FsError`, returning the underlying `io::Error`
+    pub fn into_inner(self) -> io::Error {
+        self.source
+    }
+}
+
+impl fmt::Display for FsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to {} `{}`", self.operation, self.path.display())
+    }
+}
+
+impl Error for FsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Read the entire contents of a file into a string, see [`std::fs::read_to_string`]
+pub fn read_to_string<P: AsRef<Path>>(path: P) -> Result<String, FsError> {
+    let path = path.as_ref();
+    fs::read_to_string(path).map_err(|source| FsError::new("read file", path, source))
+}
+
+/// Read the entire contents of a file into a bytes vector, see [`std::fs::read`]
+pub fn read<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, FsError> {
+    let path = path.as_ref();
+    fs::read(path).map_err(|source| FsError::new("read file", path, source))
+}
+
+/// Write a slice as the entire contents of a file, see [`std::fs::write`]
+pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Result<(), FsError> {
+    let path = path.as_ref();
+    fs::write(path, contents).map_err(|source| FsError::new("write file", path, source))
+}
+
+/// Recursively create a directory and all of its parents, see [`std::fs::create_dir_all`]
+pub fn create_dir_all<P: AsRef<Path>>(path: P) -> Result<(), FsError> {
+    let path = path.as_ref();
+    fs::create_dir_all(path).map_err(|source| FsError::new("create directory", path, source))
+}
+
+/// Remove a file from the filesystem, see [`std::fs::remove_file`]
+pub fn remove_file<P: AsRef<Path>>(path: P) -> Result<(), FsError> {
+    let path = path.as_ref();
+    fs::remove_file(path).map_err(|source| FsError::new("remove file", path, source))
+}
+
+/// Query the metadata of a file or directory, see [`std::fs::metadata`]
+pub fn metadata<P: AsRef<Path>>(path: P) -> Result<fs::Metadata, FsError> {
+    let path = path.as_ref();
+    fs::metadata(path).map_err(|source| FsError::new("read metadata of", path, source))
+}
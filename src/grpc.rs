@@ -0,0 +1,141 @@
+//! Carrying error chains in gRPC statuses for `tonic` services
+//!
+//! [`to_status`] puts the JSON serialized form of an error chain into the
+//! details of a [`tonic::Status`], the bytes which are sent in the
+//! `grpc-status-details-bin` trailer, and [`from_status`] reads them back on
+//! the client side. Errors choose the code of the status by implementing
+//! [`GrpcCode`].
+//!
+//! ```rust
+//! use errtools::grpc;
+//!
+//! let error = std::io::Error::new(std::io::ErrorKind::NotFound, "no such user");
+//! let status = grpc::to_status_with(&error, &|_: &_| tonic::Code::NotFound);
+//!
+//! let received = grpc::from_status(&status).unwrap();
+//! assert_eq!(received.to_string(), "no such user");
+//! ```
+use crate::chain::Chain;
+use crate::report::OneLine;
+use crate::{deserialize, registry, SerializeableError};
+use std::error::Error;
+use tonic::{Code, Status};
+
+/// An error which maps to a gRPC status code
+///
+/// [`to_status`] and [`to_status_with_code`] use the code of the outermost
+/// error in the chain that has one, among those registered with
+/// [`registry::register_grpc_code`].
+///
+/// ```rust
+/// use errtools::grpc::{self, GrpcCode};
+/// use tonic::Code;
+///
+/// #[derive(Debug)]
+/// struct MissingUser;
+///
+/// impl std::fmt::Display for MissingUser {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         f.write_str("no such user")
+///     }
+/// }
+///
+/// impl std::error::Error for MissingUser {}
+///
+/// impl GrpcCode for MissingUser {
+///     fn grpc_code(&self) -> Option<Code> {
+///         Some(Code::NotFound)
+///     }
+/// }
+///
+/// errtools::registry::register_grpc_code::<MissingUser>();
+/// assert_eq!(grpc::to_status(&MissingUser).code(), Code::NotFound);
+/// ```
+pub trait GrpcCode {
+    /// The gRPC status code for this error, if it has one
+    fn grpc_code(&self) -> Option<Code>;
+}
+
+/// Chooses the [`tonic::Code`] of a status built from an error chain
+///
+/// Implemented for any `Fn(&(dyn Error + 'static)) -> tonic::Code`.
+pub trait CodeMapper {
+    /// The gRPC status code describing `error`
+    fn code(&self, error: &(dyn Error + 'static)) -> Code;
+}
+
+impl<F> CodeMapper for F
+where
+    F: Fn(&(dyn Error + 'static)) -> Code,
+{
+    fn code(&self, error: &(dyn Error + 'static)) -> Code {
+        self(error)
+    }
+}
+
+/// Build a status carrying the serialized error chain, with the code of the
+/// outermost [`GrpcCode`] in the chain or else [`Code::Internal`]
+pub fn to_status(error: &(dyn Error + 'static)) -> Status {
+    to_status_with_code(error, Code::Internal)
+}
+
+/// Build a status carrying the serialized error chain, with the code of the
+/// outermost [`GrpcCode`] in the chain or else `code`
+pub fn to_status_with_code(error: &(dyn Error + 'static), code: Code) -> Status {
+    let code = Chain::new(error)
+        .find_map(registry::grpc_code_of)
+        .unwrap_or(code);
+    to_status_with(error, &|_: &(dyn Error + 'static)| code)
+}
+
+/// Build a status carrying the serialized error chain, with the code chosen
+/// by `mapper`
+///
+/// The status message is the messages of the chain joined with `": "`, so
+/// that clients which don't read the details still see all of them. If the
+/// chain can't be serialized the status carries only that message, which
+/// [`from_status`] reads as a single error; use [`try_to_status_with`] to
+/// get the serialization error instead.
+pub fn to_status_with<M>(error: &(dyn Error + 'static), mapper: &M) -> Status
+where
+    M: CodeMapper + ?Sized,
+{
+    try_to_status_with(error, mapper)
+        .unwrap_or_else(|_| Status::new(mapper.code(error), OneLine::new(error, ": ").to_string()))
+}
+
+/// Build a status carrying the serialized error chain, with the code chosen
+/// by `mapper`, or return the error serializing the chain ran into
+///
+/// The status is the same as the one built by [`to_status_with`].
+pub fn try_to_status_with<M>(
+    error: &(dyn Error + 'static),
+    mapper: &M,
+) -> Result<Status, serde_json::Error>
+where
+    M: CodeMapper + ?Sized,
+{
+    let details = serde_json::to_vec(&SerializeableError(error))?;
+    let message = OneLine::new(error, ": ").to_string();
+    Ok(Status::with_details(
+        mapper.code(error),
+        message,
+        details.into(),
+    ))
+}
+
+/// Read the error chain out of the details of a status built by
+/// [`to_status`]
+///
+/// Statuses from services which don't send a chain, or whose details don't
+/// hold one, are read as a single error with the message of the status.
+/// Returns `None` for a status with [`Code::Ok`], which isn't an error.
+pub fn from_status(status: &Status) -> Option<deserialize::Error> {
+    if status.code() == Code::Ok {
+        return None;
+    }
+
+    let error = serde_json::from_slice(status.details())
+        .unwrap_or_else(|_| deserialize::Error::new(status.message()));
+    Some(error)
+}
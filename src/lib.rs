@@ -1,11 +1,87 @@
 //! Extra error handling helpers
 #![feature(backtrace)]
+#![cfg_attr(feature = "provide", feature(error_generic_member_access, provide_any))]
+#![cfg_attr(feature = "std-report", feature(error_reporter))]
 #![warn(missing_docs)]
 
-use serde::ser::{Serialize, SerializeStruct, Serializer};
+use chain::Chain;
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fmt::Display;
 
+#[cfg(feature = "actix")]
+pub mod actix;
+mod adhoc;
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+#[cfg(feature = "axum")]
+pub mod axum;
+mod backtrace;
+mod chain;
+mod code;
+mod config;
+pub mod deserialize;
+#[cfg(feature = "eyre")]
+pub mod eyre;
+mod fields;
+pub mod fs;
+#[cfg(feature = "tonic")]
+pub mod grpc;
+mod location;
+#[cfg(feature = "log")]
+mod logging;
+#[cfg(feature = "json")]
+mod lossy;
+mod metadata;
+#[cfg(feature = "miette")]
+pub mod miette;
+mod multi;
+#[cfg(feature = "opentelemetry")]
+pub mod otel;
+mod owned;
+pub mod panic;
+pub mod problem;
+pub mod registry;
+pub mod report;
+#[cfg(any(feature = "anyhow", feature = "eyre"))]
+mod report_tools;
+#[cfg(feature = "schemars")]
+pub mod schema;
+#[cfg(feature = "sentry")]
+pub mod sentry;
+mod ser;
+pub mod serde_box_error;
+pub mod serde_opt_box_error;
+#[cfg(feature = "slog")]
+pub mod slog;
+mod string;
+#[cfg(feature = "tracing")]
+pub mod tracing;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod wrapped;
+
+pub use adhoc::AdhocError;
+pub use backtrace::Frame;
+pub use code::{Coded, ExitStatus, Help, HttpStatus};
+pub use config::{BacktraceMode, ChainLayout, DuplicateBacktraces, Redactor, SerializeConfig};
+pub use fs::FsError;
+pub use location::Located;
+#[cfg(feature = "log")]
+pub use logging::LogErr;
+pub use metadata::{ErrorMetadata, MetadataValue};
+pub use multi::{ErrorCollector, MultiError};
+pub use owned::SerializedError;
+pub use report::{MainReport, Report};
+#[cfg(any(feature = "anyhow", feature = "eyre"))]
+pub use report_tools::ReportTools;
+pub use ser::{
+    serializable, ConfiguredSerializeableError, Serializable, SerializeableConcreteError,
+    SerializeableError,
+};
+pub use string::{ResultStrExt, StringError};
+pub use wrapped::Wrapped;
+
 ///
 pub trait ErrTools<'a>: Error {
     ///
@@ -14,10 +90,104 @@ pub trait ErrTools<'a>: Error {
     ///
     fn serialize(&'a self) -> Self::Serialize;
 
+    /// Serialize the error chain with non-default settings
+    fn serialize_with(&'a self, config: &'a SerializeConfig) -> ConfiguredSerializeableError<'a>;
+
     ///
     fn downcast_refchain<T: Error + Sized + 'static>(&self) -> Option<&T>;
 
+    /// The code of the outermost error in the chain which has one
+    ///
+    /// See [`Coded`] for how errors provide codes.
+    fn code_in_chain(&self) -> Option<&str>;
+
+    /// The exit code of the outermost error in the chain which has one
+    ///
+    /// See [`ExitStatus`] for how errors provide exit codes.
+    fn exit_code_in_chain(&self) -> Option<u8>;
+
+    /// The HTTP status of the outermost error in the chain which has one
+    ///
+    /// See [`HttpStatus`] for how errors provide statuses. If none does, the
+    /// fallbacks registered with [`registry::register_http_status_fallback`]
+    /// are tried on the chain, outermost error first.
+    fn http_status_in_chain(&self) -> Option<u16>;
+
+    /// A report of the error chain, on one line with `{}` and listing each
+    /// source under a `Caused by:` header with `{:#}`
+    ///
+    /// See [`Report`] for the format.
+    fn report(&'a self) -> Report<'a>;
+
+    /// The messages of the error chain on a single line, joined with `": "`
+    ///
+    /// See [`OneLine`] for the format.
+    ///
+    /// [`OneLine`]: report::OneLine
+    fn to_one_line(&'a self) -> report::OneLine<'a>;
+
+    /// The standard library's report of the error chain, in its multi-line
+    /// form and with the backtrace if [`BacktracePreference::from_env`] asks
+    /// for one
+    ///
+    /// The chain is laid out like the `{:#}` form of [`Report`], except that
+    /// sources are indented differently, a lone source isn't numbered, and
+    /// the backtrace shown is the outermost one rather than the deepest.
+    ///
+    /// [`BacktracePreference::from_env`]: report::BacktracePreference::from_env
+    #[cfg(feature = "std-report")]
+    fn std_report(&self) -> std::error::Report<&Self> {
+        std::error::Report::new(self).pretty(true).show_backtrace(
+            report::BacktracePreference::from_env() != report::BacktracePreference::Never,
+        )
+    }
+
+    /// Flatten the error chain into dotted keys such as `error.msg` and
+    /// `error.source.msg`, for loggers which only accept flat string fields
+    ///
+    /// Each error in the chain gets a `.msg` key and, when its type name is
+    /// known, a `.type` key. The outermost error uses the prefix `error`, and
+    /// each source adds another `.source` segment, up to three of them. Past
+    /// that errors use `error.chain.<index>`, with the outermost error at
+    /// index `0`. `error.root.msg`, `error.root.type`, and `error.chain_len`
+    /// are always included.
+    fn to_field_map(&self) -> BTreeMap<String, String> {
+        self.to_field_map_with(fields::DEFAULT_MAX_NESTING)
+    }
+
+    /// Like [`to_field_map`] but using up to `max_nesting` `.source`
+    /// segments before switching to indexes
+    ///
+    /// [`to_field_map`]: ErrTools::to_field_map
+    fn to_field_map_with(&self, max_nesting: usize) -> BTreeMap<String, String>;
+
+    /// Record a `tracing` event at `level` with the error chain as structured
+    /// fields
     ///
+    /// See [`tracing::event_at`] for the fields.
+    #[cfg(feature = "tracing")]
+    fn trace_error(&self, level: ::tracing::Level);
+
+    /// The error chain as a structured `slog` value
+    ///
+    /// See [`slog::Chain`] for how it is emitted.
+    #[cfg(feature = "slog")]
+    fn slog_value(&'a self) -> slog::Chain<'a>;
+
+    /// Serialize the error chain into a [`serde_json::Value`]
+    #[cfg(feature = "json")]
+    fn to_json_value(&self) -> Result<serde_json::Value, serde_json::Error>;
+
+    /// Serialize the error chain into a JSON string
+    #[cfg(feature = "json")]
+    fn to_json_string(&self) -> Result<String, serde_json::Error>;
+
+    /// Serialize the error chain into a pretty printed JSON string
+    #[cfg(feature = "json")]
+    fn to_json_string_pretty(&self) -> Result<String, serde_json::Error>;
+
+    ///
+    #[track_caller]
     fn wrap_err<D, E2>(self, msg: D) -> E2
     where
         Self: Sized,
@@ -28,6 +198,7 @@ pub trait ErrTools<'a>: Error {
     }
 
     ///
+    #[track_caller]
     fn wrap_err_with<D, F, E2>(self, msg: F) -> E2
     where
         Self: Sized,
@@ -54,24 +225,55 @@ pub trait WrapErr<T, E> {
         D: Display + Send + Sync + 'static,
         E2: From<(E, String)>,
         F: FnOnce() -> D;
+
+    /// Wrap the error value in a [`Wrapped`] and attach a secondary error that
+    /// occurred while handling it, such as a failed rollback.
+    fn wrap_err_related<D, R>(self, msg: D, related: R) -> Result<T, Wrapped<E>>
+    where
+        D: Display + Send + Sync + 'static,
+        E: Error + 'static,
+        R: Into<Box<dyn Error + Send + Sync + 'static>>;
 }
 
 impl<T, E> WrapErr<T, E> for Result<T, E> {
+    #[track_caller]
     fn wrap_err<D, E2>(self, msg: D) -> Result<T, E2>
     where
         D: Display + Send + Sync + 'static,
         E2: From<(E, String)>,
     {
-        self.map_err(|source| E2::from((source, format!("{}", msg))))
+        // Matched rather than `map_err`ed so that the caller's location
+        // reaches `E2::from` through `#[track_caller]`
+        match self {
+            Ok(t) => Ok(t),
+            Err(source) => Err(E2::from((source, format!("{}", msg)))),
+        }
     }
 
+    #[track_caller]
     fn wrap_err_with<D, F, E2>(self, msg: F) -> Result<T, E2>
     where
         D: Display + Send + Sync + 'static,
         E2: From<(E, String)>,
         F: FnOnce() -> D,
     {
-        self.map_err(|source| E2::from((source, format!("{}", msg()))))
+        match self {
+            Ok(t) => Ok(t),
+            Err(source) => Err(E2::from((source, format!("{}", msg())))),
+        }
+    }
+
+    #[track_caller]
+    fn wrap_err_related<D, R>(self, msg: D, related: R) -> Result<T, Wrapped<E>>
+    where
+        D: Display + Send + Sync + 'static,
+        E: Error + 'static,
+        R: Into<Box<dyn Error + Send + Sync + 'static>>,
+    {
+        match self {
+            Ok(t) => Ok(t),
+            Err(source) => Err(Wrapped::from((source, format!("{}", msg))).with_related(related)),
+        }
     }
 }
 
@@ -85,18 +287,61 @@ where
         SerializeableConcreteError(self)
     }
 
+    fn serialize_with(&'a self, config: &'a SerializeConfig) -> ConfiguredSerializeableError<'a> {
+        ConfiguredSerializeableError::new(self, Some(std::any::type_name::<E>()), config)
+    }
+
     fn downcast_refchain<T: Error + Sized + 'static>(&self) -> Option<&T> {
-        let mut cur_error = Some(self as &dyn Error);
+        downcast_refchain(self)
+    }
 
-        while let Some(error) = cur_error {
-            if let Some(error) = error.downcast_ref() {
-                return Some(error);
-            }
+    fn code_in_chain(&self) -> Option<&str> {
+        code_in_chain(self)
+    }
 
-            cur_error = error.source();
-        }
+    fn exit_code_in_chain(&self) -> Option<u8> {
+        exit_code_in_chain(self)
+    }
+
+    fn http_status_in_chain(&self) -> Option<u16> {
+        http_status_in_chain(self)
+    }
+
+    fn report(&'a self) -> Report<'a> {
+        Report::new(self)
+    }
+
+    fn to_one_line(&'a self) -> report::OneLine<'a> {
+        report::OneLine::new(self, ": ")
+    }
+
+    fn to_field_map_with(&self, max_nesting: usize) -> BTreeMap<String, String> {
+        fields::field_map(self, Some(std::any::type_name::<E>()), max_nesting)
+    }
+
+    #[cfg(feature = "tracing")]
+    fn trace_error(&self, level: ::tracing::Level) {
+        tracing::event_at(level, self)
+    }
+
+    #[cfg(feature = "slog")]
+    fn slog_value(&'a self) -> slog::Chain<'a> {
+        slog::Chain::with_type_name(self, Some(std::any::type_name::<E>()))
+    }
+
+    #[cfg(feature = "json")]
+    fn to_json_value(&self) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(SerializeableConcreteError(self))
+    }
+
+    #[cfg(feature = "json")]
+    fn to_json_string(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&SerializeableConcreteError(self))
+    }
 
-        None
+    #[cfg(feature = "json")]
+    fn to_json_string_pretty(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&SerializeableConcreteError(self))
     }
 }
 
@@ -107,18 +352,61 @@ impl<'a> ErrTools<'a> for dyn Error + 'static {
         SerializeableError(self)
     }
 
+    fn serialize_with(&'a self, config: &'a SerializeConfig) -> ConfiguredSerializeableError<'a> {
+        ConfiguredSerializeableError::new(self, registry::type_name_of(self), config)
+    }
+
     fn downcast_refchain<T: Error + Sized + 'static>(&self) -> Option<&T> {
-        let mut cur_error = Some(self as &dyn Error);
+        downcast_refchain(self)
+    }
 
-        while let Some(error) = cur_error {
-            if let Some(error) = error.downcast_ref() {
-                return Some(error);
-            }
+    fn code_in_chain(&self) -> Option<&str> {
+        code_in_chain(self)
+    }
 
-            cur_error = error.source();
-        }
+    fn exit_code_in_chain(&self) -> Option<u8> {
+        exit_code_in_chain(self)
+    }
+
+    fn http_status_in_chain(&self) -> Option<u16> {
+        http_status_in_chain(self)
+    }
+
+    fn report(&'a self) -> Report<'a> {
+        Report::new(self)
+    }
+
+    fn to_one_line(&'a self) -> report::OneLine<'a> {
+        report::OneLine::new(self, ": ")
+    }
 
-        None
+    fn to_field_map_with(&self, max_nesting: usize) -> BTreeMap<String, String> {
+        fields::field_map(self, registry::type_name_of(self), max_nesting)
+    }
+
+    #[cfg(feature = "tracing")]
+    fn trace_error(&self, level: ::tracing::Level) {
+        tracing::event_at(level, self)
+    }
+
+    #[cfg(feature = "slog")]
+    fn slog_value(&'a self) -> slog::Chain<'a> {
+        slog::Chain::new(self)
+    }
+
+    #[cfg(feature = "json")]
+    fn to_json_value(&self) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(SerializeableError(self))
+    }
+
+    #[cfg(feature = "json")]
+    fn to_json_string(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&SerializeableError(self))
+    }
+
+    #[cfg(feature = "json")]
+    fn to_json_string_pretty(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&SerializeableError(self))
     }
 }
 
@@ -129,59 +417,89 @@ impl<'a> ErrTools<'a> for dyn Error + Send + Sync + 'static {
         SerializeableError(self)
     }
 
+    fn serialize_with(&'a self, config: &'a SerializeConfig) -> ConfiguredSerializeableError<'a> {
+        ConfiguredSerializeableError::new(self, registry::type_name_of(self), config)
+    }
+
     fn downcast_refchain<T: Error + Sized + 'static>(&self) -> Option<&T> {
-        let mut cur_error = Some(self as &dyn Error);
+        downcast_refchain(self)
+    }
 
-        while let Some(error) = cur_error {
-            if let Some(error) = error.downcast_ref() {
-                return Some(error);
-            }
+    fn code_in_chain(&self) -> Option<&str> {
+        code_in_chain(self)
+    }
 
-            cur_error = error.source();
-        }
+    fn exit_code_in_chain(&self) -> Option<u8> {
+        exit_code_in_chain(self)
+    }
 
-        None
+    fn http_status_in_chain(&self) -> Option<u16> {
+        http_status_in_chain(self)
     }
-}
 
-///
-pub struct SerializeableError<'a>(&'a dyn Error);
+    fn report(&'a self) -> Report<'a> {
+        Report::new(self)
+    }
 
-///
-pub struct SerializeableConcreteError<'a, E>(&'a E)
-where
-    E: Error + Sized + 'static;
+    fn to_one_line(&'a self) -> report::OneLine<'a> {
+        report::OneLine::new(self, ": ")
+    }
 
-impl Serialize for SerializeableError<'_> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut e = serializer.serialize_struct("error", 3)?;
-        let msg = self.0.to_string();
-        e.serialize_field("msg", &msg)?;
-        e.serialize_field("backtrace", &self.0.backtrace().map(ToString::to_string))?;
-        e.serialize_field("source", &self.0.source().map(ErrTools::serialize))?;
-        e.end()
+    fn to_field_map_with(&self, max_nesting: usize) -> BTreeMap<String, String> {
+        fields::field_map(self, registry::type_name_of(self), max_nesting)
+    }
+
+    #[cfg(feature = "tracing")]
+    fn trace_error(&self, level: ::tracing::Level) {
+        tracing::event_at(level, self)
+    }
+
+    #[cfg(feature = "slog")]
+    fn slog_value(&'a self) -> slog::Chain<'a> {
+        slog::Chain::new(self)
+    }
+
+    #[cfg(feature = "json")]
+    fn to_json_value(&self) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(SerializeableError(self))
+    }
+
+    #[cfg(feature = "json")]
+    fn to_json_string(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&SerializeableError(self))
+    }
+
+    #[cfg(feature = "json")]
+    fn to_json_string_pretty(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&SerializeableError(self))
     }
 }
 
-impl<E> Serialize for SerializeableConcreteError<'_, E>
+/// The outermost error of type `T` in the chain, including errors rebuilt by
+/// the deserializer
+pub(crate) fn downcast_refchain<'a, T>(error: &'a (dyn Error + 'static)) -> Option<&'a T>
 where
-    E: Error + Sized + 'static,
+    T: Error + Sized + 'static,
 {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut e = serializer.serialize_struct("error", 4)?;
-        let msg = self.0.to_string();
-        e.serialize_field("type", &std::any::type_name::<E>())?;
-        e.serialize_field("msg", &msg)?;
-        e.serialize_field("backtrace", &self.0.backtrace().map(ToString::to_string))?;
-        e.serialize_field("source", &self.0.source().map(ErrTools::serialize))?;
-        e.end()
-    }
+    Chain::new(error).find_map(|error| {
+        error
+            .downcast_ref()
+            .or_else(|| deserialize::reconstructed_of(error).and_then(|error| error.downcast_ref()))
+    })
+}
+
+fn code_in_chain<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a str> {
+    Chain::new(error).find_map(registry::code_of)
+}
+
+fn exit_code_in_chain(error: &(dyn Error + 'static)) -> Option<u8> {
+    Chain::new(error).find_map(registry::exit_code_of)
+}
+
+fn http_status_in_chain(error: &(dyn Error + 'static)) -> Option<u16> {
+    Chain::new(error)
+        .find_map(registry::http_status_of)
+        .or_else(|| Chain::new(error).find_map(registry::http_status_fallback_of))
 }
 
 #[cfg(test)]
@@ -244,6 +562,6 @@ mod tests {
         use crate::ErrTools;
 
         let e: &dyn Error = &E2(E1);
-        e.wrap_err("hi");
+        let _: crate::Wrapped<&dyn Error> = e.wrap_err("hi");
     }
 }
@@ -0,0 +1,111 @@
+use crate::{registry, AdhocError};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::borrow::Cow;
+use std::error::Error;
+use std::panic::Location;
+
+/// An error which records the source location it was created at
+///
+/// Locations are emitted as a `location` field holding the `file`, `line`, and
+/// `column` when the error is serialized. [`Wrapped`] records where it was
+/// created through `#[track_caller]`, so wrapping a result with
+/// [`WrapErr::wrap_err`] records the line that called it. Other implementors
+/// are found within a chain once registered with
+/// [`registry::register_located`].
+///
+/// [`Wrapped`]: crate::Wrapped
+/// [`WrapErr::wrap_err`]: crate::WrapErr::wrap_err
+/// [`registry::register_located`]: crate::registry::register_located
+pub trait Located {
+    /// The location this error was created at, if it was recorded
+    fn location(&self) -> Option<&'static Location<'static>>;
+}
+
+/// Serializes a `Location` as an object with `file`, `line`, and `column`
+pub(crate) struct LocationFields(pub(crate) &'static Location<'static>);
+
+impl Serialize for LocationFields {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut l = serializer.serialize_struct("location", 3)?;
+        l.serialize_field("file", self.0.file())?;
+        l.serialize_field("line", &self.0.line())?;
+        l.serialize_field("column", &self.0.column())?;
+        l.end()
+    }
+}
+
+/// A location which may not be `'static`, such as that of a panic, which
+/// serializes the same way as [`LocationFields`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LocationParts {
+    file: Cow<'static, str>,
+    line: u32,
+    column: u32,
+}
+
+impl LocationParts {
+    fn from_static(location: &'static Location<'static>) -> Self {
+        Self {
+            file: Cow::Borrowed(location.file()),
+            line: location.line(),
+            column: location.column(),
+        }
+    }
+
+    /// This location with its file replaced by `map` applied to it
+    pub(crate) fn map_file(self, map: impl FnOnce(&str) -> String) -> Self {
+        Self {
+            file: Cow::Owned(map(&self.file)),
+            ..self
+        }
+    }
+}
+
+impl From<&Location<'_>> for LocationParts {
+    fn from(location: &Location<'_>) -> Self {
+        Self {
+            file: Cow::Owned(location.file().to_string()),
+            line: location.line(),
+            column: location.column(),
+        }
+    }
+}
+
+impl From<&crate::deserialize::Location> for LocationParts {
+    fn from(location: &crate::deserialize::Location) -> Self {
+        Self {
+            file: Cow::Owned(location.file().to_string()),
+            line: location.line(),
+            column: location.column(),
+        }
+    }
+}
+
+impl Serialize for LocationParts {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut l = serializer.serialize_struct("location", 3)?;
+        l.serialize_field("file", &self.file)?;
+        l.serialize_field("line", &self.line)?;
+        l.serialize_field("column", &self.column)?;
+        l.end()
+    }
+}
+
+/// The location serialized for `error`, either that of a registered
+/// [`Located`] or that of the panic an [`AdhocError`] was caught from
+pub(crate) fn location_parts_of(error: &(dyn Error + 'static)) -> Option<LocationParts> {
+    registry::location_of(error)
+        .map(LocationParts::from_static)
+        .or_else(|| {
+            error
+                .downcast_ref::<AdhocError>()
+                .and_then(AdhocError::panic_location)
+                .cloned()
+        })
+}
@@ -0,0 +1,110 @@
+use crate::Report;
+use log::{Level, Metadata, Record};
+use std::error::Error;
+use std::panic::Location;
+
+/// Log the error of a `Result` with its whole chain, with the target of the
+/// calling module
+///
+/// `log_err!(result)` logs at the `Error` level and `log_err!(result, level)`
+/// at `level`, both through [`LogErr::log_err_to`].
+///
+/// ```rust
+/// use errtools::log_err;
+///
+/// fn load() -> Result<String, std::io::Error> {
+///     let config = log_err!(std::fs::read_to_string("config.toml"))?;
+///     Ok(config)
+/// }
+/// ```
+#[macro_export]
+macro_rules! log_err {
+    ($result:expr) => {
+        $crate::LogErr::log_err($result, ::std::module_path!())
+    };
+    ($result:expr, $level:expr) => {
+        $crate::LogErr::log_err_to($result, ::std::module_path!(), $level)
+    };
+}
+
+/// Log the error of a `Result` as a multi-line [`Report`] of its chain,
+/// passing the `Result` through unchanged
+///
+/// The record has the file and line of the call, and the report includes the
+/// backtrace according to `RUST_BACKTRACE`. See [`log_err!`] to log with the
+/// target of the calling module.
+///
+/// ```rust
+/// use errtools::LogErr;
+/// use log::Level;
+///
+/// fn load() -> Result<String, std::io::Error> {
+///     let config = std::fs::read_to_string("config.toml").log_err("app::config")?;
+///     Ok(config)
+/// }
+///
+/// let _ = std::fs::read_to_string("cache.toml").log_err_with_level(Level::Warn);
+/// ```
+pub trait LogErr<T, E> {
+    /// Log the error with `target` at the `Error` level
+    #[track_caller]
+    fn log_err(self, target: &str) -> Result<T, E>;
+
+    /// Log the error at `level`, with the `errtools` target
+    #[track_caller]
+    fn log_err_with_level(self, level: Level) -> Result<T, E>;
+
+    /// Log the error with `target` at `level`
+    #[track_caller]
+    fn log_err_to(self, target: &str, level: Level) -> Result<T, E>;
+}
+
+impl<T, E> LogErr<T, E> for Result<T, E>
+where
+    E: Error + 'static,
+{
+    #[track_caller]
+    fn log_err(self, target: &str) -> Result<T, E> {
+        self.log_err_to(target, Level::Error)
+    }
+
+    #[track_caller]
+    fn log_err_with_level(self, level: Level) -> Result<T, E> {
+        self.log_err_to(env!("CARGO_PKG_NAME"), level)
+    }
+
+    #[track_caller]
+    fn log_err_to(self, target: &str, level: Level) -> Result<T, E> {
+        if let Err(error) = &self {
+            log_report(error, target, level, Location::caller());
+        }
+        self
+    }
+}
+
+fn log_report(
+    error: &(dyn Error + 'static),
+    target: &str,
+    level: Level,
+    location: &'static Location<'static>,
+) {
+    if level > log::max_level() {
+        return;
+    }
+
+    let logger = log::logger();
+    let metadata = Metadata::builder().level(level).target(target).build();
+    if !logger.enabled(&metadata) {
+        return;
+    }
+
+    let report = Report::new(error);
+    logger.log(
+        &Record::builder()
+            .metadata(metadata)
+            .file_static(Some(location.file()))
+            .line(Some(location.line()))
+            .args(format_args!("{:#}", report))
+            .build(),
+    );
+}
@@ -0,0 +1,131 @@
+//! Recovery of the readable part of a damaged JSON error chain
+//!
+//! Serde reads a payload in a single pass and can't resume after an error, so
+//! [`Error::from_json_lossy`] works on the document as a whole instead. A
+//! document cut off part way through has its innermost unfinished `source`
+//! replaced with a placeholder, so that the rest of it parses, and every
+//! `source` that parses but isn't a valid error is replaced the same way.
+//!
+//! [`Error::from_json_lossy`]: crate::deserialize::Error::from_json_lossy
+use crate::deserialize::Error;
+use serde_json::Value;
+use std::fmt::Display;
+
+/// The message of the error standing in for a source that couldn't be read
+fn placeholder(reason: impl Display) -> String {
+    format!("<unparseable source: {}>", reason)
+}
+
+pub(crate) fn parse(json: &str) -> Result<Error, serde_json::Error> {
+    let value = match serde_json::from_str(json) {
+        Ok(value) => value,
+        Err(err) if err.is_eof() => match repair(json) {
+            Some(repaired) => serde_json::from_str(&repaired).map_err(|_| err)?,
+            None => return Err(err),
+        },
+        Err(err) => return Err(err),
+    };
+
+    convert(value)
+}
+
+/// Read a single error, replacing its source with a placeholder if it can't
+/// be read
+fn convert(mut value: Value) -> Result<Error, serde_json::Error> {
+    // A null source is left in place, so that it is written out again
+    let source = match &mut value {
+        Value::Object(map) => ["source", "cause"]
+            .iter()
+            .find(|key| map.get(**key).map_or(false, |source| !source.is_null()))
+            .and_then(|key| map.remove(*key)),
+        _ => None,
+    };
+    let error = Error::from_value(value)?;
+
+    Ok(match source {
+        None => error,
+        Some(source) => match convert(source) {
+            Ok(source) => error.with_source(source),
+            Err(reason) => error.with_source(Error::new(placeholder(reason))),
+        },
+    })
+}
+
+/// A container that was still open where the document was cut off
+struct Open {
+    object: bool,
+    /// Whether the next string in this object is a key
+    expect_key: bool,
+    /// The key whose value is currently being read
+    key: String,
+    /// Where the value of the current key starts, if that key is a source
+    source_at: Option<usize>,
+}
+
+/// Complete a truncated document by replacing the innermost unfinished
+/// `source` with a placeholder error and closing everything around it
+///
+/// Returns `None` if the document was cut off outside of any `source`, in
+/// which case nothing of it can be kept.
+fn repair(json: &str) -> Option<String> {
+    let mut stack: Vec<Open> = vec![];
+    let mut string: Option<String> = None;
+    let mut escaped = false;
+
+    for (at, c) in json.char_indices() {
+        if let Some(text) = &mut string {
+            if escaped {
+                escaped = false;
+                text.push(c);
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                let text = string.take().unwrap_or_default();
+                if let Some(open) = stack.last_mut().filter(|open| open.expect_key) {
+                    open.key = text;
+                }
+            } else {
+                text.push(c);
+            }
+            continue;
+        }
+
+        match c {
+            '"' => string = Some(String::new()),
+            '{' | '[' => stack.push(Open {
+                object: c == '{',
+                expect_key: c == '{',
+                key: String::new(),
+                source_at: None,
+            }),
+            '}' | ']' => {
+                stack.pop();
+            }
+            ':' => {
+                if let Some(open) = stack.last_mut().filter(|open| open.object) {
+                    open.expect_key = false;
+                    if open.key == "source" || open.key == "cause" {
+                        open.source_at = Some(at + 1);
+                    }
+                }
+            }
+            ',' => {
+                if let Some(open) = stack.last_mut().filter(|open| open.object) {
+                    open.expect_key = true;
+                    open.source_at = None;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let cut = stack.iter().rposition(|open| open.source_at.is_some())?;
+    let mut repaired = json[..stack[cut].source_at?].to_string();
+    let placeholder = serde_json::json!({ "msg": placeholder("unexpected end of input") });
+    repaired.push_str(&placeholder.to_string());
+    for open in stack[..=cut].iter().rev() {
+        repaired.push(if open.object { '}' } else { ']' });
+    }
+
+    Some(repaired)
+}
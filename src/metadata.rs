@@ -0,0 +1,63 @@
+use serde::ser::{Serialize, Serializer};
+
+/// An error which carries structured data worth serializing as real fields
+///
+/// The pairs returned by [`ErrorMetadata::metadata`] are serialized as a
+/// `meta` object on the error's node. Since the pairs are nested under their
+/// own key they can never collide with the built-in fields such as `msg`; if
+/// a key is repeated only its first value is kept. Implementors are
+/// serialized this way once registered with [`registry::register_metadata`].
+///
+/// [`registry::register_metadata`]: crate::registry::register_metadata
+pub trait ErrorMetadata {
+    /// The structured fields describing this error
+    fn metadata(&self) -> Vec<(&'static str, MetadataValue)>;
+}
+
+/// The value of a single [`ErrorMetadata`] field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataValue {
+    /// A string value
+    Str(String),
+    /// An integer value
+    Int(i64),
+    /// A boolean value
+    Bool(bool),
+}
+
+impl From<String> for MetadataValue {
+    fn from(value: String) -> Self {
+        MetadataValue::Str(value)
+    }
+}
+
+impl From<&str> for MetadataValue {
+    fn from(value: &str) -> Self {
+        MetadataValue::Str(value.to_string())
+    }
+}
+
+impl From<i64> for MetadataValue {
+    fn from(value: i64) -> Self {
+        MetadataValue::Int(value)
+    }
+}
+
+impl From<bool> for MetadataValue {
+    fn from(value: bool) -> Self {
+        MetadataValue::Bool(value)
+    }
+}
+
+impl Serialize for MetadataValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            MetadataValue::Str(value) => serializer.serialize_str(value),
+            MetadataValue::Int(value) => serializer.serialize_i64(*value),
+            MetadataValue::Bool(value) => serializer.serialize_bool(*value),
+        }
+    }
+}
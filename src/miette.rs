@@ -0,0 +1,124 @@
+//! Rendering error chains with `miette`
+//!
+//! [`AsDiagnostic`] turns an error chain into a [`Diagnostic`], so chains
+//! built with this crate can be printed with miette's report handlers.
+//!
+//! ```rust
+//! use errtools::miette::AsDiagnostic;
+//! use errtools::WrapErr;
+//!
+//! let err = std::fs::read_to_string("fake_file")
+//!     .wrap_err("unable to read config")
+//!     .unwrap_err();
+//!
+//! let diagnostic = AsDiagnostic::from_wrapped(&err);
+//! assert_eq!(diagnostic.to_string(), "unable to read config");
+//! ```
+use crate::chain::Chain;
+use crate::registry;
+use crate::Wrapped;
+use miette::Diagnostic;
+use std::error::Error;
+use std::fmt;
+
+/// An error chain as a `miette` [`Diagnostic`]
+///
+/// Each error of the chain becomes a node whose message is that error's
+/// message and whose [`Error::source`] and [`Diagnostic::diagnostic_source`]
+/// are the node for the next error. [`Diagnostic::code`] and
+/// [`Diagnostic::help`] come from the outermost [`Coded`] and [`Help`]
+/// errors in the chain, starting at the node itself.
+///
+/// The chain is copied when the diagnostic is created, so it doesn't borrow
+/// the original error.
+///
+/// [`Coded`]: crate::Coded
+/// [`Help`]: crate::Help
+#[derive(Debug, Clone)]
+pub struct AsDiagnostic {
+    msg: String,
+    code: Option<String>,
+    help: Option<String>,
+    source: Option<Box<AsDiagnostic>>,
+    related: Vec<AsDiagnostic>,
+}
+
+impl AsDiagnostic {
+    /// Copy the chain of `error` into a diagnostic
+    pub fn new(error: &(dyn Error + 'static)) -> Self {
+        let mut nodes = Chain::new(error)
+            .map(|error| Self {
+                msg: error.to_string(),
+                code: registry::code_of(error).map(ToString::to_string),
+                help: registry::help_of(error).map(ToString::to_string),
+                source: None,
+                related: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut diagnostic = nodes
+            .pop()
+            .expect("a chain always holds its outermost error");
+        while let Some(mut node) = nodes.pop() {
+            node.source = Some(Box::new(diagnostic));
+            diagnostic = node;
+        }
+        diagnostic
+    }
+
+    /// Copy the chain of a [`Wrapped`] into a diagnostic, with its related
+    /// errors as the [`Diagnostic::related`] diagnostics
+    pub fn from_wrapped<E>(error: &Wrapped<E>) -> Self
+    where
+        E: Error + 'static,
+    {
+        let mut diagnostic = Self::new(error);
+        diagnostic.related = error
+            .related()
+            .map(|related| Self::new(related as &(dyn Error + 'static)))
+            .collect();
+        diagnostic
+    }
+
+    fn chain(&self) -> impl Iterator<Item = &AsDiagnostic> {
+        std::iter::successors(Some(self), |node| node.source.as_deref())
+    }
+}
+
+impl fmt::Display for AsDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.msg)
+    }
+}
+
+impl Error for AsDiagnostic {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_deref().map(|source| source as _)
+    }
+}
+
+impl Diagnostic for AsDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.chain()
+            .find_map(|node| node.code.as_ref())
+            .map(|code| Box::new(code) as _)
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.chain()
+            .find_map(|node| node.help.as_ref())
+            .map(|help| Box::new(help) as _)
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        if self.related.is_empty() {
+            None
+        } else {
+            Some(Box::new(self.related.iter().map(|related| related as _)))
+        }
+    }
+
+    fn diagnostic_source(&self) -> Option<&dyn Diagnostic> {
+        self.source.as_deref().map(|source| source as _)
+    }
+}
@@ -0,0 +1,99 @@
+use std::error::Error;
+use std::fmt;
+
+/// Accumulates many errors so they can be reported together
+///
+/// ```rust
+/// use errtools::ErrorCollector;
+///
+/// let mut errors = ErrorCollector::new();
+///
+/// for record in &["1", "two", "3", "four"] {
+///     if let Err(e) = record.parse::<u32>() {
+///         errors.push(e);
+///     }
+/// }
+///
+/// let err = errors.finish().unwrap_err();
+/// assert_eq!(err.to_string(), "2 errors occurred");
+/// ```
+#[derive(Debug, Default)]
+pub struct ErrorCollector {
+    errors: Vec<Box<dyn Error + Send + Sync + 'static>>,
+}
+
+impl ErrorCollector {
+    /// Create an empty collector
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an error
+    pub fn push<E>(&mut self, error: E)
+    where
+        E: Into<Box<dyn Error + Send + Sync + 'static>>,
+    {
+        self.errors.push(error.into());
+    }
+
+    /// Returns `true` if no errors have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// The number of errors recorded so far
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Returns `Ok(())` if no errors were recorded, otherwise all of the
+    /// collected errors as a single [`MultiError`]
+    pub fn finish(self) -> Result<(), MultiError> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(MultiError {
+                errors: self.errors,
+            })
+        }
+    }
+}
+
+/// Many errors aggregated into one, produced by [`ErrorCollector::finish`]
+#[derive(Debug)]
+pub struct MultiError {
+    errors: Vec<Box<dyn Error + Send + Sync + 'static>>,
+}
+
+impl MultiError {
+    /// Iterate over the aggregated errors in the order they were collected
+    pub fn errors(&self) -> impl Iterator<Item = &(dyn Error + Send + Sync + 'static)> {
+        self.errors.iter().map(|error| &**error)
+    }
+
+    /// The number of aggregated errors
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Returns `true` if there are no aggregated errors
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Consume the `MultiError`, returning the aggregated errors
+    pub fn into_errors(self) -> Vec<Box<dyn Error + Send + Sync + 'static>> {
+        self.errors
+    }
+}
+
+impl fmt::Display for MultiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.errors.len() {
+            1 => write!(f, "1 error occurred"),
+            n => write!(f, "{} errors occurred", n),
+        }
+    }
+}
+
+impl Error for MultiError {}
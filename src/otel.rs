@@ -0,0 +1,110 @@
+//! OpenTelemetry exception attributes for error chains
+//!
+//! [`Span::record_error`] only records the message of the outermost error.
+//! [`exception_attrs`] follows the semantic conventions for exceptions
+//! instead, describing the whole chain with the `exception.type`,
+//! `exception.message` and `exception.stacktrace` attributes.
+//!
+//! ```rust
+//! use errtools::deserialize::Error;
+//!
+//! let err = Error::builder()
+//!     .type_name("app::LoadError")
+//!     .msg("unable to load config")
+//!     .causes(["permission denied"])
+//!     .build();
+//!
+//! let attrs = errtools::otel::exception_attrs(&err);
+//! assert_eq!(attrs[0].key.as_str(), "exception.type");
+//! assert_eq!(attrs[0].value.as_str(), "app::LoadError");
+//! assert_eq!(attrs[1].key.as_str(), "exception.message");
+//! assert_eq!(attrs[1].value.as_str(), "unable to load config: permission denied");
+//! ```
+use crate::backtrace::{backtrace_display_of, deepest_backtrace};
+use crate::chain::Chain;
+use crate::report::{known_type_name, OneLine};
+use opentelemetry::trace::{get_active_span, Span};
+use opentelemetry::KeyValue;
+use std::error::Error;
+
+/// The name of the span events recorded for exceptions
+const EXCEPTION_EVENT: &str = "exception";
+
+/// The exception attributes for `error` and its sources
+///
+/// - `exception.type`, the outermost type name in the chain that is known,
+///   left out if none are
+/// - `exception.message`, the messages of the chain joined with `": "`
+/// - `exception.stacktrace`, the deepest backtrace in the chain, left out if
+///   none of the errors captured one
+pub fn exception_attrs(error: &(dyn Error + 'static)) -> Vec<KeyValue> {
+    let type_name = Chain::new(error).find_map(known_type_name);
+
+    attrs(
+        type_name,
+        OneLine::new(error, ": ").to_string(),
+        deepest_backtrace(error).map(ToString::to_string),
+    )
+}
+
+/// Record an `exception` event with the [`exception_attrs`] of `error` on
+/// `span`
+///
+/// Like [`Span::record_error`], this leaves the status of the span alone and
+/// does nothing if the span isn't recording.
+pub fn record_exception<S>(span: &mut S, error: &(dyn Error + 'static))
+where
+    S: Span + ?Sized,
+{
+    if span.is_recording() {
+        span.add_event(EXCEPTION_EVENT, exception_attrs(error));
+    }
+}
+
+/// Record an `exception` event with the [`exception_attrs`] of `error` on the
+/// currently active span
+pub fn record_exception_on_active_span(error: &(dyn Error + 'static)) {
+    get_active_span(|span| {
+        if span.is_recording() {
+            span.add_event(EXCEPTION_EVENT, exception_attrs(error));
+        }
+    })
+}
+
+/// Record an `exception` event on `span` for each error in the chain, root
+/// cause first
+///
+/// Each event describes only its own error, with the type name, message and
+/// backtrace of that error.
+pub fn record_exception_chain<S>(span: &mut S, error: &(dyn Error + 'static))
+where
+    S: Span + ?Sized,
+{
+    if !span.is_recording() {
+        return;
+    }
+
+    let mut chain = Chain::new(error).collect::<Vec<_>>();
+    chain.reverse();
+
+    for error in chain {
+        let attrs = attrs(
+            known_type_name(error),
+            error.to_string(),
+            backtrace_display_of(error).map(ToString::to_string),
+        );
+        span.add_event(EXCEPTION_EVENT, attrs);
+    }
+}
+
+fn attrs(type_name: Option<&str>, message: String, stacktrace: Option<String>) -> Vec<KeyValue> {
+    let mut attrs = Vec::with_capacity(3);
+    if let Some(type_name) = type_name {
+        attrs.push(KeyValue::new("exception.type", type_name.to_string()));
+    }
+    attrs.push(KeyValue::new("exception.message", message));
+    if let Some(stacktrace) = stacktrace {
+        attrs.push(KeyValue::new("exception.stacktrace", stacktrace));
+    }
+    attrs
+}
@@ -0,0 +1,157 @@
+use crate::ser::{serialize_chain, Fields, Link, Node, Truncated, Truncation, DEFAULT_CONFIG};
+use crate::{registry, MultiError, SerializeConfig};
+use serde::ser::{Serialize, Serializer};
+use std::borrow::Cow;
+use std::error::Error;
+use std::sync::Arc;
+
+/// An owned copy of a serialized error chain
+///
+/// Created by [`SerializeableError::to_owned`] and
+/// [`SerializeableConcreteError::to_owned`]. Every message, type name, and
+/// backtrace is copied out of the chain up front, so the original error can be
+/// dropped and the copy sent to another thread and serialized later, with the
+/// same output the borrowed wrapper would have produced. Cloning only bumps a
+/// reference count.
+///
+/// [`SerializeableError::to_owned`]: crate::SerializeableError::to_owned
+/// [`SerializeableConcreteError::to_owned`]: crate::SerializeableConcreteError::to_owned
+#[derive(Debug, Clone)]
+pub struct SerializedError {
+    chain: Arc<[OwnedNode]>,
+    /// Whether the chain was cut short because it repeated an earlier error
+    cyclic: bool,
+}
+
+/// Everything serialized for a single element of the chain, excluding its
+/// source
+#[derive(Debug)]
+struct OwnedNode {
+    fields: Fields<String>,
+    errors: Option<Vec<SerializedError>>,
+    related: Option<Vec<SerializedError>>,
+}
+
+impl SerializedError {
+    pub(crate) fn new(error: &(dyn Error + 'static), type_name: Option<&'static str>) -> Self {
+        let root = Node::root(error, type_name, &DEFAULT_CONFIG);
+        let chain = std::iter::successors(Some(root.clone()), Node::source)
+            .map(OwnedNode::new)
+            .collect::<Vec<_>>();
+
+        Self {
+            chain: chain.into(),
+            cyclic: root.is_cyclic(),
+        }
+    }
+
+    fn link(&self, depth: usize) -> OwnedLink<'_> {
+        OwnedLink {
+            chain: &self.chain,
+            depth,
+            cyclic: self.cyclic,
+        }
+    }
+}
+
+impl OwnedNode {
+    fn new(node: Node<'_>) -> Self {
+        Self {
+            fields: node.fields(false).into_owned(),
+            errors: node
+                .error()
+                .downcast_ref::<MultiError>()
+                .map(|multi| copy(multi.errors())),
+            related: registry::related_of(node.error())
+                .map(|related| copy(related.iter().map(|error| &**error))),
+        }
+    }
+}
+
+/// Copy the chain of each of `errors`
+fn copy<'a, I>(errors: I) -> Vec<SerializedError>
+where
+    I: Iterator<Item = &'a (dyn Error + Send + Sync + 'static)>,
+{
+    errors
+        .map(|error| SerializedError::new(error, registry::type_name_of(error)))
+        .collect()
+}
+
+/// The rest of the chain starting at `depth`, written by the same code as
+/// borrowed chains
+#[derive(Clone, Copy)]
+struct OwnedLink<'a> {
+    chain: &'a [OwnedNode],
+    depth: usize,
+    cyclic: bool,
+}
+
+impl Link for OwnedLink<'_> {
+    type Text = String;
+
+    fn config(&self) -> &SerializeConfig {
+        &DEFAULT_CONFIG
+    }
+
+    fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn source(&self) -> Option<Self> {
+        match self.chain.len() {
+            0 | 1 => None,
+            _ => Some(OwnedLink {
+                chain: &self.chain[1..],
+                depth: self.depth + 1,
+                cyclic: self.cyclic,
+            }),
+        }
+    }
+
+    /// The marker written after the last element of a cyclic chain
+    fn cut(&self, nested: bool, fixed: bool) -> Option<Truncated> {
+        if !self.cyclic || self.chain.len() > 1 {
+            return None;
+        }
+
+        Some(Truncated {
+            kind: Truncation::Cycle,
+            nested,
+            fixed,
+        })
+    }
+
+    fn fields(&self, _fixed: bool) -> Cow<'_, Fields<String>> {
+        Cow::Borrowed(&self.chain[0].fields)
+    }
+
+    fn errors(&self) -> Option<Vec<Self>> {
+        let errors = self.chain[0].errors.as_ref()?;
+        Some(
+            errors
+                .iter()
+                .map(|error| error.link(self.depth + 1))
+                .collect(),
+        )
+    }
+
+    fn related(&self) -> Option<Vec<Self>> {
+        let related = self.chain[0].related.as_ref()?;
+        Some(
+            related
+                .iter()
+                .map(|error| error.link(self.depth + 1))
+                .collect(),
+        )
+    }
+}
+
+impl Serialize for SerializedError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_chain(self.link(0), serializer)
+    }
+}
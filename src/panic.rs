@@ -0,0 +1,93 @@
+//! Turning panics into errors
+//!
+//! [`install_hook`] hands every panic to a sink as a [`SerializedError`], so
+//! panics can be shipped through the same pipeline as other errors, and
+//! [`catch`] returns a panic as an [`AdhocError`].
+//!
+//! The message of the error is the panic's payload when it is a string, and
+//! `Box<dyn Any>` otherwise, as in the default panic message. The location
+//! of the panic is only known to panic hooks, so errors returned by [`catch`]
+//! only include it while the hook from [`install_hook`] is installed.
+use crate::location::LocationParts;
+use crate::{registry, AdhocError, SerializedError};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::panic::{self, PanicInfo, UnwindSafe};
+
+thread_local! {
+    /// The error built by the hook for the panic this thread is unwinding
+    /// from, for [`catch`] to pick up
+    static CAUGHT: RefCell<Option<AdhocError>> = RefCell::new(None);
+
+    /// How many calls to [`catch`] this thread is inside of, outside of which
+    /// the hook has no reason to keep the error
+    static CATCHING: Cell<usize> = Cell::new(0);
+}
+
+/// Install a panic hook which hands each panic to `sink` as a serialized
+/// error
+///
+/// The error has the panic's message, its location, and a backtrace if
+/// backtraces are enabled. The hook which was installed before is called
+/// first, so the panic is still printed as usual even if `sink` panics.
+///
+/// ```rust
+/// errtools::panic::install_hook(|error| {
+///     eprintln!("{}", serde_json::to_string(&error).unwrap());
+/// });
+/// ```
+pub fn install_hook<F>(sink: F)
+where
+    F: Fn(SerializedError) + Send + Sync + 'static,
+{
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        previous(info);
+        let error = from_panic_info(info);
+        sink(SerializedError::new(&error, registry::type_name_of(&error)));
+        if CATCHING.with(Cell::get) > 0 {
+            CAUGHT.with(|caught| *caught.borrow_mut() = Some(error));
+        }
+    }));
+}
+
+/// Run `f`, returning the panic as an error if it panics
+///
+/// ```rust
+/// let error = errtools::panic::catch(|| panic!("out of cheese")).unwrap_err();
+/// assert_eq!(error.to_string(), "out of cheese");
+/// ```
+pub fn catch<F, T>(f: F) -> Result<T, AdhocError>
+where
+    F: FnOnce() -> T + UnwindSafe,
+{
+    CAUGHT.with(|caught| caught.borrow_mut().take());
+    CATCHING.with(|depth| depth.set(depth.get() + 1));
+    let result = panic::catch_unwind(f);
+    CATCHING.with(|depth| depth.set(depth.get() - 1));
+    let caught = CAUGHT.with(|caught| caught.borrow_mut().take());
+
+    result.map_err(|payload| {
+        // The hook doesn't see payloads passed to `resume_unwind`, so the
+        // error it kept may be that of an earlier panic `f` caught itself
+        let msg = message(&*payload);
+        caught
+            .filter(|error| error.to_string() == msg)
+            .unwrap_or_else(|| AdhocError::from_panic(msg.to_string(), None))
+    })
+}
+
+fn from_panic_info(info: &PanicInfo<'_>) -> AdhocError {
+    AdhocError::from_panic(
+        message(info.payload()).to_string(),
+        info.location().map(LocationParts::from),
+    )
+}
+
+fn message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>")
+}
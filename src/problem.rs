@@ -0,0 +1,361 @@
+//! RFC 7807 problem details for HTTP APIs
+//!
+//! A [`Problem`] maps an error chain onto an `application/problem+json`
+//! document: `title` comes from the outermost error's message, `detail` from
+//! the whole chain joined with `": "`, and the chain itself is embedded in its
+//! usual serialized shape under the `errors` extension member.
+//!
+//! ```rust
+//! use errtools::problem::Problem;
+//!
+//! let error = std::io::Error::new(std::io::ErrorKind::NotFound, "no such user");
+//! let problem = Problem::from_error(&error, 404u16)
+//!     .type_uri("https://example.com/probs/missing-user")
+//!     .extension("user_id", 7);
+//! ```
+//!
+//! Problem documents received from other services can be read back with
+//! [`ProblemDetails`] and turned into an error chain.
+use crate::chain::Chain;
+#[cfg(feature = "json")]
+use crate::deserialize;
+use crate::{registry, MetadataValue, SerializedError};
+#[cfg(feature = "json")]
+use serde::de::{self, Deserialize, DeserializeOwned, Deserializer};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+#[cfg(feature = "json")]
+use serde_json::{Map, Value};
+use std::error::Error;
+
+/// The media type of a serialized [`Problem`]
+pub const CONTENT_TYPE: &str = "application/problem+json";
+
+/// Chooses the HTTP status of a [`Problem`] built from an error chain
+///
+/// Implemented for any `Fn(&(dyn Error + 'static)) -> u16`.
+pub trait StatusMapper {
+    /// The HTTP status code describing `error`
+    fn status(&self, error: &(dyn Error + 'static)) -> u16;
+}
+
+impl<F> StatusMapper for F
+where
+    F: Fn(&(dyn Error + 'static)) -> u16,
+{
+    fn status(&self, error: &(dyn Error + 'static)) -> u16 {
+        self(error)
+    }
+}
+
+/// An RFC 7807 problem details document describing an error chain
+#[derive(Debug, Clone)]
+pub struct Problem {
+    type_uri: String,
+    title: String,
+    status: u16,
+    detail: String,
+    instance: Option<String>,
+    extensions: Vec<(String, MetadataValue)>,
+    errors: SerializedError,
+}
+
+impl Problem {
+    /// Describe `error` with the given HTTP status
+    ///
+    /// `status` accepts a plain `u16` or any status code type which converts
+    /// into one, such as `http::StatusCode`.
+    pub fn from_error<S>(error: &(dyn Error + 'static), status: S) -> Self
+    where
+        S: Into<u16>,
+    {
+        let detail = Chain::new(error)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(": ");
+
+        Self {
+            type_uri: "about:blank".to_string(),
+            title: error.to_string(),
+            status: status.into(),
+            detail,
+            instance: None,
+            extensions: Vec::new(),
+            errors: SerializedError::new(error, registry::type_name_of(error)),
+        }
+    }
+
+    /// Describe `error` with the HTTP status chosen by `mapper`
+    pub fn from_error_with<M>(error: &(dyn Error + 'static), mapper: &M) -> Self
+    where
+        M: StatusMapper + ?Sized,
+    {
+        Self::from_error(error, mapper.status(error))
+    }
+
+    /// Set the URI identifying the problem type, `about:blank` by default
+    pub fn type_uri(mut self, type_uri: impl Into<String>) -> Self {
+        self.type_uri = type_uri.into();
+        self
+    }
+
+    /// Override the title taken from the outermost error's message
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Override the detail taken from the joined messages of the chain
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = detail.into();
+        self
+    }
+
+    /// Set the URI identifying this specific occurrence of the problem
+    pub fn instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Add an extension member
+    ///
+    /// Keys which collide with the standard members or `errors` are ignored
+    /// when serializing, as are repeated keys after the first.
+    pub fn extension(mut self, key: impl Into<String>, value: impl Into<MetadataValue>) -> Self {
+        self.extensions.push((key.into(), value.into()));
+        self
+    }
+
+    /// The HTTP status of the problem
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+}
+
+const RESERVED: &[&str] = &["type", "title", "status", "detail", "instance", "errors"];
+
+impl Serialize for Problem {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("type", &self.type_uri)?;
+        map.serialize_entry("title", &self.title)?;
+        map.serialize_entry("status", &self.status)?;
+        map.serialize_entry("detail", &self.detail)?;
+        if let Some(instance) = &self.instance {
+            map.serialize_entry("instance", instance)?;
+        }
+        map.serialize_entry("errors", &self.errors)?;
+
+        let mut seen: Vec<&str> = RESERVED.to_vec();
+        for (key, value) in &self.extensions {
+            if seen.contains(&key.as_str()) {
+                continue;
+            }
+            seen.push(key);
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+/// An RFC 7807 problem details document read from JSON
+///
+/// Every standard member is optional, as the RFC allows, and any other member
+/// is kept as an extension. An `errors` member is either the chain embedded
+/// by a [`Problem`] or an array whose entries are kept as they were read,
+/// since other producers put entries such as `{"detail", "pointer"}` there.
+/// Use [`ProblemDetails::into_error`] to turn the document into an error
+/// chain, or [`deserialize::Error::from_problem_json`] to do both at once.
+///
+/// ```rust
+/// use errtools::problem::ProblemDetails;
+///
+/// let json = r#"{
+///     "type": "https://example.com/probs/out-of-credit",
+///     "title": "You do not have enough credit.",
+///     "status": 403,
+///     "detail": "Your current balance is 30, but that costs 50.",
+///     "balance": 30
+/// }"#;
+/// let problem: ProblemDetails = serde_json::from_str(json).unwrap();
+/// assert_eq!(problem.status(), Some(403));
+/// assert_eq!(problem.extension("balance"), Some(&serde_json::json!(30)));
+///
+/// let err = problem.into_error();
+/// assert_eq!(err.msg(), "You do not have enough credit.");
+/// assert_eq!(err.root().msg(), "Your current balance is 30, but that costs 50.");
+/// assert_eq!(err.http_status(), Some(403));
+/// ```
+#[cfg(feature = "json")]
+#[derive(Debug, Clone)]
+pub struct ProblemDetails {
+    type_uri: Option<String>,
+    title: Option<String>,
+    status: Option<u16>,
+    detail: Option<String>,
+    instance: Option<String>,
+    errors: Option<ProblemErrors>,
+    extensions: Map<String, Value>,
+}
+
+/// The `errors` extension member of a problem document
+#[cfg(feature = "json")]
+#[derive(Debug, Clone)]
+enum ProblemErrors {
+    /// The chain embedded by a [`Problem`]
+    Chain(Box<deserialize::Error>),
+    /// A list of entries, which may or may not be error chains
+    List(Vec<Value>),
+}
+
+#[cfg(feature = "json")]
+impl ProblemDetails {
+    /// The URI identifying the problem type, `about:blank` if absent
+    pub fn type_uri(&self) -> &str {
+        self.type_uri.as_deref().unwrap_or("about:blank")
+    }
+
+    /// The short summary of the problem type
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// The HTTP status of the problem
+    pub fn status(&self) -> Option<u16> {
+        self.status
+    }
+
+    /// The explanation specific to this occurrence of the problem
+    pub fn detail(&self) -> Option<&str> {
+        self.detail.as_deref()
+    }
+
+    /// The URI identifying this specific occurrence of the problem
+    pub fn instance(&self) -> Option<&str> {
+        self.instance.as_deref()
+    }
+
+    /// The extension member named `key`
+    pub fn extension(&self, key: &str) -> Option<&Value> {
+        self.extensions.get(key)
+    }
+
+    /// Every extension member other than `errors`
+    pub fn extensions(&self) -> &Map<String, Value> {
+        &self.extensions
+    }
+
+    /// The entries of an `errors` array as they were read, which is empty if
+    /// the document has none or embeds a chain instead
+    pub fn errors(&self) -> &[Value] {
+        match &self.errors {
+            Some(ProblemErrors::List(list)) => list,
+            _ => &[],
+        }
+    }
+
+    /// Turn the document into an error chain
+    ///
+    /// A document written by [`Problem`] embeds the chain it describes under
+    /// `errors`, which is returned as is. Otherwise the outermost error's
+    /// message is the `title`, followed by the `detail` as its source if the
+    /// two differ. Either one stands in for the other when missing, and the
+    /// type URI stands in for both. Each entry of an `errors` array becomes
+    /// one of the errors aggregated by the outermost error: entries which
+    /// are error chains are read as such, and the message of any other entry
+    /// is its `detail` or `title`, or else its JSON text.
+    ///
+    /// Either way, the type URI, the status, and the detail are recorded as
+    /// the `type`, `status`, and `detail` metadata of the outermost error,
+    /// see [`deserialize::Error::problem_type`] and
+    /// [`deserialize::Error::http_status`].
+    pub fn into_error(self) -> deserialize::Error {
+        let type_uri = self.type_uri.unwrap_or_else(|| "about:blank".to_string());
+        let mut meta = vec![("type".to_string(), MetadataValue::Str(type_uri.clone()))];
+        if let Some(status) = self.status {
+            meta.push(("status".to_string(), MetadataValue::Int(status.into())));
+        }
+        if let Some(detail) = &self.detail {
+            meta.push(("detail".to_string(), MetadataValue::Str(detail.clone())));
+        }
+
+        let list = match self.errors {
+            Some(ProblemErrors::Chain(chain)) => return chain.with_leading_meta(meta),
+            Some(ProblemErrors::List(list)) => list,
+            None => Vec::new(),
+        };
+
+        let error = match (self.title, self.detail) {
+            (Some(title), Some(detail)) if title != detail => {
+                deserialize::Error::new(title).with_source(deserialize::Error::new(detail))
+            }
+            (Some(msg), _) | (None, Some(msg)) => deserialize::Error::new(msg),
+            (None, None) => deserialize::Error::new(type_uri),
+        };
+
+        error
+            .with_errors(list.iter().map(entry_error).collect())
+            .with_leading_meta(meta)
+    }
+}
+
+/// The error described by an entry of an `errors` array
+#[cfg(feature = "json")]
+fn entry_error(entry: &Value) -> deserialize::Error {
+    if let Ok(error) = deserialize::Error::from_value_ref(entry) {
+        return error;
+    }
+
+    let msg = ["detail", "title"]
+        .iter()
+        .find_map(|key| entry.get(key).and_then(Value::as_str));
+    match msg {
+        Some(msg) => deserialize::Error::new(msg),
+        None => deserialize::Error::new(entry.to_string()),
+    }
+}
+
+#[cfg(feature = "json")]
+impl<'de> Deserialize<'de> for ProblemDetails {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut members = Map::deserialize(deserializer)?;
+
+        let errors = match members.remove("errors") {
+            None | Some(Value::Null) => None,
+            Some(Value::Array(list)) => Some(ProblemErrors::List(list)),
+            Some(chain) => Some(ProblemErrors::Chain(Box::new(
+                deserialize::Error::from_value(chain).map_err(de::Error::custom)?,
+            ))),
+        };
+
+        Ok(Self {
+            type_uri: member(&mut members, "type")?,
+            title: member(&mut members, "title")?,
+            status: member(&mut members, "status")?,
+            detail: member(&mut members, "detail")?,
+            instance: member(&mut members, "instance")?,
+            errors,
+            extensions: members,
+        })
+    }
+}
+
+/// Remove the standard member `key` from `members` and read it
+#[cfg(feature = "json")]
+fn member<T, E>(members: &mut Map<String, Value>, key: &str) -> Result<Option<T>, E>
+where
+    T: DeserializeOwned,
+    E: de::Error,
+{
+    match members.remove(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => T::deserialize(value)
+            .map(Some)
+            .map_err(|error| de::Error::custom(format_args!("invalid `{}`: {}", key, error))),
+    }
+}
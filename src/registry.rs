@@ -0,0 +1,474 @@
+//! Runtime registration of error types
+//!
+//! `source()` only ever hands out `&dyn Error`, so once an error is nested in
+//! a chain its concrete type is no longer statically known. Types registered
+//! here can be recognized again by downcasting, which lets serialization
+//! record a `type_name` for every node of the chain rather than just the
+//! outermost one.
+//!
+//! The error types defined by this crate and the common error types from
+//! `std` are recognized without registration.
+//!
+//! The same goes for the crate's extension traits, such as [`Coded`],
+//! [`ExitStatus`], [`HttpStatus`], [`Help`], [`ErrorMetadata`], [`Located`],
+//! and, with the `tonic` feature, `GrpcCode`. Each has a `register_*`
+//! function here, and an implementor nested in a chain is only recognized
+//! once it has been registered with it. Implementors of
+//! [`ReconstructibleError`] are registered here for the deserializer to
+//! rebuild them.
+//!
+//! [`ReconstructibleError`]: crate::deserialize::ReconstructibleError
+//!
+//! ```rust
+//! #[derive(Debug)]
+//! struct MyError;
+//!
+//! impl std::fmt::Display for MyError {
+//!     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+//!         f.write_str("my error")
+//!     }
+//! }
+//!
+//! impl std::error::Error for MyError {}
+//!
+//! errtools::registry::register_type::<MyError>();
+//! ```
+use crate::deserialize::ReconstructibleError;
+#[cfg(feature = "tonic")]
+use crate::grpc::GrpcCode;
+use crate::{Coded, ErrorMetadata, ExitStatus, Help, HttpStatus, Located, Wrapped};
+use once_cell::sync::OnceCell;
+use std::any::TypeId;
+use std::error::Error;
+use std::panic::Location;
+use std::sync::{Arc, RwLock};
+
+type TypeNameFn = fn(&(dyn Error + 'static)) -> Option<&'static str>;
+type CodedFn = for<'a> fn(&'a (dyn Error + 'static)) -> Option<&'a dyn Coded>;
+type ExitStatusFn = for<'a> fn(&'a (dyn Error + 'static)) -> Option<&'a dyn ExitStatus>;
+type HttpStatusFn = for<'a> fn(&'a (dyn Error + 'static)) -> Option<&'a dyn HttpStatus>;
+type HttpStatusFallbackFn = fn(&(dyn Error + 'static)) -> Option<u16>;
+type HelpFn = for<'a> fn(&'a (dyn Error + 'static)) -> Option<&'a dyn Help>;
+#[cfg(feature = "tonic")]
+type GrpcCodeFn = for<'a> fn(&'a (dyn Error + 'static)) -> Option<&'a dyn GrpcCode>;
+
+type MetadataFn = for<'a> fn(&'a (dyn Error + 'static)) -> Option<&'a dyn ErrorMetadata>;
+type LocatedFn = for<'a> fn(&'a (dyn Error + 'static)) -> Option<&'a dyn Located>;
+type RelatedFn = for<'a> fn(&'a (dyn Error + 'static)) -> Option<&'a [RelatedError]>;
+type RelatedError = Box<dyn Error + Send + Sync + 'static>;
+type ReconstructFn = fn(&str) -> Option<Arc<dyn Error + Send + Sync + 'static>>;
+
+static CODED: Registry<TypeId, CodedFn> = Registry::new();
+static EXIT_STATUS: Registry<TypeId, ExitStatusFn> = Registry::new();
+static HTTP_STATUS: Registry<TypeId, HttpStatusFn> = Registry::new();
+static HTTP_STATUS_FALLBACK: Registry<usize, HttpStatusFallbackFn> = Registry::with(|| {
+    let f = io_http_status as HttpStatusFallbackFn;
+    vec![(f as usize, f)]
+});
+static HELP: Registry<TypeId, HelpFn> = Registry::new();
+#[cfg(feature = "tonic")]
+static GRPC_CODE: Registry<TypeId, GrpcCodeFn> = Registry::new();
+static METADATA: Registry<TypeId, MetadataFn> = Registry::new();
+static LOCATED: Registry<TypeId, LocatedFn> = Registry::new();
+static RELATED: Registry<TypeId, RelatedFn> = Registry::new();
+static RECONSTRUCT: Registry<&'static str, ReconstructFn> = Registry::new();
+
+static TYPE_NAMES: Registry<TypeId, TypeNameFn> = Registry::with(|| {
+    vec![
+        type_name_entry::<crate::AdhocError>(),
+        type_name_entry::<crate::FsError>(),
+        type_name_entry::<crate::MultiError>(),
+        type_name_entry::<crate::StringError>(),
+        type_name_entry::<std::io::Error>(),
+        type_name_entry::<std::fmt::Error>(),
+        type_name_entry::<std::num::ParseIntError>(),
+        type_name_entry::<std::num::ParseFloatError>(),
+        type_name_entry::<std::num::TryFromIntError>(),
+        type_name_entry::<std::str::ParseBoolError>(),
+        type_name_entry::<std::str::Utf8Error>(),
+        type_name_entry::<std::string::FromUtf8Error>(),
+        type_name_entry::<std::net::AddrParseError>(),
+    ]
+});
+
+/// The functions registered for recognizing one kind of error, each under the
+/// key it was registered for
+struct Registry<K, F> {
+    fns: OnceCell<RwLock<Vec<(K, F)>>>,
+    /// The functions registered from the start
+    init: fn() -> Vec<(K, F)>,
+}
+
+impl<K: PartialEq, F: Copy> Registry<K, F> {
+    const fn new() -> Self {
+        Self::with(Vec::new)
+    }
+
+    const fn with(init: fn() -> Vec<(K, F)>) -> Self {
+        Registry {
+            fns: OnceCell::new(),
+            init,
+        }
+    }
+
+    fn fns(&self) -> &RwLock<Vec<(K, F)>> {
+        self.fns.get_or_init(|| RwLock::new((self.init)()))
+    }
+
+    /// Add `f` under `key`, unless a function was registered under it already
+    fn register(&self, key: K, f: F) {
+        if self.find(|seen, _| (*seen == key).then_some(())).is_some() {
+            return;
+        }
+
+        let mut fns = self.fns().write().unwrap_or_else(|e| e.into_inner());
+        if !fns.iter().any(|(seen, _)| *seen == key) {
+            fns.push((key, f));
+        }
+    }
+
+    /// The first result of `apply` for the registered functions, in the order
+    /// they were registered
+    fn find<T>(&self, mut apply: impl FnMut(&K, F) -> Option<T>) -> Option<T> {
+        let fns = self.fns().read().unwrap_or_else(|e| e.into_inner());
+        fns.iter().find_map(|(key, f)| apply(key, *f))
+    }
+}
+
+/// The entry recognizing the type name of `E`
+fn type_name_entry<E>() -> (TypeId, TypeNameFn)
+where
+    E: Error + 'static,
+{
+    (TypeId::of::<E>(), type_name_if::<E>)
+}
+
+fn type_name_if<E>(error: &(dyn Error + 'static)) -> Option<&'static str>
+where
+    E: Error + 'static,
+{
+    if error.is::<E>() {
+        Some(std::any::type_name::<E>())
+    } else {
+        None
+    }
+}
+
+/// Register `E` so that its type name can be determined wherever it appears in
+/// an error chain
+///
+/// Registering the same type more than once has no additional effect.
+pub fn register_type<E>()
+where
+    E: Error + 'static,
+{
+    let (key, f) = type_name_entry::<E>();
+    TYPE_NAMES.register(key, f);
+}
+
+/// Determine the type name of an arbitrary error, if its type is known
+pub fn type_name_of(error: &(dyn Error + 'static)) -> Option<&'static str> {
+    TYPE_NAMES.find(|_, f| f(error))
+}
+
+fn as_coded<'a, E>(error: &'a (dyn Error + 'static)) -> Option<&'a dyn Coded>
+where
+    E: Coded + Error + 'static,
+{
+    error.downcast_ref::<E>().map(|error| error as &dyn Coded)
+}
+
+/// Register `E` so that its [`Coded::code`] can be found wherever it appears in
+/// an error chain
+pub fn register_coded<E>()
+where
+    E: Coded + Error + 'static,
+{
+    CODED.register(TypeId::of::<E>(), as_coded::<E>);
+}
+
+/// Determine the code of an arbitrary error, if its type is a registered
+/// implementor of [`Coded`] with a code
+pub fn code_of<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a str> {
+    CODED.find(|_, f| f(error)).and_then(Coded::code)
+}
+
+fn as_exit_status<'a, E>(error: &'a (dyn Error + 'static)) -> Option<&'a dyn ExitStatus>
+where
+    E: ExitStatus + Error + 'static,
+{
+    error
+        .downcast_ref::<E>()
+        .map(|error| error as &dyn ExitStatus)
+}
+
+/// Register `E` so that its [`ExitStatus::exit_code`] can be found wherever it
+/// appears in an error chain
+pub fn register_exit_status<E>()
+where
+    E: ExitStatus + Error + 'static,
+{
+    EXIT_STATUS.register(TypeId::of::<E>(), as_exit_status::<E>);
+}
+
+/// Determine the exit code of an arbitrary error, if its type is a registered
+/// implementor of [`ExitStatus`] with a code
+///
+/// With the `provide` feature, errors that provide a `dyn ExitStatus` are
+/// recognized without registration.
+pub fn exit_code_of(error: &(dyn Error + 'static)) -> Option<u8> {
+    #[cfg(feature = "provide")]
+    {
+        if let Some(status) = error.request_ref::<dyn ExitStatus>() {
+            return status.exit_code();
+        }
+    }
+
+    EXIT_STATUS
+        .find(|_, f| f(error))
+        .and_then(ExitStatus::exit_code)
+}
+
+fn as_http_status<'a, E>(error: &'a (dyn Error + 'static)) -> Option<&'a dyn HttpStatus>
+where
+    E: HttpStatus + Error + 'static,
+{
+    error
+        .downcast_ref::<E>()
+        .map(|error| error as &dyn HttpStatus)
+}
+
+/// Register `E` so that its [`HttpStatus::http_status`] can be found wherever
+/// it appears in an error chain
+pub fn register_http_status<E>()
+where
+    E: HttpStatus + Error + 'static,
+{
+    HTTP_STATUS.register(TypeId::of::<E>(), as_http_status::<E>);
+}
+
+/// Determine the HTTP status of an arbitrary error, if its type is a
+/// registered implementor of [`HttpStatus`] with a status
+///
+/// With the `provide` feature, errors that provide a `dyn HttpStatus` are
+/// recognized without registration. Fallbacks registered with
+/// [`register_http_status_fallback`] aren't consulted.
+pub fn http_status_of(error: &(dyn Error + 'static)) -> Option<u16> {
+    #[cfg(feature = "provide")]
+    {
+        if let Some(status) = error.request_ref::<dyn HttpStatus>() {
+            return status.http_status();
+        }
+    }
+
+    HTTP_STATUS
+        .find(|_, f| f(error))
+        .and_then(HttpStatus::http_status)
+}
+
+fn io_http_status(error: &(dyn Error + 'static)) -> Option<u16> {
+    use std::io::ErrorKind;
+
+    let status = match error.downcast_ref::<std::io::Error>()?.kind() {
+        ErrorKind::NotFound => 404,
+        ErrorKind::PermissionDenied => 403,
+        ErrorKind::AlreadyExists => 409,
+        ErrorKind::InvalidInput | ErrorKind::InvalidData => 400,
+        ErrorKind::TimedOut => 504,
+        _ => return None,
+    };
+
+    Some(status)
+}
+
+/// Register a function guessing the HTTP status of errors which can't
+/// implement [`HttpStatus`], such as those of other crates
+///
+/// Fallbacks are only consulted for a chain in which no error has a status
+/// of its own, and are tried in the order they were registered. A fallback
+/// mapping the kinds of `std::io::Error` is registered from the start:
+/// `NotFound` to 404, `PermissionDenied` to 403, `AlreadyExists` to 409,
+/// `InvalidInput` and `InvalidData` to 400, and `TimedOut` to 504.
+///
+/// Registering the same function more than once has no additional effect.
+///
+/// ```rust
+/// fn parse_errors(error: &(dyn std::error::Error + 'static)) -> Option<u16> {
+///     error.downcast_ref::<std::num::ParseIntError>().map(|_| 400)
+/// }
+///
+/// errtools::registry::register_http_status_fallback(parse_errors);
+/// ```
+pub fn register_http_status_fallback(f: fn(&(dyn Error + 'static)) -> Option<u16>) {
+    HTTP_STATUS_FALLBACK.register(f as usize, f);
+}
+
+/// Guess the HTTP status of an arbitrary error with the fallbacks registered
+/// with [`register_http_status_fallback`]
+pub fn http_status_fallback_of(error: &(dyn Error + 'static)) -> Option<u16> {
+    HTTP_STATUS_FALLBACK.find(|_, f| f(error))
+}
+
+fn as_help<'a, E>(error: &'a (dyn Error + 'static)) -> Option<&'a dyn Help>
+where
+    E: Help + Error + 'static,
+{
+    error.downcast_ref::<E>().map(|error| error as &dyn Help)
+}
+
+/// Register `E` so that its [`Help::help`] can be found wherever it appears in
+/// an error chain
+pub fn register_help<E>()
+where
+    E: Help + Error + 'static,
+{
+    HELP.register(TypeId::of::<E>(), as_help::<E>);
+}
+
+/// Find the advice for an arbitrary error, if its type is a registered
+/// implementor of [`Help`] with some
+pub fn help_of<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a str> {
+    HELP.find(|_, f| f(error)).and_then(Help::help)
+}
+
+#[cfg(feature = "tonic")]
+fn as_grpc_code<'a, E>(error: &'a (dyn Error + 'static)) -> Option<&'a dyn GrpcCode>
+where
+    E: GrpcCode + Error + 'static,
+{
+    error
+        .downcast_ref::<E>()
+        .map(|error| error as &dyn GrpcCode)
+}
+
+/// Register `E` so that its [`GrpcCode::grpc_code`] can be found wherever it
+/// appears in an error chain
+#[cfg(feature = "tonic")]
+pub fn register_grpc_code<E>()
+where
+    E: GrpcCode + Error + 'static,
+{
+    GRPC_CODE.register(TypeId::of::<E>(), as_grpc_code::<E>);
+}
+
+/// Determine the gRPC status code of an arbitrary error, if its type is a
+/// registered implementor of [`GrpcCode`] with a code
+#[cfg(feature = "tonic")]
+pub fn grpc_code_of(error: &(dyn Error + 'static)) -> Option<tonic::Code> {
+    GRPC_CODE
+        .find(|_, f| f(error))
+        .and_then(GrpcCode::grpc_code)
+}
+
+fn as_metadata<'a, E>(error: &'a (dyn Error + 'static)) -> Option<&'a dyn ErrorMetadata>
+where
+    E: ErrorMetadata + Error + 'static,
+{
+    error
+        .downcast_ref::<E>()
+        .map(|error| error as &dyn ErrorMetadata)
+}
+
+/// Register `E` so that its [`ErrorMetadata`] is serialized wherever it
+/// appears in an error chain
+pub fn register_metadata<E>()
+where
+    E: ErrorMetadata + Error + 'static,
+{
+    METADATA.register(TypeId::of::<E>(), as_metadata::<E>);
+}
+
+/// Find the [`ErrorMetadata`] of an arbitrary error, if its type is a
+/// registered implementor
+pub fn metadata_of<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a dyn ErrorMetadata> {
+    METADATA.find(|_, f| f(error))
+}
+
+fn as_located<'a, E>(error: &'a (dyn Error + 'static)) -> Option<&'a dyn Located>
+where
+    E: Located + Error + 'static,
+{
+    error.downcast_ref::<E>().map(|error| error as &dyn Located)
+}
+
+/// Register `E` so that its [`Located::location`] can be found wherever it
+/// appears in an error chain
+///
+/// [`Wrapped`] registers itself when it is created, so only other
+/// implementors need to be registered here.
+///
+/// [`Wrapped`]: crate::Wrapped
+pub fn register_located<E>()
+where
+    E: Located + Error + 'static,
+{
+    LOCATED.register(TypeId::of::<E>(), as_located::<E>);
+}
+
+/// Determine the location an arbitrary error was created at, if its type is a
+/// registered implementor of [`Located`] which recorded one
+pub fn location_of(error: &(dyn Error + 'static)) -> Option<&'static Location<'static>> {
+    LOCATED.find(|_, f| f(error)).and_then(Located::location)
+}
+
+fn as_related<'a, E>(error: &'a (dyn Error + 'static)) -> Option<&'a [RelatedError]>
+where
+    E: Error + 'static,
+{
+    error
+        .downcast_ref::<Wrapped<E>>()
+        .map(|wrapped| &*wrapped.related)
+}
+
+/// Register `Wrapped<E>` so that its related errors can be found wherever it
+/// appears in an error chain
+///
+/// Done by [`Wrapped::push_related`], so that every wrapper with related
+/// errors is registered by the time it is serialized.
+pub(crate) fn register_related<E>()
+where
+    E: Error + 'static,
+{
+    RELATED.register(TypeId::of::<E>(), as_related::<E>);
+}
+
+/// The secondary errors attached to an arbitrary error, if it is a
+/// [`Wrapped`] with any
+pub(crate) fn related_of<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a [RelatedError]> {
+    RELATED
+        .find(|_, f| f(error))
+        .filter(|related| !related.is_empty())
+}
+
+fn reconstruct_as<E>(msg: &str) -> Option<Arc<dyn Error + Send + Sync + 'static>>
+where
+    E: ReconstructibleError,
+{
+    E::from_parts(msg).map(|error| Arc::new(error) as Arc<dyn Error + Send + Sync + 'static>)
+}
+
+/// Register `E` so that the deserializer rebuilds it wherever its type name
+/// appears in a serialized chain
+///
+/// This also registers its type name as [`register_type`] does, so that it is
+/// recorded when `E` is serialized as part of a chain.
+pub fn register_reconstructible<E>()
+where
+    E: ReconstructibleError,
+{
+    register_type::<E>();
+
+    RECONSTRUCT.register(std::any::type_name::<E>(), reconstruct_as::<E>);
+}
+
+/// Rebuild an error read by the deserializer, if `type_name` belongs to a
+/// registered implementor of [`ReconstructibleError`] which accepts `msg`
+pub(crate) fn reconstruct(
+    type_name: Option<&str>,
+    msg: &str,
+) -> Option<Arc<dyn Error + Send + Sync + 'static>> {
+    let type_name = type_name?;
+    RECONSTRUCT
+        .find(|&name, f| Some(f).filter(|_| name == type_name))
+        .and_then(|f| f(msg))
+}
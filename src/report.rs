@@ -0,0 +1,898 @@
+//! Human readable reports of error chains
+//!
+//! These render a chain the way `anyhow` and `eyre` print errors returned from
+//! `main`, so that binaries don't each need their own loop over `source()`.
+//!
+//! With the `std-report` feature, a [`Report`] converts into the standard
+//! library's `std::error::Report`, and `ErrTools::std_report` creates one
+//! directly.
+use crate::backtrace::deepest_backtrace;
+use crate::chain::Chain;
+use crate::ErrTools;
+use once_cell::sync::Lazy;
+use std::error::Error;
+use std::fmt::{self, Write};
+use std::io;
+use std::process::{ExitCode, Termination};
+
+/// A report of an error chain, in a short and a long form
+///
+/// `{}` writes the messages of the chain on one line, joined with `": "` like
+/// [`OneLine`]. `{:#}` writes the outermost message on the first line,
+/// followed by a `Caused by:` section listing the message of every source on
+/// its own numbered line. Sources whose message spans several lines have the
+/// rest of their lines indented to line up with the first. If backtraces are
+/// enabled, see [`BacktracePreference`], and any error in the chain has one,
+/// the innermost one is appended in a `Stack backtrace:` section.
+///
+/// With the `color` feature, messages are written in red and headers dimmed
+/// when asked to with [`Report::color`], for instance with the result of
+/// [`color_from_env`].
+///
+/// ```rust
+/// use errtools::deserialize::Error;
+/// use errtools::ErrTools;
+///
+/// let err = Error::builder()
+///     .msg("unable to load config")
+///     .causes(["unable to read file", "permission denied"])
+///     .build();
+///
+/// assert_eq!(
+///     err.report().to_string(),
+///     "unable to load config: unable to read file: permission denied"
+/// );
+/// assert_eq!(
+///     format!("{:#}", err.report()),
+///     "unable to load config\n\
+///      \n\
+///      Caused by:\n    \
+///          0: unable to read file\n    \
+///          1: permission denied"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Report<'a> {
+    error: &'a (dyn Error + 'static),
+    options: ReportOptions<'static>,
+}
+
+impl<'a> Report<'a> {
+    /// Create a report of `error` and its sources
+    ///
+    /// Whether the backtrace is included follows `RUST_BACKTRACE`, see
+    /// [`BacktracePreference::from_env`].
+    pub fn new(error: &'a (dyn Error + 'static)) -> Self {
+        Self {
+            error,
+            options: ReportOptions::new(),
+        }
+    }
+
+    /// Whether the multi-line form includes a backtrace, regardless of
+    /// `RUST_BACKTRACE`
+    pub fn backtrace(mut self, backtrace: BacktracePreference) -> Self {
+        self.options = self.options.backtrace(backtrace);
+        self
+    }
+
+    /// Whether the report is styled with ANSI escape sequences, which it isn't
+    /// by default
+    #[cfg(feature = "color")]
+    pub fn color(mut self, color: bool) -> Self {
+        self.options = self.options.color(color);
+        self
+    }
+
+    /// Whether each message is prefixed with the type name of its error, see
+    /// [`ReportOptions::type_names`]
+    pub fn type_names(mut self, type_names: bool) -> Self {
+        self.options = self.options.type_names(type_names);
+        self
+    }
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = if f.alternate() {
+            self.options
+        } else {
+            self.options.layout(ReportLayout::OneLine)
+        };
+        write_report(self.error, f, &options)
+    }
+}
+
+/// The standard library's multi-line report of the same chain, with the
+/// backtrace if this report would include one
+///
+/// See [`ErrTools::std_report`] for how the two formats differ.
+#[cfg(feature = "std-report")]
+impl<'a> From<Report<'a>> for std::error::Report<&'a (dyn Error + 'static)> {
+    fn from(report: Report<'a>) -> Self {
+        std::error::Report::new(report.error)
+            .pretty(true)
+            .show_backtrace(report.options.backtrace != BacktracePreference::Never)
+    }
+}
+
+/// How [`write_report`] renders an error chain
+///
+/// The defaults are the multi-line layout of `{:#}` on a [`Report`], with
+/// the backtrace following the environment like [`Report::new`] and no
+/// colors.
+#[derive(Debug, Clone, Copy)]
+pub struct ReportOptions<'a> {
+    layout: ReportLayout,
+    separator: &'a str,
+    backtrace: BacktracePreference,
+    type_names: bool,
+    #[cfg(feature = "color")]
+    color: bool,
+}
+
+/// The ways of laying out the messages of a chain in a report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportLayout {
+    /// The messages on one line, joined with a separator, as written by
+    /// [`OneLine`] and `{}` on a [`Report`]
+    OneLine,
+    /// The outermost message followed by a `Caused by:` section, as written by
+    /// `{:#}` on a [`Report`]
+    MultiLine,
+    /// Every message on its own numbered line, as written by [`numbered`]
+    Numbered,
+}
+
+impl ReportOptions<'static> {
+    /// The default options, with the backtrace following the environment
+    pub fn new() -> Self {
+        Self {
+            backtrace: BacktracePreference::from_env(),
+            ..Self::plain(ReportLayout::MultiLine)
+        }
+    }
+
+    /// Options for `layout` without colors or a backtrace
+    const fn plain(layout: ReportLayout) -> Self {
+        Self {
+            layout,
+            separator: ": ",
+            backtrace: BacktracePreference::Never,
+            type_names: false,
+            #[cfg(feature = "color")]
+            color: false,
+        }
+    }
+}
+
+impl<'a> ReportOptions<'a> {
+    /// How the messages of the chain are laid out
+    pub const fn layout(mut self, layout: ReportLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// The separator between messages in the one-line layout, `": "` by
+    /// default
+    pub const fn separator(self, separator: &str) -> ReportOptions<'_> {
+        ReportOptions {
+            layout: self.layout,
+            separator,
+            backtrace: self.backtrace,
+            type_names: self.type_names,
+            #[cfg(feature = "color")]
+            color: self.color,
+        }
+    }
+
+    /// Whether the multi-line layout includes a backtrace
+    pub const fn backtrace(mut self, backtrace: BacktracePreference) -> Self {
+        self.backtrace = backtrace;
+        self
+    }
+
+    /// Whether the one-line and multi-line layouts write each message as
+    /// `TypeName: message` when the type name of its error is known
+    ///
+    /// The type name is the one recorded by a [`deserialize::Error`], which
+    /// tells apart errors received from another service, or else that of a
+    /// type known to the [`registry`]. Errors without one are written as just
+    /// their message.
+    ///
+    /// ```rust
+    /// use errtools::deserialize::Error;
+    /// use errtools::ErrTools;
+    ///
+    /// let err = Error::builder()
+    ///     .type_name("app::LoadError")
+    ///     .msg("unable to load config")
+    ///     .causes(["permission denied"])
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     err.report().type_names(true).to_string(),
+    ///     "app::LoadError: unable to load config: permission denied"
+    /// );
+    /// ```
+    ///
+    /// [`deserialize::Error`]: crate::deserialize::Error
+    /// [`registry`]: crate::registry
+    pub const fn type_names(mut self, type_names: bool) -> Self {
+        self.type_names = type_names;
+        self
+    }
+
+    /// Whether the one-line and multi-line layouts are styled with ANSI escape
+    /// sequences
+    #[cfg(feature = "color")]
+    pub const fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    fn styles(&self) -> &'static Styles {
+        #[cfg(feature = "color")]
+        {
+            if self.color {
+                return &color::STYLES;
+            }
+        }
+        &Styles::PLAIN
+    }
+}
+
+impl Default for ReportOptions<'static> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Write a report of `error` and its sources to `out`
+///
+/// This is what [`Report`], [`OneLine`], and [`numbered`] write, for writing
+/// into an existing buffer without formatting into a new `String` first.
+///
+/// ```rust
+/// use errtools::deserialize::Error;
+/// use errtools::report::{self, ReportLayout, ReportOptions};
+///
+/// let err = Error::builder()
+///     .msg("unable to load config")
+///     .causes(["permission denied"])
+///     .build();
+///
+/// let mut out = String::new();
+/// let options = ReportOptions::new().layout(ReportLayout::OneLine);
+/// report::write_report(&err, &mut out, &options).unwrap();
+/// assert_eq!(out, "unable to load config: permission denied");
+/// ```
+pub fn write_report(
+    error: &(dyn Error + 'static),
+    out: &mut dyn Write,
+    options: &ReportOptions<'_>,
+) -> fmt::Result {
+    let styles = options.styles();
+    match options.layout {
+        ReportLayout::OneLine => {
+            out.write_str(styles.message)?;
+            write_one_line(error, out, options)?;
+            out.write_str(styles.reset)
+        }
+        ReportLayout::MultiLine => write_multi_line(error, out, options),
+        ReportLayout::Numbered => write_numbered(error, out),
+    }
+}
+
+/// Write a report of `error` and its sources to `out`, like [`write_report`]
+pub fn write_report_io(
+    error: &(dyn Error + 'static),
+    out: &mut dyn io::Write,
+    options: &ReportOptions<'_>,
+) -> io::Result<()> {
+    let mut adapter = IoWriter {
+        inner: out,
+        error: None,
+    };
+    match write_report(error, &mut adapter, options) {
+        Ok(()) => Ok(()),
+        Err(fmt::Error) => Err(adapter
+            .error
+            .unwrap_or_else(|| io::Error::new(io::ErrorKind::Other, "formatter error"))),
+    }
+}
+
+/// Writes formatted text to an `io::Write`, keeping the error that stopped
+/// it, since `fmt::Error` can't carry one
+struct IoWriter<'a> {
+    inner: &'a mut dyn io::Write,
+    error: Option<io::Error>,
+}
+
+impl Write for IoWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+fn write_multi_line(
+    error: &(dyn Error + 'static),
+    out: &mut dyn Write,
+    options: &ReportOptions<'_>,
+) -> fmt::Result {
+    let styles = options.styles();
+    let labeled = Labeled::new(error, options);
+    write!(out, "{}{}{}", styles.message, labeled, styles.reset)?;
+
+    let mut sources = Chain::sources(error).enumerate().peekable();
+    if sources.peek().is_some() {
+        write!(out, "\n\n{}Caused by:{}", styles.header, styles.reset)?;
+    }
+    for (ind, error) in sources {
+        write!(out, "\n    {}: {}", ind, styles.message)?;
+        let labeled = Labeled::new(error, options);
+        write!(Indented::new(out, 4 + digits(ind) + 2), "{}", labeled)?;
+        out.write_str(styles.reset)?;
+    }
+
+    let backtrace = match options.backtrace {
+        BacktracePreference::Never => None,
+        _ => deepest_backtrace(error),
+    };
+    let backtrace = match backtrace {
+        Some(backtrace) => backtrace,
+        None => return Ok(()),
+    };
+    write!(
+        out,
+        "\n\n{}Stack backtrace:{}\n",
+        styles.header, styles.reset
+    )?;
+    match options.backtrace {
+        BacktracePreference::Full => write!(out, "{:#}", backtrace),
+        _ => write!(out, "{}", backtrace),
+    }
+}
+
+fn write_one_line(
+    error: &(dyn Error + 'static),
+    out: &mut dyn Write,
+    options: &ReportOptions<'_>,
+) -> fmt::Result {
+    let mut out = Separated {
+        inner: out,
+        separator: options.separator,
+        started: false,
+        pending: false,
+    };
+    for error in Chain::new(error) {
+        out.pending = out.started;
+        write!(out, "{}", Labeled::new(error, options))?;
+    }
+    Ok(())
+}
+
+/// The escape sequences written around each part of a report
+struct Styles {
+    message: &'static str,
+    header: &'static str,
+    reset: &'static str,
+}
+
+impl Styles {
+    const PLAIN: Styles = Styles {
+        message: "",
+        header: "",
+        reset: "",
+    };
+}
+
+#[cfg(feature = "color")]
+pub use color::color_from_env;
+
+#[cfg(feature = "color")]
+mod color {
+    use super::Styles;
+    use once_cell::sync::Lazy;
+
+    pub(super) const STYLES: Styles = Styles {
+        message: "\x1b[31m",
+        header: "\x1b[2m",
+        reset: "\x1b[0m",
+    };
+
+    /// Whether stderr is a terminal and `NO_COLOR` isn't set, read once per
+    /// process
+    ///
+    /// Reports are never colored unless asked to, since they are also written
+    /// to logs and other places where escape sequences would end up verbatim.
+    /// Pass this to [`Report::color`] or [`ReportOptions::color`] to color
+    /// reports printed to a terminal:
+    ///
+    /// ```rust
+    /// use errtools::report::color_from_env;
+    /// use errtools::ErrTools;
+    ///
+    /// let err = std::io::Error::new(std::io::ErrorKind::Other, "disk full");
+    /// eprintln!("{:#}", err.report().color(color_from_env()));
+    /// ```
+    ///
+    /// [`Report::color`]: super::Report::color
+    /// [`ReportOptions::color`]: super::ReportOptions::color
+    pub fn color_from_env() -> bool {
+        static FROM_ENV: Lazy<bool> = Lazy::new(|| {
+            let no_color = std::env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty());
+            !no_color && stderr_is_terminal()
+        });
+
+        *FROM_ENV
+    }
+
+    #[cfg(unix)]
+    fn stderr_is_terminal() -> bool {
+        // SAFETY: isatty only inspects the file descriptor
+        unsafe { libc::isatty(libc::STDERR_FILENO) == 1 }
+    }
+
+    #[cfg(not(unix))]
+    fn stderr_is_terminal() -> bool {
+        false
+    }
+}
+
+/// An error returned from `main`, printed as a multi-line [`Report`]
+///
+/// `main` prints the `Debug` output of the errors it returns, which for most
+/// error types is a dump of their fields. Anything `?` can turn into a
+/// `Box<dyn Error + Send + Sync>` converts into a `MainReport`, whose `Debug`
+/// output is the `{:#}` form of its [`Report`] instead, including the
+/// backtrace according to `RUST_BACKTRACE`.
+///
+/// It doesn't implement `Error` itself, so that these conversions don't
+/// overlap with the conversion of a `MainReport` into itself.
+///
+/// ```rust,no_run
+/// use errtools::MainReport;
+///
+/// fn main() -> Result<(), MainReport> {
+///     let config = std::fs::read_to_string("config.toml")?;
+///     println!("{}", config);
+///     Ok(())
+/// }
+/// ```
+///
+/// `main` exits with code 1 when it returns an error. To exit with the code
+/// the chain provides through [`ExitStatus`] instead, use the `Termination`
+/// impl, which prints the report the same way before exiting with
+/// [`MainReport::exit_code`].
+///
+/// ```rust,no_run
+/// use errtools::MainReport;
+/// use std::process::{ExitCode, Termination};
+///
+/// fn run() -> Result<(), MainReport> {
+///     let config = std::fs::read_to_string("config.toml")?;
+///     println!("{}", config);
+///     Ok(())
+/// }
+///
+/// fn main() -> ExitCode {
+///     match run() {
+///         Ok(()) => ExitCode::SUCCESS,
+///         Err(report) => Termination::report(report),
+///     }
+/// }
+/// ```
+///
+/// [`ExitStatus`]: crate::ExitStatus
+pub struct MainReport(Box<dyn Error + Send + Sync + 'static>);
+
+impl MainReport {
+    /// The [`Report`] written as the `Debug` output
+    pub fn report(&self) -> Report<'_> {
+        Report::new(&*self.0)
+    }
+
+    /// The exit code of the outermost error in the chain which has one, or 1
+    ///
+    /// See [`ExitStatus`](crate::ExitStatus) for how errors provide exit
+    /// codes.
+    pub fn exit_code(&self) -> u8 {
+        self.0.exit_code_in_chain().unwrap_or(1)
+    }
+
+    /// The error being reported
+    pub fn into_inner(self) -> Box<dyn Error + Send + Sync + 'static> {
+        self.0
+    }
+}
+
+impl<E> From<E> for MainReport
+where
+    E: Into<Box<dyn Error + Send + Sync + 'static>>,
+{
+    fn from(error: E) -> Self {
+        Self(error.into())
+    }
+}
+
+impl fmt::Debug for MainReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#}", self.report())
+    }
+}
+
+impl Termination for MainReport {
+    fn report(self) -> ExitCode {
+        eprintln!("Error: {:?}", self);
+        ExitCode::from(self.exit_code())
+    }
+}
+
+/// Whether a [`Report`] includes the backtrace of the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BacktracePreference {
+    /// Leave the backtrace out
+    Never,
+    /// Include the backtrace, with the frames of the runtime and of the
+    /// backtrace capture itself filtered out
+    Always,
+    /// Include every frame of the backtrace
+    Full,
+}
+
+impl BacktracePreference {
+    /// The preference set by `RUST_BACKTRACE`, read once per process
+    ///
+    /// Like the standard library, `full` includes every frame, `0` or an
+    /// unset variable leaves the backtrace out, and any other value includes
+    /// it.
+    pub fn from_env() -> Self {
+        static FROM_ENV: Lazy<BacktracePreference> =
+            Lazy::new(|| match std::env::var_os("RUST_BACKTRACE") {
+                None => BacktracePreference::Never,
+                Some(value) if value == "0" => BacktracePreference::Never,
+                Some(value) if value == "full" => BacktracePreference::Full,
+                Some(_) => BacktracePreference::Always,
+            });
+
+        *FROM_ENV
+    }
+}
+
+impl Default for BacktracePreference {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+/// The message of an error, prefixed with its type name if that was asked
+/// for and is known
+struct Labeled<'a> {
+    error: &'a (dyn Error + 'static),
+    type_name: Option<&'a str>,
+}
+
+impl<'a> Labeled<'a> {
+    fn new(error: &'a (dyn Error + 'static), options: &ReportOptions<'_>) -> Self {
+        let type_name = if options.type_names {
+            known_type_name(error)
+        } else {
+            None
+        };
+        Self { error, type_name }
+    }
+}
+
+impl fmt::Display for Labeled<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.type_name {
+            Some(type_name) => write!(f, "{}: {}", type_name, self.error),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+/// The type name recorded by a deserialized error, or else that of a
+/// registered type
+pub(crate) fn known_type_name<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a str> {
+    crate::deserialize::type_name_of(error).or_else(|| crate::registry::type_name_of(error))
+}
+
+/// An error chain on a single line, with its messages joined by a separator
+///
+/// Errors with an empty message are skipped, so that they don't leave two
+/// separators next to each other. Separators within the messages themselves
+/// are written as they are.
+///
+/// ```rust
+/// use errtools::deserialize::Error;
+/// use errtools::report::OneLine;
+/// use errtools::ErrTools;
+///
+/// let err = Error::builder()
+///     .msg("unable to load config")
+///     .causes(["", "permission denied"])
+///     .build();
+///
+/// assert_eq!(
+///     err.to_one_line().to_string(),
+///     "unable to load config: permission denied"
+/// );
+/// assert_eq!(
+///     OneLine::new(&err, " <- ").to_string(),
+///     "unable to load config <- permission denied"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct OneLine<'a> {
+    error: &'a (dyn Error + 'static),
+    separator: &'a str,
+}
+
+impl<'a> OneLine<'a> {
+    /// Join the messages of `error` and its sources with `separator`
+    pub fn new(error: &'a (dyn Error + 'static), separator: &'a str) -> Self {
+        Self { error, separator }
+    }
+}
+
+impl fmt::Display for OneLine<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = ReportOptions::plain(ReportLayout::OneLine).separator(self.separator);
+        write_report(self.error, f, &options)
+    }
+}
+
+/// The messages of an error chain on numbered lines, one error per line
+///
+/// The index is padded to the width of the largest one so that the messages
+/// line up, and continuation lines of multi-line messages are indented to
+/// match.
+///
+/// ```rust
+/// use errtools::deserialize::Error;
+/// use errtools::report;
+///
+/// let err = Error::builder()
+///     .msg("unable to load config")
+///     .causes(["unable to read file", "permission denied"])
+///     .build();
+///
+/// assert_eq!(
+///     report::numbered(&err).to_string(),
+///     "0: unable to load config\n\
+///      1: unable to read file\n\
+///      2: permission denied"
+/// );
+/// ```
+pub fn numbered(error: &dyn Error) -> impl fmt::Display + '_ {
+    Numbered(error)
+}
+
+/// Write the numbered report of [`numbered`] to `out`
+pub fn write_numbered(error: &dyn Error, out: &mut (impl Write + ?Sized)) -> fmt::Result {
+    let len = Chain::sources(error).count() + 1;
+    let width = digits(len - 1);
+
+    write_line(out, width, 0, error)?;
+    for (ind, error) in Chain::sources(error).enumerate() {
+        out.write_char('\n')?;
+        write_line(out, width, ind + 1, error)?;
+    }
+    Ok(())
+}
+
+fn write_line(
+    out: &mut (impl Write + ?Sized),
+    width: usize,
+    ind: usize,
+    error: &dyn Error,
+) -> fmt::Result {
+    write!(out, "{:>1$}: ", ind, width)?;
+    write!(Indented::new(out, width + 2), "{}", error)
+}
+
+struct Numbered<'a>(&'a dyn Error);
+
+impl fmt::Display for Numbered<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_numbered(self.0, f)
+    }
+}
+
+/// Compare the messages of an error chain against the ones expected at each
+/// depth
+///
+/// Returns `None` if they are the same.
+///
+/// ```rust
+/// use errtools::deserialize::Error;
+/// use errtools::report;
+///
+/// let err = Error::builder()
+///     .msg("unable to load config")
+///     .causes(["unable to read file", "permission denied"])
+///     .build();
+///
+/// let diff = report::diff(&err, &["unable to load config", "file not found"]).unwrap();
+/// assert_eq!(
+///     diff.to_string(),
+///     "  0: \"unable to load config\"\n\
+///      ! 1: \"unable to read file\", expected \"file not found\"\n\
+///      + 2: \"permission denied\", not expected"
+/// );
+/// ```
+pub fn diff(actual: &dyn Error, expected: &[&str]) -> Option<ChainDiff> {
+    let actual = std::iter::once(actual.to_string())
+        .chain(Chain::sources(actual).map(|error| error.to_string()))
+        .collect::<Vec<_>>();
+
+    let depths = actual.len().max(expected.len());
+    let entries = (0..depths)
+        .map(|depth| match (actual.get(depth), expected.get(depth)) {
+            (Some(actual), Some(&expected)) if actual == expected => {
+                DiffEntry::Same(actual.clone())
+            }
+            (Some(actual), Some(&expected)) => DiffEntry::Mismatch {
+                actual: actual.clone(),
+                expected: expected.to_string(),
+            },
+            (Some(actual), None) => DiffEntry::Extra(actual.clone()),
+            (None, Some(&expected)) => DiffEntry::Missing(expected.to_string()),
+            (None, None) => unreachable!("depth is within one of the chains"),
+        })
+        .collect::<Vec<_>>();
+
+    if entries
+        .iter()
+        .all(|entry| matches!(entry, DiffEntry::Same(_)))
+    {
+        None
+    } else {
+        Some(ChainDiff { entries })
+    }
+}
+
+/// Panic with the [`ChainDiff`] of `actual` if its messages aren't the
+/// `expected` ones
+///
+/// ```rust,should_panic
+/// use errtools::deserialize::Error;
+/// use errtools::report;
+///
+/// let err = Error::builder()
+///     .msg("unable to load config")
+///     .causes(["permission denied"])
+///     .build();
+///
+/// report::assert_chain_eq(&err, &["unable to load config"]);
+/// ```
+#[track_caller]
+pub fn assert_chain_eq(actual: &dyn Error, expected: &[&str]) {
+    if let Some(diff) = diff(actual, expected) {
+        panic!("error chain doesn't match the expected messages:\n{}", diff);
+    }
+}
+
+/// The differences between the messages of an error chain and the expected
+/// ones, as found by [`diff`]
+///
+/// Each depth is written on its own line, marked with `!` if the messages
+/// differ, `+` if the chain has an error that wasn't expected, and `-` if an
+/// expected error is missing from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainDiff {
+    entries: Vec<DiffEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DiffEntry {
+    Same(String),
+    Mismatch { actual: String, expected: String },
+    Extra(String),
+    Missing(String),
+}
+
+impl fmt::Display for ChainDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = digits(self.entries.len() - 1);
+        for (depth, entry) in self.entries.iter().enumerate() {
+            if depth > 0 {
+                f.write_char('\n')?;
+            }
+            match entry {
+                DiffEntry::Same(actual) => write!(f, "  {:>2$}: {:?}", depth, actual, width)?,
+                DiffEntry::Mismatch { actual, expected } => write!(
+                    f,
+                    "! {:>3$}: {:?}, expected {:?}",
+                    depth, actual, expected, width
+                )?,
+                DiffEntry::Extra(actual) => {
+                    write!(f, "+ {:>2$}: {:?}, not expected", depth, actual, width)?
+                }
+                DiffEntry::Missing(expected) => write!(
+                    f,
+                    "- {:>2$}: missing, expected {:?}",
+                    depth, expected, width
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes `separator` before the first text of a message, unless nothing has
+/// been written yet, so that empty messages write nothing at all
+struct Separated<'a, W: ?Sized> {
+    inner: &'a mut W,
+    separator: &'a str,
+    /// Whether any message has been written
+    started: bool,
+    /// Whether a separator is owed before the next text
+    pending: bool,
+}
+
+impl<W: Write + ?Sized> Write for Separated<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.is_empty() {
+            return Ok(());
+        }
+        if self.pending {
+            self.inner.write_str(self.separator)?;
+            self.pending = false;
+        }
+        self.started = true;
+        self.inner.write_str(s)
+    }
+}
+
+/// The number of decimal digits in `n`
+fn digits(mut n: usize) -> usize {
+    let mut digits = 1;
+    while n >= 10 {
+        n /= 10;
+        digits += 1;
+    }
+    digits
+}
+
+/// Indents every line written through it after the first by `indent` spaces,
+/// leaving empty lines empty
+struct Indented<'a, W: ?Sized> {
+    inner: &'a mut W,
+    indent: usize,
+    line_start: bool,
+}
+
+impl<'a, W: Write + ?Sized> Indented<'a, W> {
+    fn new(inner: &'a mut W, indent: usize) -> Self {
+        Self {
+            inner,
+            indent,
+            line_start: false,
+        }
+    }
+}
+
+impl<W: Write + ?Sized> Write for Indented<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (ind, line) in s.split('\n').enumerate() {
+            if ind > 0 {
+                self.inner.write_char('\n')?;
+                self.line_start = true;
+            }
+            if !line.is_empty() {
+                if self.line_start {
+                    write!(self.inner, "{:1$}", "", self.indent)?;
+                    self.line_start = false;
+                }
+                self.inner.write_str(line)?;
+            }
+        }
+        Ok(())
+    }
+}
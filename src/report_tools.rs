@@ -0,0 +1,78 @@
+use crate::chain::Chain;
+use crate::SerializeableError;
+use std::error::Error;
+
+/// Chain helpers for error reports which don't implement `Error` themselves
+///
+/// `anyhow::Error` and `eyre::Report` only deref to `dyn Error`, so
+/// [`ErrTools`] can't be called on them directly. This trait is implemented
+/// for the reports themselves and walks their `chain()`, which includes the
+/// context added with `context` and `wrap_err`.
+///
+/// ```rust
+/// # #[cfg(feature = "anyhow")]
+/// # {
+/// use errtools::ReportTools;
+///
+/// let err = anyhow::anyhow!("connection reset").context("unable to sync");
+/// assert_eq!(err.chain_len(), 2);
+/// assert_eq!(err.root_cause().to_string(), "connection reset");
+/// # }
+/// ```
+///
+/// [`ErrTools`]: crate::ErrTools
+pub trait ReportTools {
+    /// The outermost error in the chain of type `T`
+    fn downcast_refchain<T: Error + Sized + 'static>(&self) -> Option<&T>;
+
+    /// The number of errors in the chain, including the outermost one
+    fn chain_len(&self) -> usize;
+
+    /// The innermost error of the chain
+    fn root_cause(&self) -> &(dyn Error + 'static);
+
+    /// The chain in its serializable form
+    fn serialize(&self) -> SerializeableError<'_>;
+}
+
+fn root_cause<'a>(error: &'a (dyn Error + 'static)) -> &'a (dyn Error + 'static) {
+    Chain::new(error).last().unwrap_or(error)
+}
+
+#[cfg(feature = "anyhow")]
+impl ReportTools for anyhow::Error {
+    fn downcast_refchain<T: Error + Sized + 'static>(&self) -> Option<&T> {
+        crate::downcast_refchain(self.as_ref())
+    }
+
+    fn chain_len(&self) -> usize {
+        Chain::new(self.as_ref()).count()
+    }
+
+    fn root_cause(&self) -> &(dyn Error + 'static) {
+        root_cause(self.as_ref())
+    }
+
+    fn serialize(&self) -> SerializeableError<'_> {
+        SerializeableError(self.as_ref())
+    }
+}
+
+#[cfg(feature = "eyre")]
+impl ReportTools for eyre::Report {
+    fn downcast_refchain<T: Error + Sized + 'static>(&self) -> Option<&T> {
+        crate::downcast_refchain(self.as_ref())
+    }
+
+    fn chain_len(&self) -> usize {
+        Chain::new(self.as_ref()).count()
+    }
+
+    fn root_cause(&self) -> &(dyn Error + 'static) {
+        root_cause(self.as_ref())
+    }
+
+    fn serialize(&self) -> SerializeableError<'_> {
+        SerializeableError(self.as_ref())
+    }
+}
@@ -0,0 +1,166 @@
+//! JSON Schemas for serialized error chains
+//!
+//! [`ErrorChain`] describes everything [`ErrTools::serialize`] and
+//! [`ErrTools::serialize_with`] write to human readable formats, and the
+//! [`JsonSchema`] impl of [`deserialize::Error`] describes what can be read
+//! back, which is the same set of fields. Both are recursive, with the
+//! `source` of an error referring back to the schema itself.
+//!
+//! ```rust
+//! let schema = schemars::schema_for!(errtools::schema::ErrorChain);
+//! ```
+//!
+//! [`ErrTools::serialize`]: crate::ErrTools::serialize
+//! [`ErrTools::serialize_with`]: crate::ErrTools::serialize_with
+use crate::deserialize;
+use crate::ser::FIELD_NAMES;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{ArrayValidation, InstanceType, Schema, SchemaObject, SubschemaValidation};
+use schemars::JsonSchema;
+
+/// The shape of a serialized error chain, for use with `schemars` only
+///
+/// Covers both layouts: nested errors whose `source` field holds the next
+/// error, and `{"chain": [...]}` objects listing every error.
+#[derive(Debug)]
+pub enum ErrorChain {}
+
+impl JsonSchema for ErrorChain {
+    fn schema_name() -> String {
+        "ErrorChain".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        chain_schema(gen.subschema_for::<Self>())
+    }
+}
+
+impl JsonSchema for deserialize::Error {
+    fn schema_name() -> String {
+        "Error".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        chain_schema(gen.subschema_for::<Self>())
+    }
+}
+
+/// Either layout of a chain, where `this` refers back to the schema itself
+fn chain_schema(this: Schema) -> Schema {
+    let node = object(
+        FIELD_NAMES
+            .iter()
+            .filter(|name| **name != "chain")
+            .map(|name| (*name, field(name, &this)))
+            .collect(),
+        &["msg"],
+    );
+    let chain = object(
+        vec![("v", field("v", &this)), ("chain", array(this))],
+        &["chain"],
+    );
+
+    any_of(vec![node, chain])
+}
+
+/// The schema of the field `name` of an error
+fn field(name: &str, this: &Schema) -> Schema {
+    match name {
+        "v" | "os_code" | "truncated" => typed(InstanceType::Integer),
+        "type_name" => nullable(typed(InstanceType::String)),
+        "code" | "msg" | "debug" | "display_alt" | "path" | "io_kind" | "received_from"
+        | "received_at" | "span_trace" => typed(InstanceType::String),
+        "meta" => {
+            let value = any_of(vec![
+                typed(InstanceType::String),
+                typed(InstanceType::Integer),
+                typed(InstanceType::Boolean),
+            ]);
+            let mut meta = SchemaObject {
+                instance_type: Some(InstanceType::Object.into()),
+                ..Default::default()
+            };
+            meta.object().additional_properties = Some(Box::new(value));
+            meta.into()
+        }
+        "errors" | "related" => array(this.clone()),
+        "location" => location(),
+        "backtrace" => any_of(vec![
+            typed(InstanceType::String),
+            array(frame()),
+            typed(InstanceType::Null),
+        ]),
+        "source" => nullable(this.clone()),
+        _ => unreachable!("no schema for the `{}` field", name),
+    }
+}
+
+fn frame() -> Schema {
+    object(
+        vec![
+            ("symbol", typed(InstanceType::String)),
+            ("file", nullable(typed(InstanceType::String))),
+            ("line", nullable(typed(InstanceType::Integer))),
+        ],
+        &["symbol", "file", "line"],
+    )
+}
+
+fn location() -> Schema {
+    object(
+        vec![
+            ("file", typed(InstanceType::String)),
+            ("line", typed(InstanceType::Integer)),
+            ("column", typed(InstanceType::Integer)),
+        ],
+        &["file", "line", "column"],
+    )
+}
+
+fn typed(instance_type: InstanceType) -> Schema {
+    SchemaObject {
+        instance_type: Some(instance_type.into()),
+        ..Default::default()
+    }
+    .into()
+}
+
+fn any_of(schemas: Vec<Schema>) -> Schema {
+    SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            any_of: Some(schemas),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+fn nullable(schema: Schema) -> Schema {
+    any_of(vec![schema, typed(InstanceType::Null)])
+}
+
+fn array(items: Schema) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::Array.into()),
+        array: Some(Box::new(ArrayValidation {
+            items: Some(items.into()),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+fn object(properties: Vec<(&str, Schema)>, required: &[&str]) -> Schema {
+    let mut schema = SchemaObject {
+        instance_type: Some(InstanceType::Object.into()),
+        ..Default::default()
+    };
+    let object = schema.object();
+    for (name, property) in properties {
+        object.properties.insert(name.to_string(), property);
+    }
+    object.required = required.iter().map(|name| name.to_string()).collect();
+    schema.into()
+}
@@ -0,0 +1,129 @@
+//! Sentry's exception interface for error chains
+//!
+//! [`to_sentry_exception`] builds the `exception` member of a Sentry event,
+//! ready to be serialized into the event payload. Sending the event is left
+//! to the caller.
+use crate::backtrace::{backtrace_display_of, parse_frames};
+use crate::chain::Chain;
+use crate::registry;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::error::Error;
+
+/// The `exception` member of a Sentry event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentryException {
+    /// One entry per error in the chain, root cause first
+    pub values: Vec<SentryExceptionValue>,
+}
+
+/// A single error of a [`SentryException`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentryExceptionValue {
+    /// The error's type name, or `"Error"` if it isn't known
+    pub ty: String,
+    /// The error's message
+    pub value: String,
+    /// The error's backtrace, if it captured one
+    pub stacktrace: Option<SentryStacktrace>,
+}
+
+/// A backtrace in Sentry's format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentryStacktrace {
+    /// The frames of the backtrace, oldest call first
+    pub frames: Vec<SentryFrame>,
+}
+
+/// A single frame of a [`SentryStacktrace`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentryFrame {
+    /// The demangled function name
+    pub function: String,
+    /// The source file, if known
+    pub filename: Option<String>,
+    /// The line number within `filename`, if known
+    pub lineno: Option<u32>,
+}
+
+/// Build Sentry's exception interface from an error chain
+///
+/// Sentry expects the chain ordered oldest first, so the root cause is the
+/// first value and the outermost error the last. Type names are looked up in
+/// the [`registry`], falling back to `"Error"`.
+///
+/// [`registry`]: crate::registry
+pub fn to_sentry_exception(error: &(dyn Error + 'static)) -> SentryException {
+    let mut values = Chain::new(error)
+        .map(|error| SentryExceptionValue {
+            ty: registry::type_name_of(error).unwrap_or("Error").to_string(),
+            value: error.to_string(),
+            stacktrace: backtrace_display_of(error).map(|backtrace| {
+                let mut frames = parse_frames(&backtrace.to_string())
+                    .into_iter()
+                    .map(|frame| SentryFrame {
+                        function: frame.symbol,
+                        filename: frame.file,
+                        lineno: frame.line,
+                    })
+                    .collect::<Vec<_>>();
+                frames.reverse();
+                SentryStacktrace { frames }
+            }),
+        })
+        .collect::<Vec<_>>();
+    values.reverse();
+
+    SentryException { values }
+}
+
+impl Serialize for SentryException {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut e = serializer.serialize_struct("exception", 1)?;
+        e.serialize_field("values", &self.values)?;
+        e.end()
+    }
+}
+
+impl Serialize for SentryExceptionValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut e = serializer
+            .serialize_struct("exception_value", 2 + self.stacktrace.is_some() as usize)?;
+        e.serialize_field("type", &self.ty)?;
+        e.serialize_field("value", &self.value)?;
+        match &self.stacktrace {
+            Some(stacktrace) => e.serialize_field("stacktrace", stacktrace)?,
+            None => e.skip_field("stacktrace")?,
+        }
+        e.end()
+    }
+}
+
+impl Serialize for SentryStacktrace {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut e = serializer.serialize_struct("stacktrace", 1)?;
+        e.serialize_field("frames", &self.frames)?;
+        e.end()
+    }
+}
+
+impl Serialize for SentryFrame {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut f = serializer.serialize_struct("frame", 3)?;
+        f.serialize_field("function", &self.function)?;
+        f.serialize_field("filename", &self.filename)?;
+        f.serialize_field("lineno", &self.lineno)?;
+        f.end()
+    }
+}
@@ -0,0 +1,1240 @@
+use crate::backtrace::{backtrace_display_of, parse_frames, span_trace_of, Frame};
+use crate::chain::Chain;
+use crate::config::{BacktraceMode, ChainLayout, DuplicateBacktraces, SerializeConfig};
+use crate::location::{location_parts_of, LocationParts};
+use crate::{registry, FsError, MetadataValue, MultiError, SerializedError};
+use once_cell::sync::Lazy;
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, SerializeStruct, Serializer};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::rc::Rc;
+
+pub(crate) static DEFAULT_CONFIG: Lazy<SerializeConfig> = Lazy::new(SerializeConfig::new);
+
+/// The version of the serialized format, written as the `v` field
+pub(crate) const FORMAT_VERSION: u32 = 1;
+
+/// Every field written for an error, in the order they are written, followed
+/// by the `chain` field of the flat layout
+///
+/// The deserializer recognizes exactly these, and the schemas describe them.
+pub(crate) const FIELD_NAMES: &[&str] = &[
+    "v",
+    "type_name",
+    "code",
+    "msg",
+    "meta",
+    "debug",
+    "display_alt",
+    "path",
+    "io_kind",
+    "os_code",
+    "errors",
+    "related",
+    "location",
+    "received_from",
+    "received_at",
+    "span_trace",
+    "truncated",
+    "backtrace",
+    "source",
+    "chain",
+];
+
+/// The deepest an error chain is nested before switching to the flat layout
+pub(crate) const NESTING_LIMIT: usize = 64;
+
+/// The deepest an error chain is nested in the fixed layout, which has no room
+/// for the flat one
+pub(crate) const FIXED_NESTING_LIMIT: usize = 256;
+
+///
+pub struct SerializeableError<'a>(pub(crate) &'a (dyn Error + 'static));
+
+///
+pub struct SerializeableConcreteError<'a, E>(pub(crate) &'a E)
+where
+    E: Error + Sized + 'static;
+
+/// Wrap `error` for serialization, for use inside other `Serialize` impls
+///
+/// Equivalent to [`SerializeableError::new`], without going through
+/// [`ErrTools::serialize`] whose name clashes with `Serialize::serialize`.
+///
+/// [`ErrTools::serialize`]: crate::ErrTools::serialize
+pub fn serializable<'a>(error: &'a (dyn Error + 'static)) -> SerializeableError<'a> {
+    SerializeableError(error)
+}
+
+impl Clone for SerializeableError<'_> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl Copy for SerializeableError<'_> {}
+
+impl<E> Clone for SerializeableConcreteError<'_, E>
+where
+    E: Error + Sized + 'static,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E> Copy for SerializeableConcreteError<'_, E> where E: Error + Sized + 'static {}
+
+/// An owned error which serializes as its chain
+///
+/// Unlike the wrappers returned by [`ErrTools::serialize`] it doesn't borrow
+/// the error, so it can be stored in types deriving `Serialize`:
+///
+/// ```rust
+/// use errtools::Serializable;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Event {
+///     name: String,
+///     error: Serializable<std::io::Error>,
+/// }
+/// ```
+///
+/// [`ErrTools::serialize`]: crate::ErrTools::serialize
+pub struct Serializable<E>(pub E)
+where
+    E: Error + 'static;
+
+impl<'a> SerializeableError<'a> {
+    /// Wrap `error` for serialization, like [`ErrTools::serialize`] does for
+    /// trait objects
+    ///
+    /// [`ErrTools::serialize`]: crate::ErrTools::serialize
+    pub fn new(error: &'a (dyn Error + 'static)) -> Self {
+        SerializeableError(error)
+    }
+
+    /// The wrapped error
+    pub fn inner(&self) -> &'a (dyn Error + 'static) {
+        self.0
+    }
+
+    /// Copy the chain into a [`SerializedError`] which no longer borrows it
+    pub fn to_owned(&self) -> SerializedError {
+        SerializedError::new(self.0, registry::type_name_of(self.0))
+    }
+}
+
+impl<'a, E> SerializeableConcreteError<'a, E>
+where
+    E: Error + Sized + 'static,
+{
+    /// Wrap `error` for serialization, like [`ErrTools::serialize`] does for
+    /// concrete errors
+    ///
+    /// [`ErrTools::serialize`]: crate::ErrTools::serialize
+    pub fn new(error: &'a E) -> Self {
+        SerializeableConcreteError(error)
+    }
+
+    /// The wrapped error
+    pub fn inner(&self) -> &'a E {
+        self.0
+    }
+
+    /// Copy the chain into a [`SerializedError`] which no longer borrows it
+    pub fn to_owned(&self) -> SerializedError {
+        SerializedError::new(self.0, Some(std::any::type_name::<E>()))
+    }
+}
+
+/// An error chain paired with the [`SerializeConfig`] used to serialize it
+///
+/// Created by [`ErrTools::serialize_with`].
+///
+/// [`ErrTools::serialize_with`]: crate::ErrTools::serialize_with
+pub struct ConfiguredSerializeableError<'a> {
+    node: Node<'a>,
+}
+
+impl<'a> ConfiguredSerializeableError<'a> {
+    pub(crate) fn new(
+        error: &'a (dyn Error + 'static),
+        type_name: Option<&'static str>,
+        config: &'a SerializeConfig,
+    ) -> Self {
+        Self {
+            node: Node::root(error, type_name, config),
+        }
+    }
+}
+
+/// A single element of an error chain
+#[derive(Clone)]
+pub(crate) struct Node<'a> {
+    error: &'a (dyn Error + 'static),
+    type_name: Option<&'static str>,
+    config: &'a SerializeConfig,
+    depth: usize,
+    /// The depth at which the chain first repeats an earlier element, if it
+    /// is cyclic
+    cycle: Option<usize>,
+    /// Whether the backtrace of each element of the chain this node belongs
+    /// to also belongs to a later element, unless duplicates are kept
+    duplicates: Option<Rc<[bool]>>,
+    /// The depth of the first element of the chain this node belongs to
+    start: usize,
+}
+
+impl<'a> Node<'a> {
+    pub(crate) fn root(
+        error: &'a (dyn Error + 'static),
+        type_name: Option<&'static str>,
+        config: &'a SerializeConfig,
+    ) -> Self {
+        Node {
+            error,
+            type_name,
+            config,
+            depth: 0,
+            cycle: find_cycle(error),
+            duplicates: None,
+            start: 0,
+        }
+        .with_duplicates()
+    }
+
+    /// Find the duplicate backtraces of the chain starting at this node, if
+    /// `duplicate_backtraces` asks for them to be replaced
+    ///
+    /// Backtraces count as duplicates if they are the same object or render
+    /// to the same string as that of an error further down the serialized
+    /// chain. Each one is rendered at most once.
+    fn with_duplicates(mut self) -> Self {
+        if self.config.duplicate_backtraces == DuplicateBacktraces::Keep {
+            return self;
+        }
+
+        let backtraces = std::iter::successors(Some(self.clone()), Node::source)
+            .map(|node| backtrace_display_of(node.error))
+            .collect::<Vec<_>>();
+        let mut duplicates = vec![false; backtraces.len()];
+        let mut addrs = HashSet::new();
+        let mut rendered = HashSet::new();
+        for (ind, backtrace) in backtraces.into_iter().enumerate().rev() {
+            if let Some(backtrace) = backtrace {
+                let addr = backtrace as *const dyn fmt::Display as *const ();
+                duplicates[ind] = !addrs.insert(addr) || !rendered.insert(backtrace.to_string());
+            }
+        }
+
+        self.duplicates = Some(duplicates.into());
+        self
+    }
+
+    /// The error at this element of the chain
+    pub(crate) fn error(&self) -> &'a (dyn Error + 'static) {
+        self.error
+    }
+
+    /// Whether the chain this node starts repeats an earlier element
+    pub(crate) fn is_cyclic(&self) -> bool {
+        self.cycle.is_some()
+    }
+
+    fn type_name(&self) -> Option<&'static str> {
+        self.type_name.filter(|_| self.config.include_type_names)
+    }
+
+    /// The next element of the chain, unless `max_depth` has been reached or
+    /// it repeats an earlier element
+    pub(crate) fn source(&self) -> Option<Node<'a>> {
+        let depth = self.depth + 1;
+        self.error
+            .source()
+            .filter(|_| self.config.max_depth.map_or(true, |max| depth < max))
+            .filter(|_| self.cycle != Some(depth))
+            .map(|source| Node {
+                error: source,
+                type_name: registry::type_name_of(source),
+                config: self.config,
+                depth,
+                cycle: self.cycle,
+                duplicates: self.duplicates.clone(),
+                start: self.start,
+            })
+    }
+
+    /// Apply the configured redactor, if any, to `text`
+    fn redact<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        match &self.config.redactor {
+            Some(redactor) => redactor.redact(text),
+            None => Cow::Borrowed(text),
+        }
+    }
+
+    /// Prepare a string field for serialization, redacting it and capping its
+    /// length to `max_len` bytes if necessary
+    fn text<'t>(&self, text: Text<'t>, max_len: Option<usize>) -> Text<'t> {
+        if self.config.redactor.is_none() && max_len.is_none() {
+            return text;
+        }
+
+        Text::Owned(self.process(&text.to_string(), max_len))
+    }
+
+    /// Prepare a backtrace for serialization as a string, replacing the
+    /// configured path prefixes before redacting and capping it
+    fn backtrace_text<'t>(&self, backtrace: &'t (dyn fmt::Display + 'static)) -> Text<'t> {
+        let max_len = self.config.max_backtrace_len;
+        if self.config.backtrace_prefixes.is_empty() {
+            return self.text(Text::Display(backtrace), max_len);
+        }
+
+        let text = backtrace.to_string();
+        let text = self.config.strip_backtrace_prefixes(&text);
+        Text::Owned(self.process(&text, max_len))
+    }
+
+    /// Redact `text` and cap its length to `max_len` bytes
+    fn process(&self, text: &str, max_len: Option<usize>) -> String {
+        let text = self.redact(text);
+        let text = match max_len {
+            Some(max_len) => truncate(&text, max_len),
+            None => Cow::Borrowed(&*text),
+        };
+        text.into_owned()
+    }
+
+    /// The location of the error, with the configured path prefixes of its
+    /// file replaced before redacting it
+    fn location(&self) -> Option<LocationParts> {
+        let location = location_parts_of(self.error)?;
+        if self.config.redactor.is_none() && self.config.backtrace_prefixes.is_empty() {
+            return Some(location);
+        }
+
+        Some(location.map_file(|file| {
+            let file = self.config.strip_backtrace_prefixes(file);
+            self.process(&file, None)
+        }))
+    }
+
+    /// Whether this node's backtrace also belongs to an error further down the
+    /// serialized chain and should be replaced according to
+    /// `duplicate_backtraces`
+    fn has_duplicate_backtrace(&self) -> bool {
+        match &self.duplicates {
+            Some(duplicates) => duplicates[self.depth - self.start],
+            None => false,
+        }
+    }
+
+    /// The number of errors following this one that were cut off by `max_depth`
+    fn truncated(&self) -> usize {
+        match (self.config.max_depth, self.cycle) {
+            (Some(max), Some(cycle)) if self.depth + 1 >= max => cycle - self.depth - 1,
+            (Some(max), None) if self.depth + 1 >= max => Chain::sources(self.error).count(),
+            _ => 0,
+        }
+    }
+
+    /// The alternate `Display` output of the error, if it was requested and
+    /// differs from the normal one
+    fn alternate(&self) -> Option<String> {
+        if !self.config.include_alternate {
+            return None;
+        }
+
+        let alternate = format!("{:#}", self.error);
+        if alternate == self.error.to_string() {
+            return None;
+        }
+        Some(alternate)
+    }
+
+    /// The backtrace field of this node, as a string when `fixed` is set
+    fn backtrace(&self, fixed: bool) -> BacktraceField<Text<'a>> {
+        match self.config.backtraces {
+            BacktraceMode::Omit if fixed => BacktraceField::Text(None),
+            BacktraceMode::Omit => BacktraceField::Omitted,
+            _ if self.has_duplicate_backtrace() => {
+                BacktraceField::Duplicate(self.config.duplicate_backtraces.marker())
+            }
+            BacktraceMode::Frames if !fixed => {
+                let frames = backtrace_display_of(self.error).map(|backtrace| {
+                    let backtrace = backtrace.to_string();
+                    let backtrace = self.config.strip_backtrace_prefixes(&backtrace);
+                    parse_frames(&self.redact(&backtrace))
+                });
+                BacktraceField::Frames(frames)
+            }
+            _ => BacktraceField::Text(
+                backtrace_display_of(self.error).map(|backtrace| self.backtrace_text(backtrace)),
+            ),
+        }
+    }
+
+    /// Everything written for this node besides its source and the errors it
+    /// aggregates, or only what the fixed layout has room for if `fixed` is
+    /// set
+    pub(crate) fn fields(&self, fixed: bool) -> Fields<Text<'a>> {
+        let error = self.error;
+        let mut fields = Fields {
+            type_name: self.type_name().map(Text::Str),
+            code: None,
+            msg: self.text(Text::Msg(error), self.config.max_msg_len),
+            meta: None,
+            debug: None,
+            display_alt: None,
+            path: None,
+            io_kind: None,
+            os_code: None,
+            location: None,
+            received: None,
+            span_trace: None,
+            truncated: None,
+            backtrace: self.backtrace(fixed),
+        };
+        if fixed {
+            return fields;
+        }
+
+        fields.code = registry::code_of(error).map(|code| self.text(Text::Str(code), None));
+        fields.meta = registry::metadata_of(error).map(|metadata| {
+            let mut meta: Vec<(&'static str, MetadataValue)> = vec![];
+            for (key, value) in metadata.metadata() {
+                if meta.iter().any(|(seen, _)| *seen == key) {
+                    continue;
+                }
+                let value = match value {
+                    MetadataValue::Str(value) => MetadataValue::Str(self.process(&value, None)),
+                    value => value,
+                };
+                meta.push((key, value));
+            }
+            meta.into_iter()
+                .map(|(key, value)| (Text::Str(key), value))
+                .collect()
+        });
+        if self.config.include_debug {
+            fields.debug = Some(self.text(Text::Debug(error), None));
+        }
+        fields.display_alt = self
+            .alternate()
+            .map(|alternate| self.text(Text::Owned(alternate), None));
+        fields.path = error
+            .downcast_ref::<FsError>()
+            .map(|fs_error| self.text(Text::Owned(fs_error.path().display().to_string()), None));
+        if let Some(io_error) = error.downcast_ref::<io::Error>() {
+            let io_kind = Text::Owned(format!("{:?}", io_error.kind()));
+            fields.io_kind = Some(self.text(io_kind, None));
+            fields.os_code = io_error.raw_os_error();
+        }
+        fields.location = self.location();
+        fields.span_trace =
+            span_trace_of(error).map(|span_trace| self.text(Text::Display(span_trace), None));
+        fields
+    }
+}
+
+impl<'a> Link for Node<'a> {
+    type Text = Text<'a>;
+
+    fn config(&self) -> &SerializeConfig {
+        self.config
+    }
+
+    fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn source(&self) -> Option<Self> {
+        Node::source(self)
+    }
+
+    fn cut(&self, nested: bool, fixed: bool) -> Option<Truncated> {
+        let kind = match self.truncated() {
+            0 if self.cycle == Some(self.depth + 1) => Truncation::Cycle,
+            0 => return None,
+            count => Truncation::Depth(count),
+        };
+
+        Some(Truncated {
+            kind,
+            nested,
+            fixed,
+        })
+    }
+
+    fn fields(&self, fixed: bool) -> Cow<'_, Fields<Text<'a>>> {
+        Cow::Owned(Node::fields(self, fixed))
+    }
+
+    fn errors(&self) -> Option<Vec<Self>> {
+        let multi = self.error.downcast_ref::<MultiError>()?;
+        Some(self.nested(multi.errors()))
+    }
+
+    fn related(&self) -> Option<Vec<Self>> {
+        let related = registry::related_of(self.error)?;
+        Some(self.nested(related.iter().map(|error| &**error)))
+    }
+}
+
+impl<'a> Node<'a> {
+    /// The roots of the chains starting at each of `errors`, one level below
+    /// this node
+    fn nested<I>(&self, errors: I) -> Vec<Node<'a>>
+    where
+        I: Iterator<Item = &'a (dyn Error + Send + Sync + 'static)>,
+    {
+        let depth = self.depth + 1;
+        errors
+            .map(|error| {
+                let error: &(dyn Error + 'static) = error;
+                Node {
+                    error,
+                    type_name: registry::type_name_of(error),
+                    config: self.config,
+                    depth,
+                    cycle: find_cycle(error).map(|cycle| cycle + depth),
+                    duplicates: None,
+                    start: depth,
+                }
+                .with_duplicates()
+            })
+            .collect()
+    }
+}
+
+/// An element of a chain being serialized, either borrowed from a live error
+/// or copied into a [`SerializedError`]
+///
+/// Every layout is written in terms of this trait, so that both produce the
+/// same output.
+pub(crate) trait Link: Clone {
+    /// The type of the string fields of each element
+    type Text: Serialize + Clone;
+
+    /// The config the chain is serialized with
+    fn config(&self) -> &SerializeConfig;
+
+    /// The depth of this element within the whole tree
+    fn depth(&self) -> usize;
+
+    /// The next element of the chain, unless it was cut off
+    fn source(&self) -> Option<Self>;
+
+    /// The node to write in place of the rest of the chain, if `source` cut it
+    /// off
+    fn cut(&self, nested: bool, fixed: bool) -> Option<Truncated>;
+
+    /// Everything written for this element besides its source and the other
+    /// chains it holds
+    fn fields(&self, fixed: bool) -> Cow<'_, Fields<Self::Text>>;
+
+    /// The chains aggregated by this element, each starting one level below it
+    fn errors(&self) -> Option<Vec<Self>>;
+
+    /// The chains of the secondary errors attached to this element, each
+    /// starting one level below it
+    fn related(&self) -> Option<Vec<Self>>;
+
+    /// Whether this element starts the output and should carry the `v` field
+    fn versioned(&self) -> bool {
+        self.depth() == 0 && self.config().include_version
+    }
+
+    /// Whether the nested layout leaves the `source` of this element out when
+    /// it has none, rather than writing it as null
+    fn omits_source(&self) -> bool {
+        false
+    }
+}
+
+/// The fields describing a single element of a chain, ready to be written
+///
+/// Elements of live chains stream their strings from the error where
+/// possible, while those of a [`SerializedError`] hold copies of them.
+#[derive(Debug, Clone)]
+pub(crate) struct Fields<T> {
+    pub(crate) type_name: Option<T>,
+    pub(crate) code: Option<T>,
+    pub(crate) msg: T,
+    pub(crate) meta: Option<Vec<(T, MetadataValue)>>,
+    pub(crate) debug: Option<T>,
+    pub(crate) display_alt: Option<T>,
+    pub(crate) path: Option<T>,
+    pub(crate) io_kind: Option<T>,
+    pub(crate) os_code: Option<i32>,
+    pub(crate) location: Option<LocationParts>,
+    /// The origin and time a deserialized error was received at
+    pub(crate) received: Option<(T, T)>,
+    pub(crate) span_trace: Option<T>,
+    /// How many errors a deserialized truncation marker stands for
+    pub(crate) truncated: Option<usize>,
+    pub(crate) backtrace: BacktraceField<T>,
+}
+
+impl Fields<Text<'_>> {
+    /// Copy every string out of the error it borrows from
+    pub(crate) fn into_owned(self) -> Fields<String> {
+        let owned = |text: Text<'_>| text.to_string();
+        Fields {
+            type_name: self.type_name.map(owned),
+            code: self.code.map(owned),
+            msg: owned(self.msg),
+            meta: self.meta.map(|meta| {
+                meta.into_iter()
+                    .map(|(key, value)| (owned(key), value))
+                    .collect()
+            }),
+            debug: self.debug.map(owned),
+            display_alt: self.display_alt.map(owned),
+            path: self.path.map(owned),
+            io_kind: self.io_kind.map(owned),
+            os_code: self.os_code,
+            location: self.location,
+            received: self.received.map(|(origin, at)| (owned(origin), owned(at))),
+            span_trace: self.span_trace.map(owned),
+            truncated: self.truncated,
+            backtrace: match self.backtrace {
+                BacktraceField::Omitted => BacktraceField::Omitted,
+                BacktraceField::Duplicate(marker) => BacktraceField::Duplicate(marker),
+                BacktraceField::Text(text) => BacktraceField::Text(text.map(owned)),
+                BacktraceField::Frames(frames) => BacktraceField::Frames(frames),
+            },
+        }
+    }
+}
+
+impl<T: Serialize> Fields<T> {
+    /// The number of fields written by `serialize`, with `errors` and
+    /// `related` if the element holds those chains
+    fn len(&self, errors: bool, related: bool) -> usize {
+        1 + self.type_name.is_some() as usize
+            + self.code.is_some() as usize
+            + self.meta.is_some() as usize
+            + self.debug.is_some() as usize
+            + self.display_alt.is_some() as usize
+            + self.path.is_some() as usize
+            + self.io_kind.is_some() as usize
+            + self.os_code.is_some() as usize
+            + errors as usize
+            + related as usize
+            + self.location.is_some() as usize
+            + 2 * self.received.is_some() as usize
+            + self.span_trace.is_some() as usize
+            + self.truncated.is_some() as usize
+            + !matches!(self.backtrace, BacktraceField::Omitted) as usize
+    }
+
+    /// Write every field, placing the chains in `errors` and `related` after
+    /// `os_code`
+    fn serialize<S, L>(
+        &self,
+        e: &mut S,
+        errors: Option<Vec<L>>,
+        related: Option<Vec<L>>,
+    ) -> Result<(), S::Error>
+    where
+        S: SerializeStruct,
+        L: Link,
+    {
+        if let Some(type_name) = &self.type_name {
+            e.serialize_field("type_name", type_name)?;
+        }
+        if let Some(code) = &self.code {
+            e.serialize_field("code", code)?;
+        }
+        e.serialize_field("msg", &self.msg)?;
+        if let Some(meta) = &self.meta {
+            e.serialize_field("meta", &Metadata(meta))?;
+        }
+        if let Some(debug) = &self.debug {
+            e.serialize_field("debug", debug)?;
+        }
+        if let Some(display_alt) = &self.display_alt {
+            e.serialize_field("display_alt", display_alt)?;
+        }
+        if let Some(path) = &self.path {
+            e.serialize_field("path", path)?;
+        }
+        if let Some(io_kind) = &self.io_kind {
+            e.serialize_field("io_kind", io_kind)?;
+        }
+        if let Some(os_code) = &self.os_code {
+            e.serialize_field("os_code", os_code)?;
+        }
+        if let Some(errors) = errors {
+            e.serialize_field("errors", &Aggregated(errors))?;
+        }
+        if let Some(related) = related {
+            e.serialize_field("related", &Aggregated(related))?;
+        }
+        if let Some(location) = &self.location {
+            e.serialize_field("location", location)?;
+        }
+        if let Some((origin, at)) = &self.received {
+            e.serialize_field("received_from", origin)?;
+            e.serialize_field("received_at", at)?;
+        }
+        if let Some(span_trace) = &self.span_trace {
+            e.serialize_field("span_trace", span_trace)?;
+        }
+        if let Some(truncated) = &self.truncated {
+            e.serialize_field("truncated", truncated)?;
+        }
+        match &self.backtrace {
+            BacktraceField::Omitted => e.skip_field("backtrace"),
+            backtrace => e.serialize_field("backtrace", backtrace),
+        }
+    }
+}
+
+/// The value of the `backtrace` field of an element
+#[derive(Debug, Clone)]
+pub(crate) enum BacktraceField<T> {
+    /// Left out entirely
+    Omitted,
+    /// Replaced because an error further down the chain has the same one
+    Duplicate(Option<&'static str>),
+    Text(Option<T>),
+    Frames(Option<Vec<Frame>>),
+}
+
+impl<T: Serialize> Serialize for BacktraceField<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            BacktraceField::Omitted => serializer.serialize_none(),
+            BacktraceField::Duplicate(marker) => marker.serialize(serializer),
+            BacktraceField::Text(text) => text.serialize(serializer),
+            BacktraceField::Frames(frames) => frames.serialize(serializer),
+        }
+    }
+}
+
+/// The position in the chain starting at `error` of the first element which
+/// repeats an earlier one, if `source()` forms a cycle
+pub(crate) fn find_cycle(error: &(dyn Error + 'static)) -> Option<usize> {
+    let mut chain = Chain::new(error);
+    let len = chain.by_ref().count();
+    Some(len).filter(|_| chain.is_cyclic())
+}
+
+/// The `meta` object of a node, with repeated keys already removed
+struct Metadata<'a, T>(&'a [(T, MetadataValue)]);
+
+impl<T: Serialize> Serialize for Metadata<'_, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in self.0 {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+/// The `errors` array of a [`MultiError`] or the `related` array of a
+/// [`Wrapped`], holding the full chain of each error
+///
+/// Each chain is nested one level below the error holding it, so that
+/// `max_depth` and the nesting limit keep applying to the whole tree.
+///
+/// [`Wrapped`]: crate::Wrapped
+struct Aggregated<L>(Vec<L>);
+
+impl<L: Link> Serialize for Aggregated<L> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let human_readable = serializer.is_human_readable();
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for link in &self.0 {
+            match link.config().layout {
+                ChainLayout::Flat if !link.config().fixed_layout && human_readable => {
+                    seq.serialize_element(&FlatChain(link.clone()))?
+                }
+                _ => seq.serialize_element(&Nested(link.clone()))?,
+            }
+        }
+        seq.end()
+    }
+}
+
+/// Cut `text` down to at most `max_len` bytes on a character boundary, noting
+/// how many bytes were removed
+fn truncate(text: &str, max_len: usize) -> Cow<'_, str> {
+    if text.len() <= max_len {
+        return Cow::Borrowed(text);
+    }
+
+    let mut end = max_len;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    Cow::Owned(format!("{}…[+{} bytes]", &text[..end], text.len() - end))
+}
+
+/// A string field which has either been processed or can be streamed as is
+#[derive(Clone)]
+pub(crate) enum Text<'a> {
+    /// The `Display` output of an error
+    Msg(&'a (dyn Error + 'static)),
+    /// The `Debug` output of an error
+    Debug(&'a (dyn Error + 'static)),
+    Display(&'a (dyn fmt::Display + 'static)),
+    Str(&'a str),
+    Owned(String),
+}
+
+impl fmt::Display for Text<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Text::Msg(error) => fmt::Display::fmt(error, f),
+            Text::Debug(error) => fmt::Debug::fmt(error, f),
+            Text::Display(display) => fmt::Display::fmt(display, f),
+            Text::Str(text) => f.write_str(text),
+            Text::Owned(text) => f.write_str(text),
+        }
+    }
+}
+
+impl fmt::Debug for Text<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_string(), f)
+    }
+}
+
+impl Serialize for Text<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Text::Str(text) => serializer.serialize_str(text),
+            Text::Owned(text) => serializer.serialize_str(text),
+            text => serializer.collect_str(text),
+        }
+    }
+}
+
+/// Serialize the chain starting at `link` in the layout selected by its
+/// config
+pub(crate) fn serialize_chain<L, S>(link: L, serializer: S) -> Result<S::Ok, S::Error>
+where
+    L: Link,
+    S: Serializer,
+{
+    match link.config().layout {
+        ChainLayout::Flat if !link.config().fixed_layout && serializer.is_human_readable() => {
+            FlatChain(link).serialize(serializer)
+        }
+        _ => Nested(link).serialize(serializer),
+    }
+}
+
+/// Serializes an element and its sources as nested objects
+///
+/// Each level of nesting recurses through the serializer, so to keep deep
+/// chains from overflowing the stack only the first `NESTING_LIMIT` elements
+/// are nested. The `source` of the last nested element holds the rest of the
+/// chain in the flat layout, which is written with a loop.
+struct Nested<L>(L);
+
+impl<L: Link> Serialize for Nested<L> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let link = &self.0;
+        if link.config().fixed_layout || !serializer.is_human_readable() {
+            return Fixed(link.clone()).serialize(serializer);
+        }
+
+        let fields = link.fields(false);
+        let errors = link.errors();
+        let related = link.related();
+        let version = link.versioned();
+        let source = link.source();
+        let omit_source = source.is_none() && link.omits_source();
+        let len = fields.len(errors.is_some(), related.is_some())
+            + !omit_source as usize
+            + version as usize;
+        let mut e = serializer.serialize_struct("error", len)?;
+        if version {
+            e.serialize_field("v", &FORMAT_VERSION)?;
+        }
+        fields.serialize(&mut e, errors, related)?;
+        match source {
+            Some(source) if source.depth() >= NESTING_LIMIT => {
+                e.serialize_field("source", &Some(FlatChain(source)))?
+            }
+            Some(source) => e.serialize_field("source", &Some(Nested(source)))?,
+            None if omit_source => e.skip_field("source")?,
+            None => e.serialize_field("source", &link.cut(true, false))?,
+        }
+        e.end()
+    }
+}
+
+/// Serializes an element and its sources with exactly the same fields in the
+/// same order for every element: `type_name`, `msg`, `backtrace`, `errors`,
+/// `related`, `source`
+///
+/// Used for formats that aren't human readable, which are usually positional
+/// like bincode and so can't skip fields or recognize ones that only some
+/// elements have, and whenever `fixed_layout` is set. Backtraces are always
+/// written as strings here, and `errors` and `related` are empty unless the
+/// element holds such chains.
+///
+/// There is no room for the flat layout here, so rather than recursing without
+/// bound, the elements past `FIXED_NESTING_LIMIT` are replaced by a truncation
+/// marker, as if `max_depth` defaulted to that limit.
+struct Fixed<L>(L);
+
+impl<L: Link> Serialize for Fixed<L> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let link = &self.0;
+        if link.depth() >= FIXED_NESTING_LIMIT {
+            let truncated = Truncated {
+                kind: Truncation::Depth(chain_len(link)),
+                nested: true,
+                fixed: true,
+            };
+            return truncated.serialize(serializer);
+        }
+
+        let fields = link.fields(true);
+        let errors = link.errors().unwrap_or_default();
+        let related = link.related().unwrap_or_default();
+        let mut e = serializer.serialize_struct("error", 6)?;
+        match fields.truncated {
+            Some(count) => e.serialize_field("type_name", &Some(truncated_type_name(count)))?,
+            None => e.serialize_field("type_name", &fields.type_name)?,
+        }
+        e.serialize_field("msg", &fields.msg)?;
+        e.serialize_field("backtrace", &fields.backtrace)?;
+        e.serialize_field("errors", &Aggregated(errors))?;
+        e.serialize_field("related", &Aggregated(related))?;
+        match link.source() {
+            Some(source) => e.serialize_field("source", &Some(Fixed(source)))?,
+            None => e.serialize_field("source", &link.cut(true, true))?,
+        }
+        e.end()
+    }
+}
+
+/// The number of errors in the chain starting at `link`, counting those cut
+/// off after it by `max_depth`
+fn chain_len<L: Link>(link: &L) -> usize {
+    let mut len = 0;
+    let mut last = link.clone();
+    for next in std::iter::successors(Some(link.clone()), L::source) {
+        len += 1;
+        last = next;
+    }
+
+    match last.cut(true, true) {
+        Some(Truncated {
+            kind: Truncation::Depth(count),
+            ..
+        }) => len + count,
+        _ => len,
+    }
+}
+
+/// Serializes an element and its sources as a `chain` array, outermost first
+struct FlatChain<L>(L);
+
+/// A single element of a `FlatChain`, without its source
+enum FlatEntry<L> {
+    Node(L),
+    Truncated(Truncated),
+}
+
+/// The node emitted in place of the errors cut off by `max_depth` or a cycle
+pub(crate) struct Truncated {
+    pub(crate) kind: Truncation,
+    pub(crate) nested: bool,
+    pub(crate) fixed: bool,
+}
+
+/// The `type_name` of a truncation marker in the fixed layout, which has no
+/// `truncated` field to hold the number of errors it stands for
+pub(crate) fn truncated_type_name(count: usize) -> String {
+    format!("errtools::Truncated<{}>", count)
+}
+
+/// The number of errors a marker with the type name `type_name` stands for, if
+/// it was written by [`truncated_type_name`]
+pub(crate) fn parse_truncated_type_name(type_name: &str) -> Option<usize> {
+    type_name
+        .strip_prefix("errtools::Truncated<")?
+        .strip_suffix('>')?
+        .parse()
+        .ok()
+}
+
+/// Why the rest of a chain was cut off
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Truncation {
+    /// This many errors were past `max_depth`
+    Depth(usize),
+    /// The next error repeats an earlier one
+    Cycle,
+}
+
+impl<L: Link> Serialize for FlatChain<L> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut chain = std::iter::successors(Some(self.0.clone()), L::source)
+            .map(FlatEntry::Node)
+            .collect::<Vec<_>>();
+
+        if let Some(FlatEntry::Node(last)) = chain.last() {
+            chain.extend(last.cut(false, false).map(FlatEntry::Truncated));
+        }
+
+        let version = self.0.versioned();
+        let mut e = serializer.serialize_struct("error_chain", 1 + version as usize)?;
+        if version {
+            e.serialize_field("v", &FORMAT_VERSION)?;
+        }
+        e.serialize_field("chain", &chain)?;
+        e.end()
+    }
+}
+
+impl<L: Link> Serialize for FlatEntry<L> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            FlatEntry::Node(link) => {
+                let fields = link.fields(false);
+                let errors = link.errors();
+                let related = link.related();
+                let len = fields.len(errors.is_some(), related.is_some());
+                let mut e = serializer.serialize_struct("error", len)?;
+                fields.serialize(&mut e, errors, related)?;
+                e.end()
+            }
+            FlatEntry::Truncated(truncated) => truncated.serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for Truncated {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let msg = match self.kind {
+            Truncation::Depth(1) => "… 1 more error truncated".to_string(),
+            Truncation::Depth(count) => format!("… {} more errors truncated", count),
+            Truncation::Cycle => "<cyclic error chain truncated>".to_string(),
+        };
+        let count = match self.kind {
+            Truncation::Depth(count) => Some(count),
+            Truncation::Cycle => None,
+        };
+
+        if self.fixed || !serializer.is_human_readable() {
+            let mut e = serializer.serialize_struct("error", 6)?;
+            e.serialize_field("type_name", &count.map(truncated_type_name))?;
+            e.serialize_field("msg", &msg)?;
+            e.serialize_field("backtrace", &None::<&str>)?;
+            e.serialize_field("errors", &[(); 0][..])?;
+            e.serialize_field("related", &[(); 0][..])?;
+            e.serialize_field("source", &None::<()>)?;
+            return e.end();
+        }
+
+        let len = 1 + count.is_some() as usize + self.nested as usize;
+        let mut e = serializer.serialize_struct("error", len)?;
+        e.serialize_field("msg", &msg)?;
+        if let Some(count) = count {
+            e.serialize_field("truncated", &count)?;
+        }
+        if self.nested {
+            e.serialize_field("source", &None::<()>)?;
+        }
+        e.end()
+    }
+}
+
+/// Write the messages of the chain on one line, joined with `": "`
+fn fmt_joined(error: &(dyn Error + 'static), f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", error)?;
+    for error in Chain::sources(error) {
+        write!(f, ": {}", error)?;
+    }
+    Ok(())
+}
+
+/// Write each error of the chain with its type name, whether it captured a
+/// backtrace, and its span trace if it has one
+fn fmt_detailed(
+    error: &(dyn Error + 'static),
+    type_name: Option<&'static str>,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    for (ind, error) in Chain::new(error).enumerate() {
+        let type_name = match ind {
+            0 => type_name,
+            _ => registry::type_name_of(error),
+        };
+        if ind > 0 {
+            writeln!(f)?;
+        }
+        write!(f, "{}: {}", ind, error)?;
+        write!(f, "\n    type: {}", type_name.unwrap_or("unknown"))?;
+        match backtrace_display_of(error) {
+            Some(_) => write!(f, "\n    backtrace: captured")?,
+            None => write!(f, "\n    backtrace: none")?,
+        }
+        if let Some(span_trace) = span_trace_of(error) {
+            write!(f, "\n    span trace:")?;
+            for line in span_trace.to_string().lines() {
+                write!(f, "\n      {}", line)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for SerializeableError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_joined(self.0, f)
+    }
+}
+
+impl fmt::Debug for SerializeableError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_detailed(self.0, registry::type_name_of(self.0), f)
+    }
+}
+
+impl<E> fmt::Display for SerializeableConcreteError<'_, E>
+where
+    E: Error + Sized + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_joined(self.0, f)
+    }
+}
+
+impl<E> fmt::Debug for SerializeableConcreteError<'_, E>
+where
+    E: Error + Sized + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_detailed(self.0, Some(std::any::type_name::<E>()), f)
+    }
+}
+
+impl Serialize for SerializeableError<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let node = Node::root(self.0, registry::type_name_of(self.0), &DEFAULT_CONFIG);
+        serialize_chain(node, serializer)
+    }
+}
+
+impl<E> Serialize for SerializeableConcreteError<'_, E>
+where
+    E: Error + Sized + 'static,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let node = Node::root(self.0, Some(std::any::type_name::<E>()), &DEFAULT_CONFIG);
+        serialize_chain(node, serializer)
+    }
+}
+
+impl<E> Serialize for Serializable<E>
+where
+    E: Error + 'static,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        SerializeableConcreteError(&self.0).serialize(serializer)
+    }
+}
+
+impl<E> From<E> for Serializable<E>
+where
+    E: Error + 'static,
+{
+    fn from(error: E) -> Self {
+        Serializable(error)
+    }
+}
+
+impl<E> fmt::Display for Serializable<E>
+where
+    E: Error + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<E> fmt::Debug for Serializable<E>
+where
+    E: Error + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl Serialize for ConfiguredSerializeableError<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_chain(self.node.clone(), serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_on_char_boundary() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("exactly", 7), "exactly");
+        assert_eq!(truncate("abcdef", 3), "abc…[+3 bytes]");
+        // 'é' is two bytes long, a cut at 2 would fall in the middle of it
+        assert_eq!(truncate("aébc", 2), "a…[+4 bytes]");
+        assert_eq!(truncate("日本語", 4), "日…[+6 bytes]");
+    }
+}
@@ -0,0 +1,78 @@
+//! `#[serde(with = "...")]` support for boxed error fields
+//!
+//! Serializes a `Box<dyn Error + Send + Sync>` field as its error chain, in the
+//! same shape as [`ErrTools::serialize`], and deserializes it back as a
+//! [`deserialize::Error`].
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//! use std::error::Error;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct JobResult {
+//!     ok: bool,
+//!     #[serde(with = "errtools::serde_box_error")]
+//!     error: Box<dyn Error + Send + Sync>,
+//! }
+//! ```
+//!
+//! Fields of type `Box<dyn Error>` without the `Send + Sync` bounds are
+//! supported by the [`local`] submodule.
+//!
+//! [`ErrTools::serialize`]: crate::ErrTools::serialize
+//! [`deserialize::Error`]: crate::deserialize::Error
+use crate::{deserialize, SerializeableError};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::error::Error;
+
+/// Serialize a boxed error as its error chain
+#[allow(clippy::borrowed_box)]
+pub fn serialize<S>(
+    error: &Box<dyn Error + Send + Sync + 'static>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    SerializeableError(&**error).serialize(serializer)
+}
+
+/// Deserialize an error chain into a boxed [`deserialize::Error`]
+///
+/// [`deserialize::Error`]: crate::deserialize::Error
+pub fn deserialize<'de, D>(
+    deserializer: D,
+) -> Result<Box<dyn Error + Send + Sync + 'static>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let error = deserialize::Error::deserialize(deserializer)?;
+    Ok(Box::new(error))
+}
+
+/// `#[serde(with = "...")]` support for `Box<dyn Error>` fields
+pub mod local {
+    use crate::{deserialize, SerializeableError};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::error::Error;
+
+    /// Serialize a boxed error as its error chain
+    #[allow(clippy::borrowed_box)]
+    pub fn serialize<S>(error: &Box<dyn Error + 'static>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        SerializeableError(&**error).serialize(serializer)
+    }
+
+    /// Deserialize an error chain into a boxed [`deserialize::Error`]
+    ///
+    /// [`deserialize::Error`]: crate::deserialize::Error
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Box<dyn Error + 'static>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let error = deserialize::Error::deserialize(deserializer)?;
+        Ok(Box::new(error))
+    }
+}
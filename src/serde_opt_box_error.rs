@@ -0,0 +1,50 @@
+//! `#[serde(with = "...")]` support for optional boxed error fields
+//!
+//! The `Option` counterpart of [`serde_box_error`]: `None` is serialized as
+//! `null` and `Some` as the error chain. Composes with `#[serde(default)]` and
+//! `#[serde(skip_serializing_if = "Option::is_none")]`.
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//! use std::error::Error;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Worker {
+//!     name: String,
+//!     #[serde(default, with = "errtools::serde_opt_box_error")]
+//!     last_error: Option<Box<dyn Error + Send + Sync>>,
+//! }
+//! ```
+//!
+//! [`serde_box_error`]: crate::serde_box_error
+use crate::{deserialize, SerializeableError};
+use serde::{Deserialize, Deserializer, Serializer};
+use std::error::Error;
+
+/// Serialize an optional boxed error as `null` or its error chain
+pub fn serialize<S>(
+    error: &Option<Box<dyn Error + Send + Sync + 'static>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match error {
+        Some(error) => serializer.serialize_some(&SerializeableError(&**error)),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Deserialize `null` or an error chain into an optional boxed
+/// [`deserialize::Error`]
+///
+/// [`deserialize::Error`]: crate::deserialize::Error
+pub fn deserialize<'de, D>(
+    deserializer: D,
+) -> Result<Option<Box<dyn Error + Send + Sync + 'static>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let error = Option::<deserialize::Error>::deserialize(deserializer)?;
+    Ok(error.map(|error| Box::new(error) as Box<dyn Error + Send + Sync + 'static>))
+}
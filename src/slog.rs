@@ -0,0 +1,139 @@
+//! Logging error chains as structured `slog` values
+//!
+//! Keys of `slog` values are `&'static str`, so a chain can't be spread over
+//! keys of its own. [`Chain`] is emitted as a single nested value instead,
+//! the map of [`ErrTools::to_field_map`], for drains which serialize values
+//! with `serde`, and as the one-line form of the chain for the rest.
+//!
+//! ```rust
+//! use errtools::deserialize::Error;
+//! use errtools::ErrTools;
+//! use slog::{error, o, Discard, Logger};
+//!
+//! let log = Logger::root(Discard, o!());
+//! let err = Error::builder()
+//!     .msg("unable to load config")
+//!     .causes(["permission denied"])
+//!     .build();
+//!
+//! error!(log, "request failed"; "err" => err.slog_value());
+//! ```
+//!
+//! [`ErrTools::to_field_map`]: crate::ErrTools::to_field_map
+use crate::fields::{field_map, DEFAULT_MAX_NESTING};
+use crate::registry;
+use crate::report::OneLine;
+use serde::ser::{Serialize, Serializer};
+use slog::{Key, Record, SerdeValue, Value};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// An error chain as a `slog` value
+///
+/// Created by [`Chain::new`] or [`ErrTools::slog_value`].
+///
+/// [`ErrTools::slog_value`]: crate::ErrTools::slog_value
+#[derive(Clone, Copy)]
+pub struct Chain<'a> {
+    error: &'a (dyn Error + 'static),
+    type_name: Option<&'static str>,
+}
+
+impl<'a> Chain<'a> {
+    /// Wrap `error` and its sources for logging
+    pub fn new(error: &'a (dyn Error + 'static)) -> Self {
+        Self::with_type_name(error, registry::type_name_of(error))
+    }
+
+    pub(crate) fn with_type_name(
+        error: &'a (dyn Error + 'static),
+        type_name: Option<&'static str>,
+    ) -> Self {
+        Self { error, type_name }
+    }
+
+    fn fields(&self) -> BTreeMap<String, String> {
+        field_map(self.error, self.type_name, DEFAULT_MAX_NESTING)
+    }
+}
+
+impl Value for Chain<'_> {
+    fn serialize(
+        &self,
+        _: &Record<'_>,
+        key: Key,
+        serializer: &mut dyn slog::Serializer,
+    ) -> slog::Result {
+        serializer.emit_serde(key, self)
+    }
+}
+
+impl SerdeValue for Chain<'_> {
+    fn serialize_fallback(&self, key: Key, serializer: &mut dyn slog::Serializer) -> slog::Result {
+        serializer.emit_arguments(key, &format_args!("{}", OneLine::new(self.error, ": ")))
+    }
+
+    fn as_serde(&self) -> &dyn erased_serde::Serialize {
+        self
+    }
+
+    fn to_sendable(&self) -> Box<dyn SerdeValue + Send + 'static> {
+        Box::new(OwnedChain {
+            fields: self.fields(),
+            one_line: OneLine::new(self.error, ": ").to_string(),
+        })
+    }
+}
+
+impl Serialize for Chain<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.fields().serialize(serializer)
+    }
+}
+
+/// A copy of a [`Chain`] which can be sent to another thread, such as that of
+/// an asynchronous drain
+struct OwnedChain {
+    fields: BTreeMap<String, String>,
+    one_line: String,
+}
+
+impl Value for OwnedChain {
+    fn serialize(
+        &self,
+        _: &Record<'_>,
+        key: Key,
+        serializer: &mut dyn slog::Serializer,
+    ) -> slog::Result {
+        serializer.emit_serde(key, self)
+    }
+}
+
+impl SerdeValue for OwnedChain {
+    fn serialize_fallback(&self, key: Key, serializer: &mut dyn slog::Serializer) -> slog::Result {
+        serializer.emit_str(key, &self.one_line)
+    }
+
+    fn as_serde(&self) -> &dyn erased_serde::Serialize {
+        self
+    }
+
+    fn to_sendable(&self) -> Box<dyn SerdeValue + Send + 'static> {
+        Box::new(OwnedChain {
+            fields: self.fields.clone(),
+            one_line: self.one_line.clone(),
+        })
+    }
+}
+
+impl Serialize for OwnedChain {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.fields.serialize(serializer)
+    }
+}
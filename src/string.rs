@@ -0,0 +1,82 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+
+/// An error consisting of nothing but a message
+///
+/// Lets string-typed errors, such as those returned by `Result<T, String>`
+/// APIs, participate in an error chain. Displays exactly the original string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringError(Cow<'static, str>);
+
+impl StringError {
+    /// Create a new `StringError` from a message
+    pub fn new<S>(msg: S) -> Self
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        Self(msg.into())
+    }
+
+    /// The message this error was created from
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Consume the error, returning the message as an owned `String`
+    pub fn into_string(self) -> String {
+        self.0.into_owned()
+    }
+}
+
+impl fmt::Display for StringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for StringError {}
+
+impl From<String> for StringError {
+    fn from(msg: String) -> Self {
+        Self::new(msg)
+    }
+}
+
+impl From<&'static str> for StringError {
+    fn from(msg: &'static str) -> Self {
+        Self::new(msg)
+    }
+}
+
+impl From<Cow<'static, str>> for StringError {
+    fn from(msg: Cow<'static, str>) -> Self {
+        Self::new(msg)
+    }
+}
+
+/// Extension trait for lifting string-typed errors into [`StringError`]
+///
+/// ```rust
+/// use errtools::ResultStrExt;
+///
+/// fn legacy() -> Result<(), String> {
+///     Err("script failed".to_string())
+/// }
+///
+/// let err = legacy().err_as_error().unwrap_err();
+/// assert_eq!(err.to_string(), "script failed");
+/// ```
+pub trait ResultStrExt<T> {
+    /// Convert the error value into a [`StringError`] so it can be wrapped
+    fn err_as_error(self) -> Result<T, StringError>;
+}
+
+impl<T, S> ResultStrExt<T> for Result<T, S>
+where
+    S: Into<StringError>,
+{
+    fn err_as_error(self) -> Result<T, StringError> {
+        self.map_err(Into::into)
+    }
+}
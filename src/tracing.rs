@@ -0,0 +1,112 @@
+//! Recording error chains as structured `tracing` events
+//!
+//! Passing an error to `tracing::error!` with `%` records it as one string,
+//! which loses the shape of the chain. [`event`] records each part of it as
+//! its own field instead.
+//!
+//! `tracing::Value` is sealed, so errors can't be given an impl of it
+//! directly. [`AsTraceValue`] renders a chain for the `%` and `?` sigils of
+//! the `tracing` macros instead.
+use crate::chain::Chain;
+use crate::report::{known_type_name, OneLine, Report};
+use ::tracing::{event, Level};
+use std::error::Error;
+use std::fmt;
+
+/// Record an `ERROR` level event for `error` and its sources
+///
+/// See [`event_at`] for the fields.
+pub fn event(error: &(dyn Error + 'static)) {
+    event_at(Level::ERROR, error)
+}
+
+/// Record an event at `level` for `error` and its sources
+///
+/// The event has the fields
+///
+/// - `error.msg`, the message of `error`
+/// - `error.type`, its type name, when that is known
+/// - `error.chain_len`, the number of errors in the chain
+/// - `error.root.msg`, the message of the innermost error
+/// - `error.chain`, the messages of the chain joined with `": "`
+///
+/// ```rust
+/// use errtools::deserialize::Error;
+/// use tracing::Level;
+///
+/// let err = Error::builder()
+///     .type_name("app::LoadError")
+///     .msg("unable to load config")
+///     .causes(["permission denied"])
+///     .build();
+///
+/// errtools::tracing::event_at(Level::WARN, &err);
+/// ```
+pub fn event_at(level: Level, error: &(dyn Error + 'static)) {
+    let type_name = known_type_name(error);
+    let mut chain_len = 0u64;
+    let mut root = error;
+    for error in Chain::new(error) {
+        chain_len += 1;
+        root = error;
+    }
+    let one_line = OneLine::new(error, ": ");
+
+    // The level of an event is part of its static metadata, so each one needs
+    // its own callsite
+    macro_rules! record {
+        ($level:expr) => {
+            event!(
+                $level,
+                "error.msg" = %error,
+                "error.type" = type_name,
+                "error.chain_len" = chain_len,
+                "error.root.msg" = %root,
+                "error.chain" = %one_line,
+            )
+        };
+    }
+
+    if level == Level::ERROR {
+        record!(Level::ERROR)
+    } else if level == Level::WARN {
+        record!(Level::WARN)
+    } else if level == Level::INFO {
+        record!(Level::INFO)
+    } else if level == Level::DEBUG {
+        record!(Level::DEBUG)
+    } else {
+        record!(Level::TRACE)
+    }
+}
+
+/// An error chain for the fields of `tracing` events
+///
+/// Its `Display` is the one-line form of the chain, for `%`, and its `Debug`
+/// is the multi-line [`Report`], without colors, for `?`.
+///
+/// ```rust
+/// use errtools::deserialize::Error;
+/// use errtools::tracing::AsTraceValue;
+///
+/// let err = Error::builder()
+///     .msg("unable to load config")
+///     .causes(["permission denied"])
+///     .build();
+///
+/// tracing::warn!(error = %AsTraceValue(&err), "retrying");
+/// ```
+#[derive(Clone, Copy)]
+pub struct AsTraceValue<'a>(pub &'a (dyn Error + 'static));
+
+impl fmt::Display for AsTraceValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        OneLine::new(self.0, ": ").fmt(f)
+    }
+}
+
+impl fmt::Debug for AsTraceValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#}", Report::new(self.0))
+    }
+}
@@ -0,0 +1,24 @@
+//! Passing error chains to JavaScript
+//!
+//! Implementing `From<&dyn Error>` for `JsValue` isn't allowed outside of
+//! `wasm-bindgen`, so [`to_js_value`] does the conversion instead.
+//!
+//! Backtraces can't be captured on `wasm32-unknown-unknown`, so the
+//! `backtrace` of each error is `null` there.
+use crate::report::OneLine;
+use crate::SerializeableError;
+use serde::Serialize;
+use std::error::Error;
+use wasm_bindgen::JsValue;
+
+/// Serialize the error chain into a plain JavaScript object
+///
+/// The object has the same shape as the JSON form of the chain, with
+/// missing values as `null`. If the chain can't be serialized the value is
+/// instead a string of the messages of the chain joined with `": "`.
+pub fn to_js_value(error: &(dyn Error + 'static)) -> JsValue {
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    SerializeableError(error)
+        .serialize(&serializer)
+        .unwrap_or_else(|_| JsValue::from_str(&OneLine::new(error, ": ").to_string()))
+}
@@ -0,0 +1,151 @@
+use crate::chain::Chain;
+use crate::{registry, Located, SerializeableConcreteError};
+use serde::ser::{Serialize, Serializer};
+use std::error::Error;
+use std::fmt;
+use std::panic::Location;
+
+/// A message wrapped around a source error whose concrete type is preserved
+///
+/// Unlike boxing the source as `Box<dyn Error + Send + Sync>`, `Wrapped<E>`
+/// keeps the source statically typed so it can be matched on directly without
+/// downcasting.
+///
+/// ```rust
+/// use errtools::{WrapErr, Wrapped};
+/// use std::io;
+///
+/// let err: Wrapped<io::Error> = std::fs::read_to_string("fake_file")
+///     .wrap_err("unable to read config")
+///     .unwrap_err();
+///
+/// assert_eq!(err.source.kind(), io::ErrorKind::NotFound);
+/// ```
+///
+/// Secondary failures, such as a rollback that also failed while handling the
+/// original error, can be attached with [`Wrapped::push_related`] so they
+/// aren't lost. They are rendered by the alternate (`{:#}`) format and
+/// serialized under a `related` array.
+///
+/// The location a `Wrapped` is created at, usually the call to
+/// [`WrapErr::wrap_err`], is recorded, available through [`Located`], and
+/// serialized as its `location`.
+///
+/// [`WrapErr::wrap_err`]: crate::WrapErr::wrap_err
+#[derive(Debug)]
+pub struct Wrapped<E> {
+    /// The message describing the context of the failure
+    pub msg: String,
+    /// The wrapped error
+    pub source: E,
+    pub(crate) related: Vec<Box<dyn Error + Send + Sync + 'static>>,
+    location: &'static Location<'static>,
+}
+
+impl<E> Wrapped<E> {
+    /// Consume the wrapper, returning the source error
+    pub fn into_inner(self) -> E {
+        self.source
+    }
+
+    /// Attach a secondary error that occurred while handling this one
+    pub fn push_related<R>(&mut self, error: R)
+    where
+        E: Error + 'static,
+        R: Into<Box<dyn Error + Send + Sync + 'static>>,
+    {
+        registry::register_related::<E>();
+        self.related.push(error.into());
+    }
+
+    /// Builder style version of [`Wrapped::push_related`]
+    pub fn with_related<R>(mut self, error: R) -> Self
+    where
+        E: Error + 'static,
+        R: Into<Box<dyn Error + Send + Sync + 'static>>,
+    {
+        self.push_related(error);
+        self
+    }
+
+    /// The secondary errors attached to this wrapper
+    pub fn related(&self) -> impl Iterator<Item = &(dyn Error + Send + Sync + 'static)> {
+        self.related.iter().map(|error| &**error)
+    }
+}
+
+impl<E> fmt::Display for Wrapped<E>
+where
+    E: Error + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.msg)?;
+
+        if !f.alternate() {
+            return Ok(());
+        }
+
+        write!(f, "\n\nCaused by:")?;
+        for (ind, error) in Chain::new(&self.source).enumerate() {
+            write!(f, "\n    {}: {}", ind, error)?;
+        }
+
+        if !self.related.is_empty() {
+            write!(f, "\n\nRelated:")?;
+            for (ind, error) in self.related.iter().enumerate() {
+                write!(f, "\n    {}: {}", ind, error)?;
+                for error in Chain::sources(&**error) {
+                    write!(f, ": {}", error)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<E> Error for Wrapped<E>
+where
+    E: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl<E> From<(E, String)> for Wrapped<E>
+where
+    E: Error + 'static,
+{
+    #[track_caller]
+    fn from((source, msg): (E, String)) -> Self {
+        registry::register_located::<Self>();
+        Self {
+            msg,
+            source,
+            related: Vec::new(),
+            location: Location::caller(),
+        }
+    }
+}
+
+impl<E> Located for Wrapped<E> {
+    fn location(&self) -> Option<&'static Location<'static>> {
+        Some(self.location)
+    }
+}
+
+/// Serializes the same way as through [`ErrTools::serialize`]
+///
+/// [`ErrTools::serialize`]: crate::ErrTools::serialize
+impl<E> Serialize for Wrapped<E>
+where
+    E: Error + 'static,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        SerializeableConcreteError(self).serialize(serializer)
+    }
+}
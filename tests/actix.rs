@@ -0,0 +1,88 @@
+#![cfg(feature = "actix")]
+
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::http::StatusCode;
+use actix_web::ResponseError;
+use errtools::actix::ErrorResponse;
+use errtools::{HttpStatus, Wrapped};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("upstream timed out")]
+struct UpstreamTimeout;
+
+impl HttpStatus for UpstreamTimeout {
+    fn http_status(&self) -> Option<u16> {
+        Some(503)
+    }
+}
+
+fn two_deep() -> Wrapped<UpstreamTimeout> {
+    Wrapped::from((UpstreamTimeout, "unable to sync".to_string()))
+}
+
+async fn body_json(response: &ErrorResponse) -> (StatusCode, String, serde_json::Value) {
+    let response = response.error_response();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = actix_web::body::to_bytes(response.into_body())
+        .await
+        .ok()
+        .unwrap();
+    (status, content_type, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn status_from_chain() {
+    errtools::registry::register_http_status::<UpstreamTimeout>();
+
+    let response = ErrorResponse::from(two_deep());
+    assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let (status, content_type, body) = body_json(&response).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(content_type, "application/json");
+    assert_eq!(body["msg"], "unable to sync");
+    assert_eq!(body["source"]["msg"], "upstream timed out");
+    assert!(body["source"]["source"].is_null());
+}
+
+#[tokio::test]
+async fn explicit_and_default_status() {
+    let response = ErrorResponse::new(StatusCode::BAD_REQUEST, two_deep());
+    let (status, _, body) = body_json(&response).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["source"]["msg"], "upstream timed out");
+
+    let response = ErrorResponse::from(std::io::Error::new(std::io::ErrorKind::Other, "disk full"));
+    let (status, _, body) = body_json(&response).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body["msg"], "disk full");
+}
+
+#[tokio::test]
+async fn status_from_io_error_fallback() {
+    let err = std::io::Error::new(std::io::ErrorKind::TimedOut, "connection timed out");
+    let response = ErrorResponse::from(Wrapped::from((err, "unable to sync".to_string())));
+    assert_eq!(response.status_code(), StatusCode::GATEWAY_TIMEOUT);
+
+    let (status, _, body) = body_json(&response).await;
+    assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(body["source"]["msg"], "connection timed out");
+}
+
+#[test]
+fn display_as_report() {
+    let response = ErrorResponse::from(two_deep());
+    assert_eq!(response.to_string(), "unable to sync: upstream timed out");
+    assert_eq!(
+        format!("{:?}", response),
+        "unable to sync\n\nCaused by:\n    0: upstream timed out"
+    );
+}
@@ -0,0 +1,55 @@
+use errtools::{adhoc, AdhocError, ErrTools, WrapErr};
+use std::error::Error;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("{msg}")]
+struct Wrapper {
+    source: Box<dyn Error + Send + Sync + 'static>,
+    msg: String,
+}
+
+impl<E> From<(E, String)> for Wrapper
+where
+    E: Error + Send + Sync + 'static,
+{
+    fn from((source, msg): (E, String)) -> Self {
+        let source = Box::new(source);
+        Wrapper { source, msg }
+    }
+}
+
+fn enable_backtraces() {
+    std::env::set_var("RUST_LIB_BACKTRACE", "1");
+}
+
+#[test]
+fn adhoc_formats_message() {
+    let user_id = 42;
+    let err = adhoc!("user {} has no active plan", user_id);
+    assert_eq!(err.to_string(), "user 42 has no active plan");
+}
+
+#[test]
+fn serialize_adhoc_type() {
+    enable_backtraces();
+    let err = adhoc!("validation failed");
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+    assert!(json["type_name"].as_str().unwrap().ends_with("AdhocError"));
+    assert_eq!(json["msg"], "validation failed");
+}
+
+#[test]
+fn serialize_adhoc_chain() {
+    enable_backtraces();
+    let err: Wrapper = Err::<(), AdhocError>(adhoc!("root cause"))
+        .wrap_err("outer error")
+        .unwrap_err();
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+    println!("{}", serde_json::to_string_pretty(&json).unwrap());
+
+    assert_eq!(json["msg"], "outer error");
+    assert_eq!(json["source"]["msg"], "root cause");
+    assert!(json["source"]["backtrace"].is_string());
+    assert!(json["source"]["source"].is_null());
+}
@@ -0,0 +1,99 @@
+use errtools::ErrTools;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Held while counting, so that tests running in parallel don't count each
+/// other's allocations
+static COUNTING: Mutex<()> = Mutex::new(());
+
+/// Run `f`, returning its result and the number of allocations it made
+fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let _guard = COUNTING
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    let value = f();
+    (value, ALLOCATIONS.load(Ordering::SeqCst) - before)
+}
+
+#[derive(Debug)]
+struct Link(usize, Option<Box<Link>>);
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error number {} in the chain", self.0)
+    }
+}
+
+impl Error for Link {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.1.as_deref().map(|link| link as &(dyn Error + 'static))
+    }
+}
+
+#[test]
+fn serialize_without_message_allocations() {
+    let err = (0..10).fold(None, |source, n| Some(Box::new(Link(n, source))));
+    let err = err.unwrap();
+    let mut out = Vec::with_capacity(64 * 1024);
+
+    let ((), allocations) =
+        count_allocations(|| serde_json::to_writer(&mut out, &err.serialize()).unwrap());
+
+    let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(json["msg"], "error number 9 in the chain");
+    assert!(
+        allocations < 10,
+        "serializing a 10 element chain performed {} allocations",
+        allocations
+    );
+}
+
+#[test]
+fn deserialize_borrowed_strings() {
+    use errtools::deserialize::{self, ErrorRef};
+
+    let err = (0..5).fold(None, |source, n| Some(Box::new(Link(n, source))));
+    let err = err.unwrap();
+    let json = serde_json::to_string(&err.serialize()).unwrap();
+
+    let (owned, owned_allocations) =
+        count_allocations(|| serde_json::from_str::<deserialize::Error>(&json).unwrap());
+    let (borrowed, borrowed_allocations) =
+        count_allocations(|| serde_json::from_str::<ErrorRef<'_>>(&json).unwrap());
+
+    // Every node of the owned chain copies its message
+    assert_eq!(borrowed.chain_len(), 5);
+    assert!(
+        borrowed_allocations + 5 <= owned_allocations,
+        "borrowing performed {} allocations, copying {}",
+        borrowed_allocations,
+        owned_allocations
+    );
+    assert!(borrowed.chain().all(|error| json
+        .as_bytes()
+        .as_ptr_range()
+        .contains(&error.msg().as_ptr())));
+    assert_eq!(borrowed.to_owned(), owned);
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "axum")]
+
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use errtools::axum::ErrorResponse;
+use errtools::{HttpStatus, Wrapped};
+use serde_json::json;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("no such user")]
+struct MissingUser;
+
+impl HttpStatus for MissingUser {
+    fn http_status(&self) -> Option<u16> {
+        Some(404)
+    }
+}
+
+async fn body_json(response: ErrorResponse) -> (StatusCode, String, serde_json::Value) {
+    let response = response.into_response();
+    let status = response.status();
+    let content_type = response.headers()[CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, content_type, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn explicit_status() {
+    let err = std::io::Error::new(std::io::ErrorKind::Other, "disk full");
+    let err = Wrapped::from((err, "unable to save".to_string()));
+    let (status, content_type, body) =
+        body_json(ErrorResponse::new(StatusCode::SERVICE_UNAVAILABLE, err)).await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(content_type, "application/problem+json");
+    assert_eq!(body["type"], "about:blank");
+    assert_eq!(body["title"], "unable to save");
+    assert_eq!(body["status"], 503);
+    assert_eq!(body["detail"], "unable to save: disk full");
+    assert_eq!(body["errors"]["msg"], "unable to save");
+    assert_eq!(body["errors"]["source"]["msg"], "disk full");
+}
+
+#[tokio::test]
+async fn status_from_chain() {
+    errtools::registry::register_http_status::<MissingUser>();
+
+    fn handler() -> Result<(), ErrorResponse> {
+        Err(Wrapped::from((
+            MissingUser,
+            "unable to load profile".to_string(),
+        )))?;
+        Ok(())
+    }
+
+    let response = handler().unwrap_err();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let (status, _, body) = body_json(response).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["status"], 404);
+    assert_eq!(body["errors"]["source"]["msg"], json!("no such user"));
+}
+
+#[tokio::test]
+async fn status_from_io_error_fallback() {
+    let err = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+    let response = ErrorResponse::from(Wrapped::from((err, "unable to load avatar".to_string())));
+    let (status, _, body) = body_json(response).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["status"], 404);
+}
+
+#[tokio::test]
+async fn default_status() {
+    let response = ErrorResponse::from("unable to sync");
+    let (status, content_type, body) = body_json(response).await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(content_type, "application/problem+json");
+    assert_eq!(body["title"], "unable to sync");
+    assert_eq!(body["status"], 500);
+}
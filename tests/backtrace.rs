@@ -0,0 +1,297 @@
+#![feature(backtrace)]
+
+use errtools::{
+    BacktraceMode, DuplicateBacktraces, ErrTools, ErrorCollector, SerializeConfig, WrapErr, Wrapped,
+};
+use std::backtrace::Backtrace;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+struct Traced(Backtrace);
+
+impl Traced {
+    fn new() -> Self {
+        Traced(Backtrace::force_capture())
+    }
+}
+
+impl fmt::Display for Traced {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("traced root")
+    }
+}
+
+impl Error for Traced {
+    fn backtrace(&self) -> Option<&Backtrace> {
+        Some(&self.0)
+    }
+}
+
+fn traced_chain() -> Wrapped<Traced> {
+    Err::<(), _>(Traced::new()).wrap_err("outer").unwrap_err()
+}
+
+#[test]
+fn serialize_backtrace_frames() {
+    let err = traced_chain();
+    let config = SerializeConfig::new().backtraces(BacktraceMode::Frames);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+
+    assert!(json["backtrace"].is_null());
+    let frames = json["source"]["backtrace"].as_array().unwrap();
+    assert!(frames.iter().all(|frame| frame["symbol"].is_string()));
+    assert!(frames.iter().any(|frame| {
+        frame["symbol"]
+            .as_str()
+            .unwrap()
+            .starts_with("backtrace::traced_chain")
+    }));
+}
+
+#[test]
+fn serialize_backtrace_string_by_default() {
+    let err = traced_chain();
+    let default = serde_json::to_value(&err.serialize()).unwrap();
+    let json = serde_json::to_value(&err.serialize_with(&SerializeConfig::new())).unwrap();
+
+    assert!(json["source"]["backtrace"].is_string());
+    assert_eq!(json, default);
+}
+
+fn strip_backtraces(value: &mut serde_json::Value) {
+    if let Some(map) = value.as_object_mut() {
+        map.remove("backtrace");
+        map.values_mut().for_each(strip_backtraces);
+    }
+}
+
+#[test]
+fn serialize_without_backtraces() {
+    let err = traced_chain();
+    let config = SerializeConfig::new().backtraces(BacktraceMode::Omit);
+    let json = serde_json::to_string(&err.serialize_with(&config)).unwrap();
+    assert!(!json.contains("\"backtrace\":"));
+
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let mut expected = serde_json::to_value(&err.serialize()).unwrap();
+    strip_backtraces(&mut expected);
+    assert_eq!(json, expected);
+    assert_eq!(json["source"]["msg"], "traced root");
+}
+
+/// Forwards the backtrace of its source as its own, like errors built with
+/// `#[backtrace] source` do
+#[derive(Debug)]
+struct Forwarding(Traced);
+
+impl fmt::Display for Forwarding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("forwarding")
+    }
+}
+
+impl Error for Forwarding {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        self.0.backtrace()
+    }
+}
+
+#[derive(Debug)]
+struct Recaptured(Traced, Backtrace);
+
+impl fmt::Display for Recaptured {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("recaptured")
+    }
+}
+
+impl Error for Recaptured {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        Some(&self.1)
+    }
+}
+
+fn full_traces(json: &str) -> usize {
+    json.matches("backtrace::Traced::new").count()
+}
+
+#[test]
+fn serialize_deduplicated_backtraces() {
+    let err = Wrapped::from((Forwarding(Traced::new()), "outer".to_string()));
+    let json = serde_json::to_string(&err.serialize()).unwrap();
+    assert_eq!(full_traces(&json), 2);
+
+    let config = SerializeConfig::new().duplicate_backtraces(DuplicateBacktraces::Marker);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    assert_eq!(full_traces(&json.to_string()), 1);
+    assert_eq!(json["source"]["backtrace"], "(see source)");
+    assert!(json["source"]["source"]["backtrace"].is_string());
+
+    let config = SerializeConfig::new().duplicate_backtraces(DuplicateBacktraces::Null);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    assert_eq!(full_traces(&json.to_string()), 1);
+    assert!(json["source"]["backtrace"].is_null());
+}
+
+#[test]
+fn deduplicate_within_serialized_chain() {
+    let config = SerializeConfig::new().duplicate_backtraces(DuplicateBacktraces::Marker);
+
+    let err = Forwarding(Traced::new());
+    let json = serde_json::to_value(&err.serialize_with(&config.clone().max_depth(1))).unwrap();
+    assert_eq!(full_traces(&json.to_string()), 1);
+    assert!(json["backtrace"].is_string());
+    assert_ne!(json["backtrace"], "(see source)");
+
+    let mut errors = ErrorCollector::new();
+    errors.push(Forwarding(Traced::new()));
+    errors.push(Forwarding(Traced::new()));
+    let err = errors.finish().unwrap_err();
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    assert_eq!(full_traces(&json.to_string()), 2);
+    for error in json["errors"].as_array().unwrap() {
+        assert_eq!(error["backtrace"], "(see source)");
+        assert!(error["source"]["backtrace"].is_string());
+    }
+}
+
+#[test]
+fn keep_distinct_backtraces() {
+    let err = Recaptured(Traced::new(), Backtrace::force_capture());
+    let config = SerializeConfig::new().duplicate_backtraces(DuplicateBacktraces::Marker);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+
+    assert!(json["backtrace"]
+        .as_str()
+        .unwrap()
+        .contains("keep_distinct"));
+    assert!(json["source"]["backtrace"].is_string());
+    assert_ne!(json["backtrace"], json["source"]["backtrace"]);
+}
+
+#[test]
+fn serialize_stripped_backtrace_prefixes() {
+    let err = traced_chain();
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let config =
+        SerializeConfig::new().default_backtrace_prefixes(option_env!("CARGO_MANIFEST_DIR"));
+
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    let backtrace = json["source"]["backtrace"].as_str().unwrap();
+    assert!(!backtrace.contains(manifest_dir));
+    assert!(backtrace.contains("tests/backtrace.rs:"));
+
+    let config = config.backtraces(BacktraceMode::Frames);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    let frames = json["source"]["backtrace"].as_array().unwrap();
+    assert!(frames.iter().any(|frame| {
+        let file = frame["file"].as_str().unwrap_or_default();
+        !file.contains(manifest_dir)
+            && file.ends_with("tests/backtrace.rs")
+            && frame["line"].is_u64()
+    }));
+}
+
+#[test]
+fn round_trip_backtrace() {
+    let err = Traced::new();
+    let json = serde_json::to_string(&err.serialize()).unwrap();
+    let de: errtools::deserialize::Error = serde_json::from_str(&json).unwrap();
+
+    let expected = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+    assert_eq!(de.backtrace_str(), expected["backtrace"].as_str());
+    assert!(format!("{:#}", de).contains("\n\nStack backtrace:\n"));
+    assert_eq!(de.to_string(), "traced root");
+}
+
+#[test]
+fn reserialize_deserialized_backtrace() {
+    let err = traced_chain();
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+    let de: errtools::deserialize::Error = serde_json::from_value(json.clone()).unwrap();
+    assert!(de.backtrace_str().is_none());
+
+    let de: &dyn Error = &de;
+    let again = serde_json::to_value(&de.serialize()).unwrap();
+    let backtrace = again["source"]["backtrace"].as_str().unwrap();
+    assert_eq!(backtrace, json["source"]["backtrace"].as_str().unwrap());
+    assert!(again["backtrace"].is_null());
+
+    let owned = serde_json::to_value(&de.serialize().to_owned()).unwrap();
+    assert_eq!(again, owned);
+}
+
+#[test]
+fn round_trip_backtrace_frames() {
+    let err = traced_chain();
+    let config = SerializeConfig::new().backtraces(BacktraceMode::Frames);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    let de: errtools::deserialize::Error = serde_json::from_value(json.clone()).unwrap();
+
+    let source = de.sources().next().unwrap();
+    assert!(source.backtrace_str().is_none());
+    let frames = source.frames().unwrap().collect::<Vec<_>>();
+    assert_eq!(
+        frames.len(),
+        json["source"]["backtrace"].as_array().unwrap().len()
+    );
+    assert!(frames
+        .iter()
+        .any(|frame| frame.symbol.starts_with("backtrace::traced_chain")
+            && frame
+                .file
+                .as_deref()
+                .unwrap()
+                .ends_with("tests/backtrace.rs")
+            && frame.line.is_some()));
+    assert_eq!(serde_json::to_value(&de).unwrap()["source"], json["source"]);
+
+    let de: &dyn Error = &de;
+    let again = serde_json::to_value(&de.serialize_with(&config)).unwrap();
+    assert_eq!(again["source"]["backtrace"], json["source"]["backtrace"]);
+}
+
+#[test]
+fn deserialize_backtrace_forms() {
+    use errtools::deserialize::{Backtrace, Error as DeError};
+
+    let json = r#"{"msg": "boom", "backtrace": "   0: app::main\n"}"#;
+    let de: DeError = serde_json::from_str(json).unwrap();
+    assert_eq!(de.backtrace_str(), Some("   0: app::main\n"));
+    assert!(de.frames().is_none());
+
+    let json = r#"{
+        "msg": "boom",
+        "backtrace": [
+            {"symbol": "app::run", "file": "src/run.rs", "line": 12},
+            {"symbol": "app::main", "file": null, "line": null}
+        ]
+    }"#;
+    let de: DeError = serde_json::from_str(json).unwrap();
+    let frames = de.backtrace().and_then(Backtrace::frames).unwrap();
+    assert_eq!(frames[0].symbol, "app::run");
+    assert_eq!(frames[0].file.as_deref(), Some("src/run.rs"));
+    assert_eq!(frames[0].line, Some(12));
+    assert_eq!(frames[1].file, None);
+    assert_eq!(
+        de.backtrace().unwrap().to_string(),
+        "   0: app::run\n             at src/run.rs:12\n   1: app::main\n"
+    );
+
+    let json = r#"{"msg": "boom", "backtrace": [{"file": "src/run.rs", "line": 12}]}"#;
+    let err = serde_json::from_str::<DeError>(json).unwrap_err();
+    assert!(err.to_string().contains("missing field `symbol`"));
+
+    let json = r#"{"msg": "boom", "backtrace": [42]}"#;
+    let err = serde_json::from_str::<DeError>(json).unwrap_err();
+    assert!(err.to_string().contains("expected a backtrace frame"));
+}
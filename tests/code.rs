@@ -0,0 +1,152 @@
+use errtools::{Coded, ErrTools, ExitStatus, HttpStatus, MainReport, Wrapped};
+use std::error::Error;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+enum ApiError {
+    #[error("record not found")]
+    NotFound,
+    #[error("request failed")]
+    Request(#[source] Box<dyn Error + Send + Sync + 'static>),
+}
+
+impl Coded for ApiError {
+    fn code(&self) -> Option<&str> {
+        match self {
+            ApiError::NotFound => Some("not_found"),
+            ApiError::Request(_) => Some("request_failed"),
+        }
+    }
+}
+
+fn chain() -> Wrapped<ApiError> {
+    errtools::registry::register_coded::<ApiError>();
+
+    let err = ApiError::Request(Box::new(ApiError::NotFound));
+    Wrapped::from((err, "handler failed".to_string()))
+}
+
+#[test]
+fn code_in_chain_is_outermost() {
+    let err = chain();
+    assert_eq!(err.code_in_chain(), Some("request_failed"));
+
+    let err: &dyn Error = &err.source;
+    assert_eq!(err.source().unwrap().code_in_chain(), Some("not_found"));
+
+    let err: &dyn Error = &std::io::Error::new(std::io::ErrorKind::Other, "uncoded");
+    assert_eq!(err.code_in_chain(), None);
+}
+
+#[test]
+fn serialize_codes() {
+    let err = chain();
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+
+    assert!(json.get("code").is_none());
+    assert_eq!(json["source"]["code"], "request_failed");
+    assert_eq!(json["source"]["source"]["code"], "not_found");
+}
+
+#[derive(Error, Debug)]
+enum CliError {
+    #[error("unable to read input")]
+    NoInput,
+    #[error("service unavailable")]
+    Unavailable,
+}
+
+impl ExitStatus for CliError {
+    fn exit_code(&self) -> Option<u8> {
+        match self {
+            CliError::NoInput => Some(66),
+            CliError::Unavailable => Some(75),
+        }
+    }
+}
+
+#[test]
+fn exit_code_in_chain_is_outermost() {
+    errtools::registry::register_exit_status::<CliError>();
+
+    let err = Wrapped::from((CliError::Unavailable, "request failed".to_string()));
+    let err = Wrapped::from((err, "sync failed".to_string()));
+    let err = Wrapped::from((err, "unable to sync".to_string()));
+    assert_eq!(err.exit_code_in_chain(), Some(75));
+
+    let err = Wrapped::from((CliError::NoInput, "unable to load".to_string()));
+    let err = Wrapped::from((err, "unable to sync".to_string()));
+    assert_eq!(err.exit_code_in_chain(), Some(66));
+    assert_eq!(MainReport::from(err).exit_code(), 66);
+
+    let err = Wrapped::from((CliError::Unavailable, "request failed".to_string()));
+    assert_eq!(MainReport::from(err).exit_code(), 75);
+}
+
+#[test]
+fn exit_code_defaults_without_status() {
+    let err = chain();
+    assert_eq!(err.exit_code_in_chain(), None);
+    assert_eq!(MainReport::from(err).exit_code(), 1);
+    assert_eq!(MainReport::from("unable to sync").exit_code(), 1);
+}
+
+#[derive(Error, Debug)]
+enum StoreError {
+    #[error("row not found")]
+    NoRow,
+    #[error("upstream timed out")]
+    Timeout(#[source] std::io::Error),
+}
+
+impl HttpStatus for StoreError {
+    fn http_status(&self) -> Option<u16> {
+        match self {
+            StoreError::NoRow => Some(404),
+            StoreError::Timeout(_) => Some(503),
+        }
+    }
+}
+
+#[test]
+fn http_status_in_chain_is_outermost() {
+    errtools::registry::register_http_status::<StoreError>();
+
+    let err = Wrapped::from((StoreError::NoRow, "unable to load user".to_string()));
+    let err = Wrapped::from((err, "request failed".to_string()));
+    assert_eq!(err.http_status_in_chain(), Some(404));
+
+    let io = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+    let err = Wrapped::from((StoreError::Timeout(io), "unable to sync".to_string()));
+    assert_eq!(err.http_status_in_chain(), Some(503));
+
+    let err: &(dyn Error + 'static) = &err;
+    assert_eq!(err.http_status_in_chain(), Some(503));
+}
+
+#[test]
+fn http_status_falls_back_to_io_error_kind() {
+    let io = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+    let err = Wrapped::from((io, "unable to load config".to_string()));
+    assert_eq!(err.http_status_in_chain(), Some(404));
+
+    let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "permission denied");
+    let err: Box<dyn Error + Send + Sync + 'static> = Box::new(io);
+    assert_eq!(err.http_status_in_chain(), Some(403));
+
+    let io = std::io::Error::new(std::io::ErrorKind::Other, "disk full");
+    assert_eq!(io.http_status_in_chain(), None);
+}
+
+#[test]
+fn http_status_fallback_is_extensible() {
+    fn parse_errors(error: &(dyn Error + 'static)) -> Option<u16> {
+        error.downcast_ref::<std::num::ParseIntError>().map(|_| 422)
+    }
+
+    errtools::registry::register_http_status_fallback(parse_errors);
+
+    let err = "twelve".parse::<u32>().unwrap_err();
+    let err = Wrapped::from((err, "invalid page size".to_string()));
+    assert_eq!(err.http_status_in_chain(), Some(422));
+}
@@ -0,0 +1,32 @@
+//! Errors shared by the integration tests
+#![allow(dead_code)]
+use std::error::Error;
+use std::fmt;
+
+/// An error with no source, displayed as `root cause`
+#[derive(Debug)]
+pub struct RootError;
+
+impl fmt::Display for RootError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "root cause")
+    }
+}
+
+impl Error for RootError {}
+
+/// An error caused by a [`RootError`], displayed as `second error`
+#[derive(Debug)]
+pub struct SecondError(pub RootError);
+
+impl fmt::Display for SecondError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "second error")
+    }
+}
+
+impl Error for SecondError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
@@ -0,0 +1,142 @@
+use errtools::problem::Problem;
+use errtools::{deserialize, report, ChainLayout, ErrTools, SerializeConfig};
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt;
+
+const MARKER: &str = "<cyclic error chain truncated>";
+
+/// An error whose source can be pointed back at an earlier error
+#[derive(Debug)]
+struct Link {
+    name: &'static str,
+    next: Cell<Option<&'static Link>>,
+}
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+impl Error for Link {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.next.get().map(|next| next as _)
+    }
+}
+
+fn link(name: &'static str) -> &'static Link {
+    Box::leak(Box::new(Link {
+        name,
+        next: Cell::new(None),
+    }))
+}
+
+/// `first -> second -> third -> second -> ...`
+fn cyclic_chain() -> &'static Link {
+    let (first, second, third) = (link("first"), link("second"), link("third"));
+    first.next.set(Some(second));
+    second.next.set(Some(third));
+    third.next.set(Some(second));
+    first
+}
+
+fn messages(error: &dyn Error) -> Vec<String> {
+    std::iter::successors(Some(error), |&error| error.source())
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test]
+fn serialize_cyclic_chain() {
+    let err = cyclic_chain();
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+
+    assert_eq!(json["source"]["source"]["msg"], "third");
+    assert_eq!(json["source"]["source"]["source"]["msg"], MARKER);
+    assert!(json["source"]["source"]["source"]["source"].is_null());
+
+    let owned = serde_json::to_value(&err.serialize().to_owned()).unwrap();
+    assert_eq!(json, owned);
+
+    let de: deserialize::Error = serde_json::from_value(json).unwrap();
+    assert_eq!(messages(&de), ["first", "second", "third", MARKER]);
+}
+
+#[test]
+fn serialize_cyclic_chain_flat() {
+    let err = cyclic_chain();
+    let config = SerializeConfig::new().layout(ChainLayout::Flat);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+
+    let chain = json["chain"].as_array().unwrap();
+    assert_eq!(chain.len(), 4);
+    assert_eq!(chain[3], serde_json::json!({ "msg": MARKER }));
+}
+
+#[test]
+fn serialize_cyclic_chain_binary() {
+    let err: &dyn Error = cyclic_chain();
+    let bytes = bincode::serialize(&err.serialize()).unwrap();
+    let de: deserialize::Error = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(messages(&de), ["first", "second", "third", MARKER]);
+}
+
+#[test]
+fn field_map_cyclic_chain() {
+    let err = cyclic_chain();
+    let fields = err.to_field_map();
+
+    assert_eq!(fields["error.source.source.msg"], "third");
+    assert!(!fields.contains_key("error.source.source.source.msg"));
+    assert_eq!(fields["error.root.msg"], "third");
+    assert_eq!(fields["error.chain_len"], "3");
+}
+
+#[test]
+fn problem_detail_cyclic_chain() {
+    let problem = Problem::from_error(cyclic_chain(), 500u16);
+    let json = serde_json::to_value(&problem).unwrap();
+
+    assert_eq!(json["detail"], "first: second: third");
+}
+
+#[cfg(feature = "sentry")]
+#[test]
+fn sentry_exception_cyclic_chain() {
+    let exception = errtools::sentry::to_sentry_exception(cyclic_chain());
+    let json = serde_json::to_value(&exception).unwrap();
+
+    let values = json["values"].as_array().unwrap();
+    let values = values
+        .iter()
+        .map(|value| &value["value"])
+        .collect::<Vec<_>>();
+    assert_eq!(values, ["third", "second", "first"]);
+}
+
+#[test]
+fn walk_cyclic_chain() {
+    let err: &(dyn Error + 'static) = cyclic_chain();
+
+    assert_eq!(err.to_one_line().to_string(), "first: second: third");
+    assert_eq!(
+        report::numbered(err).to_string(),
+        "0: first\n1: second\n2: third"
+    );
+    assert!(report::diff(err, &["first", "second", "third"]).is_none());
+    assert!(err.downcast_refchain::<std::io::Error>().is_none());
+    assert_eq!(err.code_in_chain(), None);
+    assert_eq!(err.exit_code_in_chain(), None);
+    assert_eq!(err.http_status_in_chain(), None);
+}
+
+#[test]
+fn truncate_cyclic_chain() {
+    let err = cyclic_chain();
+    let config = SerializeConfig::new().max_depth(2);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+
+    assert_eq!(json["source"]["source"]["msg"], "… 1 more error truncated");
+    assert_eq!(json["source"]["source"]["truncated"], 1);
+}
@@ -0,0 +1,782 @@
+use errtools::deserialize::{self, DeserializeConfig, Error};
+use errtools::{
+    BacktraceMode, ChainLayout, Coded, ErrTools, ErrorCollector, ErrorMetadata, MetadataValue,
+    SerializeConfig, Wrapped,
+};
+use serde::de::DeserializeSeed;
+use std::io;
+
+fn fixture() -> Error {
+    Error::new("unable to load config")
+        .with_type_name("app::LoadError")
+        .with_source(
+            Error::new("unable to read file")
+                .with_type_name("app::ReadError")
+                .with_source(Error::new("permission denied")),
+        )
+}
+
+#[test]
+fn build_nested_chain() {
+    let err = fixture();
+
+    let nodes = err
+        .chain()
+        .map(|error| (error.msg(), error.type_name()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        nodes,
+        [
+            ("unable to load config", Some("app::LoadError")),
+            ("unable to read file", Some("app::ReadError")),
+            ("permission denied", None),
+        ]
+    );
+
+    let source = std::error::Error::source(&err).unwrap();
+    assert_eq!(source.to_string(), "unable to read file");
+    assert_eq!(deserialize::type_name_of(source), Some("app::ReadError"));
+}
+
+#[test]
+fn serialize_built_chain() {
+    let err = fixture();
+    let json = serde_json::to_string(&err.serialize()).unwrap();
+    let de: Error = serde_json::from_str(&json).unwrap();
+
+    let messages = de.chain().map(Error::msg).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "unable to load config",
+            "unable to read file",
+            "permission denied"
+        ]
+    );
+    assert!(de.chain().all(|error| error.backtrace_str().is_none()));
+}
+
+#[test]
+fn accessors() {
+    let json = r#"{
+        "v": 1,
+        "type_name": "app::SyncError",
+        "msg": "sync of account 42 failed",
+        "source": {
+            "type_name": "app::HttpError",
+            "msg": "request to upstream failed",
+            "source": {
+                "msg": "connection reset by peer",
+                "backtrace": "0: main",
+                "source": null
+            }
+        }
+    }"#;
+    let err: Error = serde_json::from_str(json).unwrap();
+
+    assert_eq!(err.msg(), "sync of account 42 failed");
+    assert_eq!(err.type_name(), Some("app::SyncError"));
+    assert_eq!(err.chain_len(), 3);
+
+    let sources = err.sources().collect::<Vec<_>>();
+    assert_eq!(sources.len(), 2);
+    assert_eq!(sources[0].msg(), "request to upstream failed");
+    assert_eq!(sources[0].type_name(), Some("app::HttpError"));
+    assert_eq!(sources[0].chain_len(), 2);
+    assert_eq!(sources[1].msg(), "connection reset by peer");
+    assert_eq!(sources[1].type_name(), None);
+
+    let root = err.root();
+    assert!(root.msg().contains("reset"));
+    assert_eq!(root.backtrace_str(), Some("0: main"));
+    assert_eq!(root.chain_len(), 1);
+    assert!(std::ptr::eq(root.root(), root));
+}
+
+#[test]
+fn builder_matches_json() {
+    let built = Error::builder()
+        .type_name("app::SyncError")
+        .msg("sync of account 42 failed")
+        .backtrace("0: app::sync")
+        .source(
+            Error::builder()
+                .type_name("app::HttpError")
+                .msg("request to upstream failed"),
+        )
+        .causes(vec!["connection reset by peer", "broken pipe"])
+        .build();
+
+    let json = r#"{
+        "type_name": "app::SyncError",
+        "msg": "sync of account 42 failed",
+        "backtrace": "0: app::sync",
+        "source": {
+            "type_name": "app::HttpError",
+            "msg": "request to upstream failed",
+            "source": {
+                "msg": "connection reset by peer",
+                "source": {"msg": "broken pipe", "source": null}
+            }
+        }
+    }"#;
+    let parsed: Error = serde_json::from_str(json).unwrap();
+
+    let report = |err: &Error| format!("{:?}", err.serialize());
+    assert_eq!(report(&built), report(&parsed));
+    assert_eq!(
+        serde_json::to_value(&built.serialize()).unwrap(),
+        serde_json::to_value(&parsed.serialize()).unwrap()
+    );
+    assert_eq!(built.root().msg(), "broken pipe");
+    assert_eq!(built.backtrace_str(), Some("0: app::sync"));
+}
+
+#[test]
+fn reserialize_lossless() {
+    let json = serde_json::json!({
+        "v": 1,
+        "type_name": "app::SyncError",
+        "msg": "sync failed",
+        "errors": [
+            {
+                "type_name": "app::HttpError",
+                "msg": "request to upstream failed",
+                "backtrace": null,
+                "source": {"msg": "connection reset by peer", "backtrace": null, "source": null}
+            },
+            {"msg": "timed out", "backtrace": null, "source": null}
+        ],
+        "location": {"file": "src/sync.rs", "line": 42, "column": 9},
+        "backtrace": "0: app::sync",
+        "source": {
+            "msg": "unable to read file",
+            "backtrace": null,
+            "source": null
+        }
+    });
+    let err: Error = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&err).unwrap(), json);
+
+    let bytes = bincode::serialize(&err).unwrap();
+    let de: Error = bincode::deserialize(&bytes).unwrap();
+    let messages = de.chain().map(Error::msg).collect::<Vec<_>>();
+    assert_eq!(messages, ["sync failed", "unable to read file"]);
+    assert_eq!(de.backtrace_str(), Some("0: app::sync"));
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("unable to open the `{table}` table")]
+struct OpenError {
+    table: &'static str,
+    source: io::Error,
+}
+
+impl Coded for OpenError {
+    fn code(&self) -> Option<&str> {
+        Some("E_OPEN")
+    }
+}
+
+impl ErrorMetadata for OpenError {
+    fn metadata(&self) -> Vec<(&'static str, MetadataValue)> {
+        vec![
+            ("table", self.table.into()),
+            ("shard", 3.into()),
+            ("readonly", true.into()),
+        ]
+    }
+}
+
+#[test]
+fn reserialize_serialized_chain() {
+    errtools::registry::register_coded::<OpenError>();
+    errtools::registry::register_metadata::<OpenError>();
+
+    let err = OpenError {
+        table: "orders",
+        source: io::Error::from_raw_os_error(2),
+    };
+    let err = Wrapped::from((err, "nightly export failed".to_string()));
+    let config = SerializeConfig::new().include_debug(true);
+    let json = serde_json::to_string(&err.serialize_with(&config)).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["source"]["code"], "E_OPEN");
+    assert_eq!(value["source"]["source"]["os_code"], 2);
+
+    let parsed: Error = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+
+    let open = parsed.get(1).unwrap();
+    assert_eq!(open.code(), Some("E_OPEN"));
+    let meta = open.metadata().collect::<Vec<_>>();
+    assert_eq!(
+        meta,
+        [
+            ("table", &MetadataValue::from("orders")),
+            ("shard", &MetadataValue::from(3)),
+            ("readonly", &MetadataValue::from(true)),
+        ]
+    );
+    let io = parsed.root();
+    assert_eq!(io.io_kind(), Some("NotFound"));
+    assert_eq!(io.os_code(), Some(2));
+}
+
+#[test]
+fn strict_round_trip() {
+    errtools::registry::register_coded::<OpenError>();
+    errtools::registry::register_metadata::<OpenError>();
+
+    let mut errors = ErrorCollector::new();
+    errors.push(OpenError {
+        table: "orders",
+        source: io::Error::from_raw_os_error(2),
+    });
+    errors.push(errtools::fs::read_to_string("/this/path/does/not/exist.toml").unwrap_err());
+    let mut err = Wrapped::from((
+        errors.finish().unwrap_err(),
+        "nightly export failed".to_string(),
+    ));
+    err.push_related(errtools::adhoc!("lock not released"));
+
+    let strict = DeserializeConfig::new().strict(true).aliases(false);
+    let configs = [
+        SerializeConfig::new(),
+        SerializeConfig::new()
+            .include_debug(true)
+            .include_alternate(true),
+        SerializeConfig::new().backtraces(BacktraceMode::Frames),
+        SerializeConfig::new().layout(ChainLayout::Flat),
+        SerializeConfig::new().max_depth(1),
+    ];
+    for config in &configs {
+        let json = serde_json::to_string(&err.serialize_with(config)).unwrap();
+        let mut parsed = strict
+            .deserialize_error(&mut serde_json::Deserializer::from_str(&json))
+            .unwrap();
+
+        parsed.mark_received("export-svc");
+        let json = serde_json::to_string(&parsed).unwrap();
+        strict
+            .deserialize_error(&mut serde_json::Deserializer::from_str(&json))
+            .unwrap();
+    }
+}
+
+#[test]
+fn reserialize_deep_chains() {
+    let built = Error::builder()
+        .msg("sync failed")
+        .causes((0..300).map(|ind| format!("cause {}", ind)))
+        .build();
+    let pushed = (0..300).fold(Error::new("root cause"), |err, ind| {
+        err.push_context(format!("context {}", ind))
+    });
+
+    for err in [built, pushed] {
+        let json = serde_json::to_string(&err).unwrap();
+        let parsed: Error = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, err);
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+    }
+
+    let err = Error::builder()
+        .causes((0..200).map(|ind| format!("cause {}", ind)))
+        .build();
+    let bytes = bincode::serialize(&err).unwrap();
+    assert_eq!(bincode::deserialize::<Error>(&bytes).unwrap(), err);
+}
+
+fn hash_of(err: &Error) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    err.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn compare_parsed_chains() {
+    let payload = |root: &str, backtrace: &str| {
+        serde_json::json!({
+            "type_name": "app::SyncError",
+            "msg": "sync failed",
+            "backtrace": backtrace,
+            "source": {"msg": root, "backtrace": backtrace, "source": null}
+        })
+    };
+    let parse = |json| serde_json::from_value::<Error>(json).unwrap();
+
+    let a = parse(payload("connection reset by peer", "0: app::sync"));
+    let b = parse(payload("connection reset by peer", "0: app::retry"));
+    assert_eq!(a, b);
+    assert_eq!(hash_of(&a), hash_of(&b));
+    assert_eq!(a.clone(), a);
+
+    let c = parse(payload("broken pipe", "0: app::sync"));
+    assert_ne!(a, c);
+    assert_ne!(hash_of(&a), hash_of(&c));
+
+    let mut seen = std::collections::HashSet::new();
+    assert!(seen.insert(a));
+    assert!(!seen.insert(b));
+    assert!(seen.insert(c));
+}
+
+fn nested_json(depth: usize) -> String {
+    let mut json = String::new();
+    for _ in 0..depth {
+        json.push_str(r#"{"msg": "x", "source": "#);
+    }
+    json.push_str("null");
+    json.push_str(&"}".repeat(depth));
+    json
+}
+
+/// The fixed layout bincode reads, with no depth limit of its own
+fn nested_bincode(depth: usize) -> Vec<u8> {
+    let mut bytes = vec![];
+    for level in 0..depth {
+        bytes.push(0); // no type name
+        bytes.extend_from_slice(&1u64.to_le_bytes());
+        bytes.push(b'x');
+        bytes.push(0); // no backtrace
+        bytes.extend_from_slice(&0u64.to_le_bytes()); // no aggregated errors
+        bytes.extend_from_slice(&0u64.to_le_bytes()); // no related errors
+        bytes.push((level + 1 < depth) as u8);
+    }
+    bytes
+}
+
+#[test]
+fn reject_deeply_nested_chains() {
+    let json = nested_json(100_000);
+    assert!(serde_json::from_str::<Error>(&json).is_err());
+
+    let bytes = nested_bincode(100_000);
+    let err = bincode::deserialize::<Error>(&bytes).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("error chain is nested more than 257 levels deep"));
+
+    // The default limit has to fit the default stack of a spawned thread
+    let chain_len = std::thread::Builder::new()
+        .stack_size(2 << 20)
+        .spawn(|| {
+            let err: Error = bincode::deserialize(&nested_bincode(257)).unwrap();
+            err.chain_len()
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(chain_len, 257);
+    assert!(bincode::deserialize::<Error>(&nested_bincode(258)).is_err());
+
+    let config = DeserializeConfig::new().max_depth(16);
+    let parse = |json: &str| config.deserialize(&mut serde_json::Deserializer::from_str(json));
+    assert_eq!(parse(&nested_json(16)).unwrap().chain_len(), 16);
+
+    let err = parse(&nested_json(17)).unwrap_err();
+    assert!(err.to_string().contains("more than 16 levels deep"));
+
+    let flat = serde_json::json!({
+        "chain": (0..100).map(|ind| serde_json::json!({"msg": ind.to_string()})).collect::<Vec<_>>()
+    });
+    assert_eq!(parse(&flat.to_string()).unwrap().chain_len(), 100);
+}
+
+#[test]
+fn drop_deep_chain() {
+    let mut err = Error::new("root cause");
+    for _ in 0..200_000 {
+        err = Error::new("wrapper").with_source(err);
+    }
+
+    assert_eq!(err.root().msg(), "root cause");
+    drop(err);
+}
+
+#[test]
+fn deserialize_flat_chain() {
+    let err = fixture();
+    let config = SerializeConfig::new().layout(ChainLayout::Flat);
+    let flat = serde_json::to_string(&err.serialize_with(&config)).unwrap();
+    let nested = serde_json::to_string(&err.serialize()).unwrap();
+    assert!(flat.contains(r#""chain":"#));
+
+    let flat: Error = serde_json::from_str(&flat).unwrap();
+    let nested: Error = serde_json::from_str(&nested).unwrap();
+    assert_eq!(flat, nested);
+    assert_eq!(
+        format!("{:?}", flat.serialize()),
+        format!("{:?}", nested.serialize())
+    );
+    assert_eq!(flat.chain_len(), 3);
+
+    let json = r#"{"chain": []}"#;
+    assert!(serde_json::from_str::<Error>(json).is_err());
+    let json = r#"{"msg": "outer", "chain": [{"msg": "root"}]}"#;
+    assert!(serde_json::from_str::<Error>(json).is_err());
+    let json = r#"{"chain": [{"msg": "outer", "source": {"msg": "root"}}]}"#;
+    assert!(serde_json::from_str::<Error>(json).is_err());
+}
+
+#[test]
+fn deserialize_past_nesting_limit() {
+    let mut err = Error::new("error 99");
+    for i in (0..99).rev() {
+        err = Error::new(format!("error {}", i)).with_source(err);
+    }
+
+    let json = serde_json::to_string(&err.serialize()).unwrap();
+    assert!(json.contains(r#""chain":"#));
+
+    let de: Error = serde_json::from_str(&json).unwrap();
+    let messages = de.chain().map(Error::msg).collect::<Vec<_>>();
+    let expected = err.chain().map(Error::msg).collect::<Vec<_>>();
+    assert_eq!(messages.len(), 100);
+    assert_eq!(messages, expected);
+}
+
+#[test]
+fn deserialize_with_config() {
+    use bincode::Options;
+
+    let json = r#"{
+        "type": "app::SyncError",
+        "message": "sync failed",
+        "backtrace": "0: app::sync",
+        "trace_id": "4bf92f35",
+        "cause": {"msg": "request failed", "source": {"msg": "timed out"}}
+    }"#;
+    let parse = |config: DeserializeConfig| {
+        config.deserialize_error(&mut serde_json::Deserializer::from_str(json))
+    };
+
+    let err = parse(DeserializeConfig::default()).unwrap();
+    assert_eq!(err.type_name(), Some("app::SyncError"));
+    assert_eq!(err.chain_len(), 3);
+    assert_eq!(err.backtrace_str(), Some("0: app::sync"));
+
+    let err = parse(DeserializeConfig::new().strict(true).max_depth(2)).unwrap_err();
+    assert!(err.to_string().contains("unknown field `trace_id`"));
+    let err = parse(DeserializeConfig::new().max_depth(2)).unwrap_err();
+    assert!(err.to_string().contains("more than 2 levels deep"));
+
+    let err = parse(DeserializeConfig::new().aliases(false)).unwrap_err();
+    assert!(err.to_string().contains("missing field `msg`"));
+
+    let err = parse(DeserializeConfig::new().drop_backtraces(true)).unwrap();
+    assert!(err.chain().all(|error| error.backtrace().is_none()));
+    assert_eq!(err.msg(), "sync failed");
+
+    let bytes = bincode::serialize(&parse(DeserializeConfig::new()).unwrap()).unwrap();
+    let config = DeserializeConfig::new().drop_backtraces(true);
+    let err = config
+        .deserialize_error(&mut bincode::Deserializer::from_slice(
+            &bytes,
+            bincode::DefaultOptions::new()
+                .with_fixint_encoding()
+                .allow_trailing_bytes(),
+        ))
+        .unwrap();
+    assert!(err.backtrace().is_none());
+    assert_eq!(err.chain_len(), 3);
+}
+
+#[test]
+fn reserialize_source_spelling() {
+    // Keys sorted, since serde_json's map orders them
+    let canonical = |json: &str| {
+        serde_json::from_str::<serde_json::Value>(json)
+            .unwrap()
+            .to_string()
+    };
+
+    let payloads = [
+        r#"{"v": 1, "msg": "outer", "backtrace": null, "source": {"msg": "root", "backtrace": null}}"#,
+        r#"{"v": 1, "msg": "outer", "backtrace": null, "source": {"msg": "root", "backtrace": null, "source": null}}"#,
+        r#"{"v": 1, "msg": "leaf", "backtrace": null}"#,
+        r#"{"v": 1, "msg": "leaf", "backtrace": null, "source": null}"#,
+    ];
+    for json in &payloads {
+        let err: Error = serde_json::from_str(json).unwrap();
+        let again = serde_json::to_string(&err).unwrap();
+        assert_eq!(canonical(&again), canonical(json));
+
+        let err: &dyn std::error::Error = &err;
+        assert_eq!(err.source().is_some(), json.contains("root"));
+    }
+
+    let omitted: Error = serde_json::from_str(payloads[0]).unwrap();
+    let null: Error = serde_json::from_str(payloads[1]).unwrap();
+    assert_eq!(omitted, null);
+    assert_eq!(omitted.root().chain_len(), 1);
+    assert_eq!(null.root().chain_len(), 1);
+}
+
+#[test]
+fn deserialize_error_batch() {
+    use errtools::deserialize::ErrorBatch;
+    use errtools::{WrapErr, Wrapped};
+
+    let chains = r#"[
+        {"msg": "item 3 failed", "source": {"msg": "price is negative"}},
+        {"type_name": "app::Conflict", "msg": "item 7 failed"}
+    ]"#;
+    let bare: ErrorBatch = serde_json::from_str(chains).unwrap();
+    let envelope = format!(r#"{{"request_id": "6f1c", "errors": {}}}"#, chains);
+    let enveloped: ErrorBatch = serde_json::from_str(&envelope).unwrap();
+    assert_eq!(bare, enveloped);
+
+    assert_eq!(bare.len(), 2);
+    let messages = bare.iter().map(Error::msg).collect::<Vec<_>>();
+    assert_eq!(messages, ["item 3 failed", "item 7 failed"]);
+    assert_eq!(
+        bare.to_string(),
+        "2 errors occurred:\n    0: item 3 failed: price is negative\n    1: item 7 failed"
+    );
+    assert!(std::error::Error::source(&bare).is_none());
+    assert_eq!(
+        bare.clone().into_vec()[1].type_name(),
+        Some("app::Conflict")
+    );
+
+    let wrapped: Wrapped<ErrorBatch> = Err::<(), _>(bare)
+        .wrap_err("batch import failed")
+        .unwrap_err();
+    assert_eq!(
+        std::error::Error::source(&wrapped).unwrap().to_string(),
+        enveloped.to_string()
+    );
+
+    for json in &["[]", r#"{"errors": []}"#] {
+        let empty: ErrorBatch = serde_json::from_str(json).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.into_vec(), []);
+    }
+    assert!(serde_json::from_str::<ErrorBatch>(r#"{"request_id": "6f1c"}"#).is_err());
+    assert!(serde_json::from_str::<ErrorBatch>(r#"{"msg": "boom"}"#).is_err());
+}
+
+#[test]
+fn mark_received_errors() {
+    let json = serde_json::json!({
+        "v": 1,
+        "type_name": "billing::ChargeError",
+        "msg": "charge failed",
+        "backtrace": null,
+        "source": {"msg": "card declined", "backtrace": null, "source": null}
+    });
+    let mut err: Error = serde_json::from_value(json.clone()).unwrap();
+    let unmarked = err.clone();
+    err.mark_received("billing-svc");
+
+    assert_eq!(err.received_from(), Some("billing-svc"));
+    let at = err.received_at().unwrap();
+    assert_eq!(at.len(), "2024-05-01T12:00:00Z".len());
+    let report = format!("{:#}", err);
+    assert_eq!(
+        report,
+        format!("charge failed (received from billing-svc at {})", at)
+    );
+    assert_eq!(err.to_string(), "charge failed");
+    assert!(err.sources().all(|error| error.received_from().is_none()));
+    assert_eq!(err, unmarked);
+
+    let again = serde_json::to_value(&err).unwrap();
+    assert_eq!(again["received_from"], "billing-svc");
+    assert_eq!(again["received_at"], at);
+    let mut expected = json;
+    expected["received_from"] = "billing-svc".into();
+    expected["received_at"] = at.into();
+    assert_eq!(again, expected);
+
+    let de: Error = serde_json::from_value(again).unwrap();
+    assert_eq!(de.received_from(), Some("billing-svc"));
+    assert_eq!(de.received_at(), Some(at));
+
+    let json = r#"{"msg": "charge failed", "received_from": "billing-svc"}"#;
+    let err = serde_json::from_str::<Error>(json).unwrap_err();
+    assert!(err.to_string().contains("missing field `received_at`"));
+}
+
+#[test]
+fn push_context_layers() {
+    let json = r#"{"v": 1, "type_name": "orders::NotFound", "msg": "order 42 not found"}"#;
+    let err: Error = serde_json::from_str(json).unwrap();
+    let err = err
+        .push_context("while loading the order")
+        .push_context("while handling GET /orders/42");
+
+    assert_eq!(err.chain_len(), 3);
+    let nodes = err
+        .chain()
+        .map(|error| (error.msg(), error.type_name()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        nodes,
+        [
+            ("while handling GET /orders/42", None),
+            ("while loading the order", None),
+            ("order 42 not found", Some("orders::NotFound")),
+        ]
+    );
+
+    let again = serde_json::to_value(&err).unwrap();
+    assert_eq!(
+        again,
+        serde_json::json!({
+            "v": 1,
+            "msg": "while handling GET /orders/42",
+            "backtrace": null,
+            "source": {
+                "msg": "while loading the order",
+                "backtrace": null,
+                "source": {
+                    "type_name": "orders::NotFound",
+                    "msg": "order 42 not found",
+                    "backtrace": null
+                }
+            }
+        })
+    );
+    let de: Error = serde_json::from_value(again).unwrap();
+    assert_eq!(de, err);
+
+    let dyn_err: &dyn std::error::Error = &err;
+    assert_eq!(
+        dyn_err.serialize().to_string(),
+        "while handling GET /orders/42: while loading the order: order 42 not found"
+    );
+}
+
+#[test]
+fn take_chain_apart() {
+    use errtools::deserialize::Backtrace;
+
+    let json = r#"{
+        "type_name": "app::SyncError",
+        "msg": "sync failed",
+        "backtrace": "0: app::sync",
+        "source": {
+            "type_name": "app::HttpError",
+            "msg": "request failed",
+            "source": {"msg": "connection reset by peer"}
+        }
+    }"#;
+    let err: Error = serde_json::from_str(json).unwrap();
+
+    let expected = ["sync failed", "request failed", "connection reset by peer"];
+    assert_eq!(err.messages(), expected);
+    assert_eq!(err.clone().into_messages(), expected);
+
+    let parts = err.clone().into_parts();
+    let type_names = parts
+        .iter()
+        .map(|node| node.type_name.as_deref())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        type_names,
+        [Some("app::SyncError"), Some("app::HttpError"), None]
+    );
+    assert_eq!(
+        parts[0].backtrace,
+        Some(Backtrace::Text("0: app::sync".to_string()))
+    );
+    assert!(parts[1..].iter().all(|node| node.backtrace.is_none()));
+
+    let rebuilt = parts.into_iter().rev().fold(None, |source, node| {
+        let mut builder = Error::builder().msg(node.msg);
+        if let Some(type_name) = node.type_name {
+            builder = builder.type_name(type_name);
+        }
+        if let Some(backtrace) = node.backtrace {
+            builder = builder.backtrace(backtrace.to_string());
+        }
+        if let Some(source) = source {
+            builder = builder.source(source);
+        }
+        Some(builder.build())
+    });
+    let rebuilt = rebuilt.unwrap();
+    assert_eq!(rebuilt, err);
+    assert_eq!(rebuilt.backtrace_str(), Some("0: app::sync"));
+}
+
+#[test]
+fn get_by_depth() {
+    let json = r#"{
+        "type_name": "app::SyncError",
+        "msg": "sync failed",
+        "source": {
+            "type_name": "app::HttpError",
+            "msg": "request failed",
+            "source": {"msg": "connection reset by peer", "backtrace": "0: app::read"}
+        }
+    }"#;
+    let err: Error = serde_json::from_str(json).unwrap();
+
+    let nodes = (0..3)
+        .map(|depth| err.get(depth).unwrap())
+        .map(|error| (error.msg(), error.type_name(), error.backtrace_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        nodes,
+        [
+            ("sync failed", Some("app::SyncError"), None),
+            ("request failed", Some("app::HttpError"), None),
+            ("connection reset by peer", None, Some("0: app::read")),
+        ]
+    );
+    assert!(std::ptr::eq(err.get(0).unwrap(), &err));
+    assert!(std::ptr::eq(err.get(2).unwrap(), err.root()));
+    assert!(err.get(3).is_none());
+    assert!(err.get(usize::MAX).is_none());
+}
+
+#[test]
+fn deserialize_borrowed_chain() {
+    use errtools::deserialize::ErrorRef;
+
+    let json = serde_json::json!({
+        "v": 1,
+        "type_name": "app::SyncError",
+        "msg": "sync failed",
+        "errors": [{"msg": "timed out", "backtrace": null, "source": null}],
+        "location": {"file": "src/sync.rs", "line": 42, "column": 9},
+        "backtrace": "0: app::sync\n",
+        "source": {"msg": "unable to read \"config.toml\"", "backtrace": null}
+    });
+    let text = json.to_string();
+
+    let borrowed: ErrorRef<'_> = serde_json::from_str(&text).unwrap();
+    assert_eq!(borrowed.type_name(), Some("app::SyncError"));
+    assert_eq!(borrowed.backtrace_str(), Some("0: app::sync\n"));
+    assert_eq!(borrowed.location().unwrap().line(), 42);
+    assert_eq!(borrowed.root().msg(), r#"unable to read "config.toml""#);
+    assert_eq!(borrowed.sources().count(), 1);
+
+    let owned = borrowed.to_owned();
+    assert_eq!(owned, serde_json::from_str::<Error>(&text).unwrap());
+    assert_eq!(serde_json::to_value(&owned).unwrap(), json);
+
+    let bytes = bincode::serialize(&owned).unwrap();
+    let borrowed: ErrorRef<'_> = bincode::deserialize(&bytes).unwrap();
+    assert!(bytes.as_ptr_range().contains(&borrowed.msg().as_ptr()));
+    assert_eq!(borrowed.backtrace_str(), Some("0: app::sync\n"));
+    let de: Error = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(borrowed.to_owned(), de);
+
+    let deep = nested_json(2000);
+    assert!(serde_json::from_str::<ErrorRef<'_>>(&deep).is_err());
+    let flat = SerializeConfig::new().layout(ChainLayout::Flat);
+    let flat = serde_json::to_string(&fixture().serialize_with(&flat)).unwrap();
+    let borrowed: ErrorRef<'_> = serde_json::from_str(&flat).unwrap();
+    assert_eq!(borrowed.chain_len(), 3);
+    assert_eq!(
+        borrowed.to_owned(),
+        serde_json::from_str::<Error>(&flat).unwrap()
+    );
+}
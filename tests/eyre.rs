@@ -0,0 +1,45 @@
+#![cfg(feature = "eyre")]
+
+use errtools::eyre::SerializingHandler;
+use errtools::ErrTools;
+use eyre::{eyre, WrapErr};
+
+#[test]
+fn serializing_handler() {
+    errtools::eyre::install().unwrap();
+    assert!(errtools::eyre::install().is_err());
+
+    let root = eyre!("connection reset");
+    let handler = root.handler().downcast_ref::<SerializingHandler>().unwrap();
+    let json = serde_json::to_value(handler.serialized()).unwrap();
+    assert_eq!(json["msg"], "connection reset");
+    assert_eq!(json["source"], serde_json::Value::Null);
+
+    let mut report = root.wrap_err("request failed").wrap_err("unable to sync");
+    let expected = serde_json::to_value(report.serialize()).unwrap();
+    let serialized = errtools::eyre::serialized(&mut report).unwrap();
+    let json = serde_json::to_value(serialized).unwrap();
+    assert_eq!(json, expected);
+    assert_eq!(json["msg"], "unable to sync");
+    assert_eq!(json["source"]["msg"], "request failed");
+    assert_eq!(json["source"]["source"]["msg"], "connection reset");
+
+    let handler = report
+        .handler()
+        .downcast_ref::<SerializingHandler>()
+        .unwrap();
+    assert_eq!(serde_json::to_value(handler.serialized()).unwrap(), json);
+
+    assert_eq!(
+        format!("{:?}", report),
+        "unable to sync\n\nCaused by:\n    0: request failed\n    1: connection reset"
+    );
+
+    let err = Err::<(), _>(std::io::Error::from(std::io::ErrorKind::NotFound))
+        .wrap_err("unable to read config")
+        .unwrap_err();
+    let handler = err.handler().downcast_ref::<SerializingHandler>().unwrap();
+    let json = serde_json::to_value(handler.serialized()).unwrap();
+    assert_eq!(json["msg"], "unable to read config");
+    assert_eq!(json["source"]["type_name"], "std::io::error::Error");
+}
@@ -0,0 +1,78 @@
+use errtools::ErrTools;
+use std::error::Error;
+use std::fmt;
+
+mod common;
+
+use common::{RootError, SecondError};
+
+#[derive(Debug)]
+struct ThirdError(SecondError);
+
+impl fmt::Display for ThirdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "outermost error")
+    }
+}
+
+impl std::error::Error for ThirdError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+fn pairs(fields: &[(&str, &str)]) -> Vec<(String, String)> {
+    fields
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn three_deep_chain() {
+    let err = ThirdError(SecondError(RootError));
+    let fields = err.to_field_map().into_iter().collect::<Vec<_>>();
+
+    assert_eq!(
+        fields,
+        pairs(&[
+            ("error.chain_len", "3"),
+            ("error.msg", "outermost error"),
+            ("error.root.msg", "root cause"),
+            ("error.source.msg", "second error"),
+            ("error.source.source.msg", "root cause"),
+            ("error.type", "fields::ThirdError"),
+        ])
+    );
+}
+
+#[test]
+fn indexes_past_max_nesting() {
+    let err = ThirdError(SecondError(RootError));
+    let fields = err.to_field_map_with(1).into_iter().collect::<Vec<_>>();
+
+    assert_eq!(
+        fields,
+        pairs(&[
+            ("error.chain.2.msg", "root cause"),
+            ("error.chain_len", "3"),
+            ("error.msg", "outermost error"),
+            ("error.root.msg", "root cause"),
+            ("error.source.msg", "second error"),
+            ("error.type", "fields::ThirdError"),
+        ])
+    );
+}
+
+#[test]
+fn registered_types_in_chain() {
+    let err: Box<dyn Error + Send + Sync> = Box::new(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "disk on fire",
+    ));
+    let fields = err.to_field_map();
+
+    assert_eq!(fields["error.type"], "std::io::error::Error");
+    assert_eq!(fields["error.root.type"], "std::io::error::Error");
+    assert_eq!(fields["error.chain_len"], "1");
+}
@@ -0,0 +1,219 @@
+use errtools::{deserialize, ErrTools, ErrorCollector, SerializeConfig, SerializeableError};
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+
+mod common;
+
+use common::{RootError, SecondError};
+
+fn messages(error: &dyn Error) -> Vec<String> {
+    std::iter::successors(Some(error), |&error| error.source())
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[derive(Debug)]
+struct OuterError(SecondError);
+
+impl fmt::Display for OuterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "outermost error")
+    }
+}
+
+impl std::error::Error for OuterError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+fn chain() -> OuterError {
+    OuterError(SecondError(RootError))
+}
+
+const MESSAGES: &[&str] = &["outermost error", "second error", "root cause"];
+
+fn bincode_round_trip<T: Serialize>(value: &T) -> deserialize::Error {
+    bincode::deserialize(&bincode::serialize(value).unwrap()).unwrap()
+}
+
+fn postcard_round_trip<T: Serialize>(value: &T) -> deserialize::Error {
+    postcard::from_bytes(&postcard::to_allocvec(value).unwrap()).unwrap()
+}
+
+fn ciborium_round_trip<T: Serialize>(value: &T) -> deserialize::Error {
+    let mut bytes = vec![];
+    ciborium::ser::into_writer(value, &mut bytes).unwrap();
+    ciborium::de::from_reader(&bytes[..]).unwrap()
+}
+
+#[test]
+fn round_trip_binary_formats() {
+    let err = chain();
+    let dyn_err: &dyn Error = &err;
+
+    for round_trip in &[
+        bincode_round_trip::<SerializeableError<'_>>,
+        postcard_round_trip::<SerializeableError<'_>>,
+        ciborium_round_trip::<SerializeableError<'_>>,
+    ] {
+        let de = round_trip(&dyn_err.serialize());
+        assert_eq!(messages(&de), MESSAGES);
+        assert_eq!(de.type_name(), None);
+    }
+
+    let de = bincode_round_trip(&err.serialize());
+    assert_eq!(messages(&de), MESSAGES);
+    assert_eq!(de.type_name(), Some(std::any::type_name::<OuterError>()));
+    let de = postcard_round_trip(&err.serialize());
+    assert_eq!(messages(&de), MESSAGES);
+    let de = ciborium_round_trip(&err.serialize());
+    assert_eq!(messages(&de), MESSAGES);
+}
+
+#[test]
+fn round_trip_truncated_binary() {
+    let err = chain();
+    let config = SerializeConfig::new().max_depth(2);
+
+    let de = bincode_round_trip(&err.serialize_with(&config));
+    assert_eq!(
+        messages(&de),
+        [
+            "outermost error",
+            "second error",
+            "… 1 more error truncated"
+        ]
+    );
+    let de = postcard_round_trip(&err.serialize_with(&config));
+    assert_eq!(messages(&de).len(), 3);
+}
+
+#[test]
+fn mixed_paths_share_one_layout() {
+    let err = chain();
+    let dyn_err: &dyn Error = &err;
+
+    // Both paths write the same four fields, so a reader can't tell them
+    // apart and doesn't need to
+    let bytes = bincode::serialize(&(err.serialize(), dyn_err.serialize())).unwrap();
+    let (concrete, dynamic): (deserialize::Error, deserialize::Error) =
+        bincode::deserialize(&bytes).unwrap();
+    assert_eq!(messages(&concrete), messages(&dynamic));
+
+    let bytes = postcard::to_allocvec(&(dyn_err.serialize(), err.serialize())).unwrap();
+    let (dynamic, concrete): (deserialize::Error, deserialize::Error) =
+        postcard::from_bytes(&bytes).unwrap();
+    assert_eq!(messages(&concrete), messages(&dynamic));
+}
+
+#[test]
+fn fixed_layout_in_json() {
+    let err = chain();
+    let config = SerializeConfig::new()
+        .fixed_layout(true)
+        .include_debug(true);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+
+    let mut node = &json;
+    while !node.is_null() {
+        let mut keys = node.as_object().unwrap().keys().collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "backtrace",
+                "errors",
+                "msg",
+                "related",
+                "source",
+                "type_name"
+            ]
+        );
+        node = &node["source"];
+    }
+    assert!(json["source"]["source"]["type_name"].is_null());
+
+    let de: deserialize::Error = serde_json::from_value(json).unwrap();
+    assert_eq!(messages(&de), MESSAGES);
+}
+
+#[test]
+fn round_trip_dyn_paths_bincode() {
+    let boxed: Box<dyn Error + Send + Sync> = Box::new(chain());
+    let de = bincode_round_trip(&boxed.serialize());
+    assert_eq!(messages(&de), MESSAGES);
+    assert_eq!(de.type_name(), None);
+
+    let dyn_err: &dyn Error = boxed.as_ref();
+    let de = bincode_round_trip(&dyn_err.serialize().to_owned());
+    assert_eq!(messages(&de), MESSAGES);
+
+    let io_error = std::io::Error::new(std::io::ErrorKind::Other, SecondError(RootError));
+    let dyn_err: &dyn Error = &io_error;
+    let de = bincode_round_trip(&dyn_err.serialize());
+    assert_eq!(de.msg(), "second error");
+    assert_eq!(de.type_name(), Some("std::io::error::Error"));
+}
+
+#[derive(Debug)]
+struct Link(usize, Option<Box<Link>>);
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "link {}", self.0)
+    }
+}
+
+impl std::error::Error for Link {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.1.as_deref().map(|link| link as _)
+    }
+}
+
+#[test]
+fn deep_chain_truncated() {
+    let err = (1..10_000).fold(Link(0, None), |source, i| Link(i, Some(Box::new(source))));
+
+    let de = bincode_round_trip(&err.serialize());
+    assert_eq!(de.chain_len(), 257);
+    assert_eq!(de.get(255).unwrap().msg(), "link 9744");
+    assert_eq!(de.root().msg(), "… 9744 more errors truncated");
+
+    assert_eq!(bincode_round_trip(&err.serialize().to_owned()), de);
+    let config = SerializeConfig::new().fixed_layout(true);
+    let json = serde_json::to_string(&err.serialize_with(&config)).unwrap();
+    assert_eq!(json.matches("\"source\":{").count(), 256);
+
+    let config = SerializeConfig::new().max_depth(256);
+    assert_eq!(bincode_round_trip(&err.serialize_with(&config)), de);
+    let config = SerializeConfig::new().max_depth(5_000);
+    assert_eq!(bincode_round_trip(&err.serialize_with(&config)), de);
+}
+
+#[test]
+fn multi_error_round_trip() {
+    let mut errors = ErrorCollector::new();
+    errors.push(chain());
+    errors.push(RootError);
+    let err = errors.finish().unwrap_err();
+
+    let de = bincode_round_trip(&err.serialize());
+    assert_eq!(de.msg(), "2 errors occurred");
+    let aggregated = de.errors().iter().map(|e| messages(e)).collect::<Vec<_>>();
+    assert_eq!(
+        aggregated,
+        [messages(&chain()), vec!["root cause".to_string()]]
+    );
+
+    let owned = bincode_round_trip(&err.serialize().to_owned());
+    assert_eq!(owned.errors().len(), 2);
+    let reserialized = bincode_round_trip(&de);
+    assert_eq!(reserialized.errors()[0].chain_len(), 3);
+
+    let config = SerializeConfig::new().fixed_layout(true);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    assert_eq!(json["errors"][1]["msg"], "root cause");
+    assert_eq!(json["errors"][0]["source"]["errors"], serde_json::json!([]));
+}
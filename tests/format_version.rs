@@ -0,0 +1,69 @@
+use errtools::{deserialize, ErrTools, SerializeConfig};
+use std::error::Error;
+
+mod common;
+
+use common::{RootError, SecondError};
+
+fn messages(error: &dyn Error) -> Vec<String> {
+    std::iter::successors(Some(error), |&error| error.source())
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test]
+fn version_on_outermost_error() {
+    let json = serde_json::to_value(&SecondError(RootError).serialize()).unwrap();
+
+    assert_eq!(json["v"], 1);
+    assert!(json["source"].get("v").is_none());
+}
+
+#[test]
+fn version_can_be_disabled() {
+    let config = SerializeConfig::new().include_version(false);
+    let err = SecondError(RootError);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+
+    assert!(json.get("v").is_none());
+    assert_eq!(json["msg"], "second error");
+}
+
+#[test]
+fn frozen_v1_fixtures() {
+    let err: deserialize::Error =
+        serde_json::from_str(include_str!("fixtures/v1/nested.json")).unwrap();
+    assert_eq!(err.type_name(), Some("app::SyncError"));
+    assert_eq!(
+        messages(&err),
+        ["failed to sync account 42", "connection reset by peer"]
+    );
+
+    let err: deserialize::Error =
+        serde_json::from_str(include_str!("fixtures/v1/unversioned.json")).unwrap();
+    assert_eq!(err.type_name(), Some("app::ConfigError"));
+    assert_eq!(
+        messages(&err),
+        ["invalid configuration", "missing field `port`"]
+    );
+}
+
+#[test]
+fn read_newer_versions() {
+    let json = r#"{
+        "v": 99,
+        "msg": "from the future",
+        "severity": "warning",
+        "source": {"v": 99, "msg": "root cause", "backtrace": null}
+    }"#;
+    let err = serde_json::from_str::<deserialize::Error>(json).unwrap();
+    assert_eq!(messages(&err), ["from the future", "root cause"]);
+
+    let strict = deserialize::DeserializeConfig::new().strict(true);
+    let err = strict
+        .deserialize_error(&mut serde_json::Deserializer::from_str(json))
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("unsupported error format version 99"));
+}
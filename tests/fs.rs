@@ -0,0 +1,49 @@
+use errtools::ErrTools;
+use std::error::Error;
+
+const MISSING: &str = "/this/path/does/not/exist.toml";
+
+#[test]
+fn read_missing_file() {
+    let err = errtools::fs::read_to_string(MISSING).unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "failed to read file `/this/path/does/not/exist.toml`"
+    );
+    assert_eq!(err.operation(), "read file");
+    assert_eq!(err.path(), std::path::Path::new(MISSING));
+
+    let io_error = err.downcast_refchain::<std::io::Error>().unwrap();
+    assert_eq!(io_error.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn remove_missing_file() {
+    let err = errtools::fs::remove_file(MISSING).unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "failed to remove file `/this/path/does/not/exist.toml`"
+    );
+
+    let err: &dyn Error = &err;
+    assert!(err.downcast_refchain::<std::io::Error>().is_some());
+}
+
+#[test]
+fn serialize_fs_error_path() {
+    let err = errtools::fs::metadata(MISSING).unwrap_err();
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+
+    assert_eq!(json["path"], MISSING);
+    assert_eq!(
+        json["msg"],
+        "failed to read metadata of `/this/path/does/not/exist.toml`"
+    );
+    assert!(json["source"]["msg"].is_string());
+
+    let err: &dyn Error = &err;
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+    assert_eq!(json["path"], MISSING);
+}
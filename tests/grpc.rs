@@ -0,0 +1,99 @@
+#![cfg(feature = "tonic")]
+use errtools::grpc::{self, GrpcCode};
+use errtools::Wrapped;
+use std::error::Error;
+use std::fmt;
+use tonic::{Code, Status};
+
+mod common;
+
+use common::{RootError, SecondError};
+
+#[test]
+fn round_trip_status() {
+    let err = SecondError(RootError);
+    let status = grpc::to_status(&err);
+    assert_eq!(status.code(), Code::Internal);
+    assert_eq!(status.message(), "second error: root cause");
+
+    let received = grpc::from_status(&status).unwrap();
+    assert_eq!(received.to_string(), "second error");
+    assert_eq!(received.source().unwrap().to_string(), "root cause");
+    assert!(received.source().unwrap().source().is_none());
+}
+
+#[test]
+fn code_from_mapper() {
+    fn code(error: &(dyn Error + 'static)) -> Code {
+        if error.is::<SecondError>() {
+            Code::FailedPrecondition
+        } else {
+            Code::Unknown
+        }
+    }
+
+    assert_eq!(
+        grpc::to_status_with(&SecondError(RootError), &code).code(),
+        Code::FailedPrecondition
+    );
+    assert_eq!(
+        grpc::to_status_with(&RootError, &code).code(),
+        Code::Unknown
+    );
+
+    let status = grpc::try_to_status_with(&SecondError(RootError), &code).unwrap();
+    let plain = grpc::to_status_with(&SecondError(RootError), &code);
+    assert_eq!(status.code(), plain.code());
+    assert_eq!(status.message(), plain.message());
+    assert_eq!(status.details(), plain.details());
+}
+
+#[test]
+fn status_without_chain() {
+    let received = grpc::from_status(&Status::internal("plain")).unwrap();
+    assert_eq!(received.to_string(), "plain");
+    assert!(received.source().is_none());
+
+    let status = Status::with_details(Code::Internal, "garbage", b"not json"[..].into());
+    let received = grpc::from_status(&status).unwrap();
+    assert_eq!(received.to_string(), "garbage");
+    assert!(received.source().is_none());
+
+    assert!(grpc::from_status(&Status::new(Code::Ok, "")).is_none());
+}
+
+#[derive(Debug)]
+struct InvalidName;
+
+impl fmt::Display for InvalidName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid name")
+    }
+}
+
+impl std::error::Error for InvalidName {}
+
+impl GrpcCode for InvalidName {
+    fn grpc_code(&self) -> Option<Code> {
+        Some(Code::InvalidArgument)
+    }
+}
+
+#[test]
+fn code_from_chain() {
+    errtools::registry::register_grpc_code::<InvalidName>();
+
+    let err = Wrapped::from((InvalidName, "unable to create user".to_string()));
+    let status = grpc::to_status(&err);
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(status.message(), "unable to create user: invalid name");
+
+    let received = grpc::from_status(&status).unwrap();
+    assert_eq!(received.to_string(), "unable to create user");
+    assert_eq!(received.source().unwrap().to_string(), "invalid name");
+
+    let status = grpc::to_status_with_code(&SecondError(RootError), Code::Unavailable);
+    assert_eq!(status.code(), Code::Unavailable);
+    let status = grpc::to_status_with_code(&err, Code::Unavailable);
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
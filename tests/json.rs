@@ -0,0 +1,112 @@
+#![cfg(feature = "json")]
+use errtools::ErrTools;
+use std::error::Error;
+
+mod common;
+
+use common::{RootError, SecondError};
+
+#[test]
+fn value_matches_string() {
+    let err = SecondError(RootError);
+    let value = err.to_json_value().unwrap();
+
+    let parsed: serde_json::Value = serde_json::from_str(&err.to_json_string().unwrap()).unwrap();
+    assert_eq!(value, parsed);
+    let parsed: serde_json::Value =
+        serde_json::from_str(&err.to_json_string_pretty().unwrap()).unwrap();
+    assert_eq!(value, parsed);
+
+    assert_eq!(value["msg"], "second error");
+    assert_eq!(value["source"]["msg"], "root cause");
+    assert_eq!(value, serde_json::to_value(&err.serialize()).unwrap());
+}
+
+#[test]
+fn value_matches_string_dyn() {
+    let err: &dyn Error = &SecondError(RootError);
+    let value = err.to_json_value().unwrap();
+
+    let parsed: serde_json::Value = serde_json::from_str(&err.to_json_string().unwrap()).unwrap();
+    assert_eq!(value, parsed);
+    assert_eq!(value, serde_json::to_value(&err.serialize()).unwrap());
+}
+
+#[test]
+fn deserialize_from_value() {
+    use errtools::deserialize;
+
+    let envelope = serde_json::json!({
+        "request_id": "6f1c",
+        "status": 502,
+        "error": {
+            "type": "app::HttpError",
+            "message": "request to upstream failed",
+            "retryable": true,
+            "cause": {"msg": "connection reset by peer"}
+        }
+    });
+
+    let err = deserialize::Error::from_value_ref(&envelope["error"]).unwrap();
+    assert_eq!(err.type_name(), Some("app::HttpError"));
+    assert_eq!(err.msg(), "request to upstream failed");
+    assert_eq!(err.root().msg(), "connection reset by peer");
+
+    let owned = deserialize::Error::from_value(envelope["error"].clone()).unwrap();
+    assert_eq!(owned, err);
+
+    assert!(deserialize::Error::from_value_ref(&envelope["status"]).is_err());
+}
+
+#[test]
+fn deserialize_lossy() {
+    use errtools::deserialize::Error as DeError;
+
+    fn messages(err: &DeError) -> Vec<&str> {
+        err.chain().map(DeError::msg).collect()
+    }
+
+    let json = r#"{
+        "msg": "sync failed",
+        "source": {
+            "msg": "request failed",
+            "source": {"msg": "connection reset by peer", "source": null}
+        }
+    }"#;
+    let whole = DeError::from_json_lossy(json).unwrap();
+    assert_eq!(whole, serde_json::from_str(json).unwrap());
+
+    let cut = json.find("connection").unwrap() + 4;
+    let err = DeError::from_json_lossy(&json[..cut]).unwrap();
+    assert!(serde_json::from_str::<DeError>(&json[..cut]).is_err());
+    assert_eq!(
+        messages(&err),
+        [
+            "sync failed",
+            "request failed",
+            "<unparseable source: unexpected end of input>"
+        ]
+    );
+
+    let cut = json.rfind("null").unwrap() + 2;
+    let err = DeError::from_json_lossy(&json[..cut]).unwrap();
+    assert_eq!(messages(&err).len(), 4);
+    assert_eq!(messages(&err)[2], "connection reset by peer");
+
+    let json = r#"{"msg": "sync failed", "source": {"msg": "request \"failed\"", "cause": {"ms"#;
+    let err = DeError::from_json_lossy(json).unwrap();
+    assert_eq!(err.sources().next().unwrap().msg(), r#"request "failed""#);
+    assert_eq!(err.chain_len(), 3);
+
+    let json =
+        r#"{"msg": "sync failed", "source": {"msg": "request failed", "source": {"msg": 5}}}"#;
+    let err = DeError::from_json_lossy(json).unwrap();
+    assert_eq!(messages(&err)[1], "request failed");
+    assert!(err
+        .root()
+        .msg()
+        .starts_with("<unparseable source: invalid type: integer `5`"));
+
+    assert!(DeError::from_json_lossy(r#"{"msg": "sync fa"#).is_err());
+    assert!(DeError::from_json_lossy(r#"{"msg": 5, "source": null}"#).is_err());
+}
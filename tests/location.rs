@@ -0,0 +1,47 @@
+use errtools::{deserialize, ErrTools, Located, SerializeConfig, WrapErr, Wrapped};
+
+mod common;
+
+use common::RootError;
+
+#[test]
+fn wrap_err_records_caller() {
+    let line = line!() + 1;
+    let err: Wrapped<RootError> = Err::<(), _>(RootError).wrap_err("outer").unwrap_err();
+
+    let location = err.location().unwrap();
+    assert!(location.file().ends_with("location.rs"));
+    assert_eq!(location.line(), line);
+}
+
+#[test]
+fn serialize_location() {
+    let line = line!() + 1;
+    let err: Wrapped<RootError> = Err::<(), _>(RootError).wrap_err("outer").unwrap_err();
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+
+    let file = json["location"]["file"].as_str().unwrap();
+    assert!(file.ends_with("location.rs"));
+    assert_eq!(json["location"]["line"], line);
+    assert!(json["location"]["column"].is_u64());
+    assert!(json["source"].get("location").is_none());
+
+    let owned = serde_json::to_value(&err.serialize().to_owned()).unwrap();
+    assert_eq!(json, owned);
+
+    let de: deserialize::Error = serde_json::from_value(json).unwrap();
+    let location = de.location().unwrap();
+    assert!(location.file().ends_with("location.rs"));
+    assert_eq!(location.line(), line);
+}
+
+#[test]
+fn serialize_stripped_location() {
+    let err: Wrapped<RootError> = Err::<(), _>(RootError).wrap_err("outer").unwrap_err();
+    let file = err.location().unwrap().file();
+    let dir = &file[..file.rfind('/').unwrap()];
+    let config = SerializeConfig::new().backtrace_prefix(dir, "<tests>");
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+
+    assert_eq!(json["location"]["file"], "<tests>/location.rs");
+}
@@ -0,0 +1,118 @@
+#![cfg(feature = "log")]
+
+use errtools::deserialize::Error;
+use errtools::{log_err, LogErr};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::sync::{Mutex, Once};
+use std::thread::{self, ThreadId};
+
+#[derive(Debug, PartialEq)]
+struct Logged {
+    level: Level,
+    target: String,
+    message: String,
+    file: Option<String>,
+}
+
+/// Keeps every record, tagged with the thread that logged it so that tests
+/// running in parallel only see their own
+struct Capture(Mutex<Vec<(ThreadId, Logged)>>);
+
+impl Log for Capture {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let logged = Logged {
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+            file: record.file().map(str::to_string),
+        };
+        self.0
+            .lock()
+            .unwrap()
+            .push((thread::current().id(), logged));
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<Logged>) {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+    });
+
+    let value = f();
+    let mut records = CAPTURE.0.lock().unwrap();
+    let id = thread::current().id();
+    let (ours, theirs) = records.drain(..).partition(|(thread, _)| *thread == id);
+    *records = theirs;
+    (value, ours.into_iter().map(|(_, logged)| logged).collect())
+}
+
+fn failing() -> Result<u32, Error> {
+    Err(Error::builder()
+        .msg("unable to sync")
+        .causes(["request failed", "connection reset"])
+        .build())
+}
+
+const REPORT: &str = "unable to sync\n\nCaused by:\n    0: request failed\n    1: connection reset";
+
+#[test]
+fn log_err_logs_chain_once() {
+    let (result, logged) = capture(|| failing().log_err("app::sync"));
+
+    assert_eq!(result.unwrap_err().to_string(), "unable to sync");
+    assert_eq!(
+        logged,
+        vec![Logged {
+            level: Level::Error,
+            target: "app::sync".to_string(),
+            message: REPORT.to_string(),
+            file: Some(file!().to_string()),
+        }]
+    );
+}
+
+#[test]
+fn log_err_with_level_and_macro() {
+    let (_, logged) = capture(|| failing().log_err_with_level(Level::Warn));
+    assert_eq!(logged.len(), 1);
+    assert_eq!(logged[0].level, Level::Warn);
+    assert_eq!(logged[0].target, "errtools");
+    assert_eq!(logged[0].message, REPORT);
+
+    let (_, logged) = capture(|| log_err!(failing(), Level::Info));
+    assert_eq!(logged.len(), 1);
+    assert_eq!(logged[0].level, Level::Info);
+    assert_eq!(logged[0].target, module_path!());
+    assert_eq!(logged[0].message, REPORT);
+
+    let (_, logged) = capture(|| log_err!(failing()));
+    assert_eq!(logged[0].level, Level::Error);
+    assert_eq!(logged[0].target, module_path!());
+}
+
+#[test]
+fn log_err_passes_ok_through() {
+    fn run() -> Result<u32, Error> {
+        let value = Ok::<_, Error>(42).log_err("app::sync")?;
+        let _ = failing().log_err("app::sync")?;
+        Ok(value)
+    }
+
+    let (result, logged) = capture(run);
+    assert_eq!(result.unwrap_err().to_string(), "unable to sync");
+    assert_eq!(logged.len(), 1);
+    assert_eq!(
+        capture(|| Ok::<_, Error>(42).log_err("app::sync")).1,
+        vec![]
+    );
+}
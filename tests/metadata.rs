@@ -0,0 +1,47 @@
+use errtools::{ErrTools, ErrorMetadata, MetadataValue, Wrapped};
+use serde_json::json;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("query against `{table}` failed")]
+struct QueryError {
+    table: String,
+    job_id: i64,
+    retryable: bool,
+}
+
+impl ErrorMetadata for QueryError {
+    fn metadata(&self) -> Vec<(&'static str, MetadataValue)> {
+        vec![
+            ("table", self.table.as_str().into()),
+            ("job_id", self.job_id.into()),
+            ("retryable", self.retryable.into()),
+            ("table", "shadowed".into()),
+        ]
+    }
+}
+
+#[test]
+fn serialize_metadata() {
+    errtools::registry::register_metadata::<QueryError>();
+
+    let err = QueryError {
+        table: "orders".to_string(),
+        job_id: 7781,
+        retryable: false,
+    };
+    let err = Wrapped::from((err, "nightly export failed".to_string()));
+    let json = serde_json::to_string(&err.serialize()).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    assert!(json.get("meta").is_none());
+    assert_eq!(json["source"]["msg"], "query against `orders` failed");
+    assert_eq!(
+        json["source"]["meta"],
+        json!({
+            "table": "orders",
+            "job_id": 7781,
+            "retryable": false,
+        })
+    );
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "miette")]
+
+use errtools::miette::AsDiagnostic;
+use errtools::{registry, Coded, Help, WrapErr, Wrapped};
+use miette::Diagnostic;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+struct MissingConfig;
+
+impl fmt::Display for MissingConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("config file not found")
+    }
+}
+
+impl Error for MissingConfig {}
+
+impl Coded for MissingConfig {
+    fn code(&self) -> Option<&str> {
+        Some("config::missing")
+    }
+}
+
+impl Help for MissingConfig {
+    fn help(&self) -> Option<&str> {
+        Some("run `app init` to create one")
+    }
+}
+
+fn load() -> Result<(), MissingConfig> {
+    Err(MissingConfig)
+}
+
+fn text(diagnostic: Option<Box<dyn fmt::Display + '_>>) -> Option<String> {
+    diagnostic.map(|display| display.to_string())
+}
+
+#[test]
+fn diagnostic_from_chain() {
+    registry::register_coded::<MissingConfig>();
+    registry::register_help::<MissingConfig>();
+
+    let err: Wrapped<MissingConfig> = load().wrap_err("unable to start").unwrap_err();
+    let diagnostic = AsDiagnostic::new(&err);
+
+    assert_eq!(diagnostic.to_string(), "unable to start");
+    assert_eq!(text(diagnostic.code()).as_deref(), Some("config::missing"));
+    assert_eq!(
+        text(diagnostic.help()).as_deref(),
+        Some("run `app init` to create one")
+    );
+    assert!(diagnostic.related().is_none());
+
+    let source = diagnostic.diagnostic_source().unwrap();
+    assert_eq!(source.to_string(), "config file not found");
+    assert_eq!(text(source.code()).as_deref(), Some("config::missing"));
+    assert!(source.diagnostic_source().is_none());
+    assert!(source.source().is_none());
+}
+
+#[test]
+fn diagnostic_without_code_or_help() {
+    let err: Wrapped<std::io::Error> =
+        Err::<(), _>(std::io::Error::from(std::io::ErrorKind::NotFound))
+            .wrap_err("unable to read config")
+            .unwrap_err();
+    let err = err.with_related("unable to remove lock file");
+    let diagnostic = AsDiagnostic::from_wrapped(&err);
+
+    assert!(diagnostic.code().is_none());
+    assert!(diagnostic.help().is_none());
+
+    let related = diagnostic.related().unwrap().collect::<Vec<_>>();
+    assert_eq!(related.len(), 1);
+    assert_eq!(related[0].to_string(), "unable to remove lock file");
+}
@@ -0,0 +1,83 @@
+use errtools::{deserialize, ErrTools};
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+struct Layer {
+    depth: usize,
+    source: Option<Box<Layer>>,
+}
+
+impl Layer {
+    fn chain(len: usize) -> Self {
+        (1..len).fold(
+            Layer {
+                depth: 0,
+                source: None,
+            },
+            |source, depth| Layer {
+                depth,
+                source: Some(Box::new(source)),
+            },
+        )
+    }
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "layer {}", self.depth)
+    }
+}
+
+impl Error for Layer {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_deref().map(|source| source as _)
+    }
+}
+
+fn messages(error: &dyn Error) -> Vec<String> {
+    std::iter::successors(Some(error), |&error| error.source())
+        .map(ToString::to_string)
+        .collect()
+}
+
+fn compact<T: Serialize>(value: &T) -> deserialize::Error {
+    rmp_serde::from_slice(&rmp_serde::to_vec(value).unwrap()).unwrap()
+}
+
+fn named<T: Serialize>(value: &T) -> deserialize::Error {
+    rmp_serde::from_slice(&rmp_serde::to_vec_named(value).unwrap()).unwrap()
+}
+
+#[test]
+fn round_trip_concrete() {
+    let err = Layer::chain(3);
+    let expected = messages(&err);
+
+    for de in &[compact(&err.serialize()), named(&err.serialize())] {
+        assert_eq!(messages(de), expected);
+        assert_eq!(de.type_name(), Some(std::any::type_name::<Layer>()));
+    }
+}
+
+#[test]
+fn round_trip_dyn() {
+    let err = Layer::chain(3);
+    let err: &dyn Error = &err;
+    let expected = messages(err);
+
+    for de in &[compact(&err.serialize()), named(&err.serialize())] {
+        assert_eq!(messages(de), expected);
+        assert_eq!(de.type_name(), None);
+    }
+}
+
+#[test]
+fn round_trip_deep_chain() {
+    let err = Layer::chain(100);
+    let expected = messages(&err);
+
+    assert_eq!(messages(&compact(&err.serialize())), expected);
+    assert_eq!(messages(&named(&err.serialize())), expected);
+}
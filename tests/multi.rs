@@ -0,0 +1,110 @@
+use errtools::{adhoc, deserialize, ErrTools, ErrorCollector, MultiError, WrapErr, Wrapped};
+use std::error::Error;
+
+#[test]
+fn empty_collector_finishes_ok() {
+    let errors = ErrorCollector::new();
+    assert!(errors.is_empty());
+    assert!(errors.finish().is_ok());
+}
+
+#[test]
+fn collects_heterogeneous_errors_in_order() {
+    let mut errors = ErrorCollector::new();
+    errors.push("abc".parse::<u32>().unwrap_err());
+    errors.push(std::io::Error::new(std::io::ErrorKind::Other, "disk full"));
+    errors.push(adhoc!("record {} is missing a name", 7));
+    assert_eq!(errors.len(), 3);
+
+    let err = errors.finish().unwrap_err();
+    assert_eq!(err.to_string(), "3 errors occurred");
+
+    let messages = err.errors().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "invalid digit found in string",
+            "disk full",
+            "record 7 is missing a name",
+        ]
+    );
+}
+
+#[test]
+fn wrap_multi_error() {
+    let mut errors = ErrorCollector::new();
+    errors.push(adhoc!("first"));
+
+    let err: Wrapped<MultiError> = errors
+        .finish()
+        .wrap_err("validation of batch 3 failed")
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "validation of batch 3 failed");
+    assert_eq!(err.source.to_string(), "1 error occurred");
+}
+
+fn three_errors() -> Wrapped<MultiError> {
+    let mut errors = ErrorCollector::new();
+    errors.push("abc".parse::<u32>().unwrap_err());
+    errors.push(Wrapped::from((
+        adhoc!("disk full"),
+        "flush failed".to_string(),
+    )));
+    errors.push(adhoc!("record 7 is missing a name"));
+
+    errors
+        .finish()
+        .wrap_err("validation of batch 3 failed")
+        .unwrap_err()
+}
+
+#[test]
+fn serialize_multi_error() {
+    let err = three_errors();
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+
+    assert_eq!(json["msg"], "validation of batch 3 failed");
+    let multi = &json["source"];
+    assert_eq!(multi["msg"], "3 errors occurred");
+    assert!(multi["source"].is_null());
+
+    let errors = multi["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 3);
+    assert_eq!(errors[0]["msg"], "invalid digit found in string");
+    assert_eq!(errors[1]["msg"], "flush failed");
+    assert_eq!(errors[1]["source"]["msg"], "disk full");
+    assert_eq!(errors[2]["msg"], "record 7 is missing a name");
+    assert!(errors.iter().all(|error| error.get("v").is_none()));
+
+    let owned = serde_json::to_value(&err.serialize().to_owned()).unwrap();
+    assert_eq!(json, owned);
+}
+
+#[test]
+fn round_trip_multi_error() {
+    let err = three_errors();
+    let json = serde_json::to_string(&err.serialize()).unwrap();
+    let de: deserialize::Error = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(de.to_string(), "validation of batch 3 failed");
+    assert_eq!(de.source().unwrap().to_string(), "3 errors occurred");
+
+    let chains = de
+        .errors()
+        .iter()
+        .map(|error| {
+            std::iter::successors(Some(error as &dyn Error), |&error| error.source())
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        chains,
+        [
+            vec!["invalid digit found in string"],
+            vec!["flush failed", "disk full"],
+            vec!["record 7 is missing a name"],
+        ]
+    );
+}
@@ -0,0 +1,181 @@
+#![cfg(feature = "opentelemetry")]
+
+use errtools::deserialize::Error;
+use errtools::otel;
+use opentelemetry::trace::{mark_span_as_active, Span, SpanContext, Status};
+use opentelemetry::KeyValue;
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+type Attrs = Vec<(String, String)>;
+
+/// Keeps the name and attributes of every event added to it
+#[derive(Clone)]
+struct Capture {
+    context: SpanContext,
+    recording: bool,
+    events: Arc<Mutex<Vec<(String, Attrs)>>>,
+}
+
+impl Capture {
+    fn new() -> Self {
+        Self {
+            context: SpanContext::empty_context(),
+            recording: true,
+            events: Default::default(),
+        }
+    }
+
+    fn events(&self) -> Vec<(String, Attrs)> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl Span for Capture {
+    fn add_event_with_timestamp<T>(&mut self, name: T, _: SystemTime, attributes: Vec<KeyValue>)
+    where
+        T: Into<Cow<'static, str>>,
+    {
+        self.events
+            .lock()
+            .unwrap()
+            .push((name.into().into_owned(), attrs(&attributes)));
+    }
+
+    fn span_context(&self) -> &SpanContext {
+        &self.context
+    }
+
+    fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    fn set_attribute(&mut self, _: KeyValue) {}
+
+    fn set_status(&mut self, _: Status) {}
+
+    fn update_name<T>(&mut self, _: T)
+    where
+        T: Into<Cow<'static, str>>,
+    {
+    }
+
+    fn end_with_timestamp(&mut self, _: SystemTime) {}
+}
+
+fn attrs(attributes: &[KeyValue]) -> Attrs {
+    attributes
+        .iter()
+        .map(|kv| (kv.key.as_str().to_string(), kv.value.as_str().into_owned()))
+        .collect()
+}
+
+fn owned(attrs: &[(&str, &str)]) -> Attrs {
+    attrs
+        .iter()
+        .map(|&(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn fixture() -> Error {
+    Error::builder()
+        .type_name("app::SyncError")
+        .msg("unable to sync")
+        .backtrace("   0: app::sync\n")
+        .source(
+            Error::builder().msg("request failed").source(
+                Error::builder()
+                    .type_name("std::io::Error")
+                    .msg("connection reset")
+                    .backtrace("   0: app::read\n"),
+            ),
+        )
+        .build()
+}
+
+#[test]
+fn exception_attrs() {
+    let err = fixture();
+    assert_eq!(
+        attrs(&otel::exception_attrs(&err)),
+        owned(&[
+            ("exception.type", "app::SyncError"),
+            (
+                "exception.message",
+                "unable to sync: request failed: connection reset"
+            ),
+            ("exception.stacktrace", "   0: app::read\n"),
+        ])
+    );
+
+    let err = Error::builder()
+        .msg("unable to sync")
+        .causes(["request failed"])
+        .build();
+    assert_eq!(
+        attrs(&otel::exception_attrs(&err)),
+        owned(&[("exception.message", "unable to sync: request failed")])
+    );
+}
+
+#[test]
+fn record_exception() {
+    let err = fixture();
+    let mut span = Capture::new();
+    otel::record_exception(&mut span, &err);
+    assert_eq!(
+        span.events(),
+        [("exception".to_string(), attrs(&otel::exception_attrs(&err)))]
+    );
+
+    let mut span = Capture::new();
+    span.recording = false;
+    otel::record_exception(&mut span, &err);
+    assert!(span.events().is_empty());
+}
+
+#[test]
+fn record_exception_on_active_span() {
+    let err = fixture();
+    let span = Capture::new();
+    {
+        let _guard = mark_span_as_active(span.clone());
+        otel::record_exception_on_active_span(&err);
+    }
+    otel::record_exception_on_active_span(&err);
+
+    assert_eq!(
+        span.events(),
+        [("exception".to_string(), attrs(&otel::exception_attrs(&err)))]
+    );
+}
+
+#[test]
+fn record_exception_chain() {
+    let err = fixture();
+    let mut span = Capture::new();
+    otel::record_exception_chain(&mut span, &err);
+
+    let events = span.events();
+    assert!(events.iter().all(|(name, _)| name == "exception"));
+    assert_eq!(
+        events
+            .into_iter()
+            .map(|(_, attrs)| attrs)
+            .collect::<Vec<_>>(),
+        [
+            owned(&[
+                ("exception.type", "std::io::Error"),
+                ("exception.message", "connection reset"),
+                ("exception.stacktrace", "   0: app::read\n"),
+            ]),
+            owned(&[("exception.message", "request failed")]),
+            owned(&[
+                ("exception.type", "app::SyncError"),
+                ("exception.message", "unable to sync"),
+                ("exception.stacktrace", "   0: app::sync\n"),
+            ]),
+        ]
+    );
+}
@@ -0,0 +1,84 @@
+use errtools::{ErrTools, SerializedError};
+use std::error::Error;
+use std::fmt;
+use std::sync::mpsc;
+use std::thread;
+
+mod common;
+
+use common::{RootError, SecondError};
+
+#[derive(Debug)]
+struct Link(usize, Option<Box<Link>>);
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "link {}", self.0)
+    }
+}
+
+impl std::error::Error for Link {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.1.as_deref().map(|link| link as _)
+    }
+}
+
+fn assert_send_sync_static<T: Send + Sync + 'static>() {}
+
+#[test]
+fn owned_is_send_sync_static() {
+    assert_send_sync_static::<SerializedError>();
+}
+
+#[test]
+fn serialize_after_drop_on_other_thread() {
+    let err = Box::new(SecondError(RootError));
+    let expected = serde_json::to_string(&err.serialize()).unwrap();
+    let owned = err.serialize().to_owned();
+    drop(err);
+
+    let (tx, rx) = mpsc::channel();
+    let copy = owned.clone();
+    thread::spawn(move || tx.send(owned).unwrap());
+    let owned = rx.recv().unwrap();
+
+    assert_eq!(serde_json::to_string(&owned).unwrap(), expected);
+    assert_eq!(serde_json::to_string(&copy).unwrap(), expected);
+}
+
+#[test]
+fn owned_matches_dyn_path() {
+    let err: Box<dyn Error + Send + Sync> = Box::new(SecondError(RootError));
+    let expected = serde_json::to_string(&err.serialize()).unwrap();
+    let owned = err.serialize().to_owned();
+    drop(err);
+
+    assert_eq!(serde_json::to_string(&owned).unwrap(), expected);
+}
+
+#[test]
+fn owned_matches_io_error() {
+    let err = std::io::Error::from_raw_os_error(2);
+    let expected = serde_json::to_string(&err.serialize()).unwrap();
+    let owned = err.serialize().to_owned();
+
+    assert_eq!(serde_json::to_string(&owned).unwrap(), expected);
+}
+
+#[test]
+fn owned_matches_deep_chain() {
+    let err = (1..100).fold(Link(0, None), |source, i| Link(i, Some(Box::new(source))));
+    let expected = serde_json::to_string(&err.serialize()).unwrap();
+    let owned = err.serialize().to_owned();
+
+    assert_eq!(serde_json::to_string(&owned).unwrap(), expected);
+}
+
+#[test]
+fn owned_matches_binary() {
+    let err = SecondError(RootError);
+    let expected = bincode::serialize(&err.serialize()).unwrap();
+    let owned = err.serialize().to_owned();
+
+    assert_eq!(bincode::serialize(&owned).unwrap(), expected);
+}
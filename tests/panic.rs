@@ -0,0 +1,72 @@
+use errtools::{ErrTools, SerializedError};
+use std::sync::{Mutex, Once};
+
+static RECEIVED: Mutex<Vec<SerializedError>> = Mutex::new(Vec::new());
+
+fn install() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        errtools::panic::install_hook(|error| RECEIVED.lock().unwrap().push(error));
+    });
+}
+
+fn received(msg: &str) -> serde_json::Value {
+    let received = RECEIVED.lock().unwrap();
+    let json = received
+        .iter()
+        .map(|error| serde_json::to_value(error).unwrap())
+        .find(|json| json["msg"] == msg);
+    json.unwrap()
+}
+
+#[test]
+fn hook_receives_panic() {
+    install();
+    let line = line!() + 1;
+    let error = errtools::panic::catch(|| panic!("out of cheese")).unwrap_err();
+    assert_eq!(error.to_string(), "out of cheese");
+
+    let json = received("out of cheese");
+    assert_eq!(json["type_name"], "errtools::adhoc::AdhocError");
+    assert_eq!(json["location"]["file"], file!());
+    assert_eq!(json["location"]["line"], line);
+    assert_eq!(
+        serde_json::to_value(error.serialize()).unwrap()["location"],
+        json["location"]
+    );
+}
+
+#[test]
+fn hook_receives_formatted_panic() {
+    install();
+    let error =
+        errtools::panic::catch(|| -> u32 { panic!("{} is not a number", "cheese") }).unwrap_err();
+    assert_eq!(error.to_string(), "cheese is not a number");
+    assert!(received("cheese is not a number")["location"].is_object());
+}
+
+#[test]
+fn catch_without_panic() {
+    assert_eq!(errtools::panic::catch(|| 42).unwrap(), 42);
+}
+
+#[test]
+fn catch_non_string_payload() {
+    let error = errtools::panic::catch(|| std::panic::panic_any(42)).unwrap_err();
+    assert_eq!(error.to_string(), "Box<dyn Any>");
+}
+
+#[test]
+fn catch_resumed_panic() {
+    install();
+    let _ = std::panic::catch_unwind(|| panic!("stray panic"));
+
+    let error = errtools::panic::catch(|| {
+        let _ = std::panic::catch_unwind(|| panic!("handled panic"));
+        std::panic::resume_unwind(Box::new("resumed panic"))
+    })
+    .unwrap_err();
+    assert_eq!(error.to_string(), "resumed panic");
+    assert!(serde_json::to_value(error.serialize()).unwrap()["location"].is_null());
+    assert!(received("stray panic")["location"].is_object());
+}
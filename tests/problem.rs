@@ -0,0 +1,223 @@
+use errtools::problem::{Problem, CONTENT_TYPE};
+use serde_json::{json, Value};
+use std::error::Error;
+
+mod common;
+
+use common::{RootError, SecondError};
+
+#[test]
+fn required_members() {
+    let err = SecondError(RootError);
+    let problem = Problem::from_error(&err, 503u16);
+    let value = serde_json::to_value(&problem).unwrap();
+
+    assert_eq!(CONTENT_TYPE, "application/problem+json");
+    assert_eq!(value["type"], "about:blank");
+    assert_eq!(value["title"], "second error");
+    assert_eq!(value["status"], 503);
+    assert_eq!(value["detail"], "second error: root cause");
+    assert!(value.get("instance").is_none());
+}
+
+#[test]
+fn errors_extension_nests_chain() {
+    let err = SecondError(RootError);
+    let problem = Problem::from_error(&err, 500u16);
+    let value = serde_json::to_value(&problem).unwrap();
+
+    let errors = &value["errors"];
+    assert_eq!(errors["msg"], "second error");
+    assert_eq!(errors["source"]["msg"], "root cause");
+    assert_eq!(errors["source"]["source"], Value::Null);
+}
+
+#[test]
+fn builder_and_extensions() {
+    let err = SecondError(RootError);
+    let problem = Problem::from_error(&err, 404u16)
+        .type_uri("https://example.com/probs/missing")
+        .title("Missing")
+        .instance("/users/7")
+        .extension("user_id", 7)
+        .extension("status", 200)
+        .extension("user_id", 8);
+    let mut value = serde_json::to_value(&problem).unwrap();
+    value.as_object_mut().unwrap().remove("errors");
+
+    assert_eq!(
+        value,
+        json!({
+            "type": "https://example.com/probs/missing",
+            "title": "Missing",
+            "status": 404,
+            "detail": "second error: root cause",
+            "instance": "/users/7",
+            "user_id": 7,
+        })
+    );
+}
+
+#[test]
+fn status_from_mapper() {
+    fn status(error: &(dyn Error + 'static)) -> u16 {
+        if error.is::<SecondError>() {
+            409
+        } else {
+            500
+        }
+    }
+
+    let err = SecondError(RootError);
+    assert_eq!(Problem::from_error_with(&err, &status).status(), 409);
+    assert_eq!(Problem::from_error_with(&RootError, &status).status(), 500);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn read_problem_document() {
+    use errtools::deserialize;
+    use errtools::problem::ProblemDetails;
+
+    let json = r#"{
+        "type": "https://example.com/probs/out-of-credit",
+        "title": "You do not have enough credit.",
+        "status": 403,
+        "detail": "Your current balance is 30, but that costs 50.",
+        "instance": "/account/12345/msgs/abc",
+        "balance": 30,
+        "accounts": ["/account/12345", "/account/67890"],
+        "errors": [
+            {"msg": "balance too low", "source": {"msg": "pending charge"}},
+            {"message": "card expired"}
+        ]
+    }"#;
+    let problem: ProblemDetails = serde_json::from_str(json).unwrap();
+
+    assert_eq!(
+        problem.type_uri(),
+        "https://example.com/probs/out-of-credit"
+    );
+    assert_eq!(problem.title(), Some("You do not have enough credit."));
+    assert_eq!(problem.status(), Some(403));
+    assert_eq!(problem.instance(), Some("/account/12345/msgs/abc"));
+    assert_eq!(problem.extension("balance"), Some(&json!(30)));
+    assert_eq!(problem.extensions().len(), 2);
+    assert!(problem.extension("errors").is_none());
+
+    let err = problem.into_error();
+    let messages = err.chain().map(deserialize::Error::msg).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "You do not have enough credit.",
+            "Your current balance is 30, but that costs 50."
+        ]
+    );
+    let errors = err
+        .errors()
+        .iter()
+        .map(|error| {
+            error
+                .chain()
+                .map(deserialize::Error::msg)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            vec!["balance too low", "pending charge"],
+            vec!["card expired"]
+        ]
+    );
+    assert_eq!(deserialize::Error::from_problem_json(json).unwrap(), err);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn read_problem_without_detail() {
+    use errtools::deserialize;
+    use errtools::problem::ProblemDetails;
+
+    let err = deserialize::Error::from_problem_json(r#"{"title": "Not Found", "status": 404}"#);
+    assert_eq!(err.unwrap(), deserialize::Error::new("Not Found"));
+
+    let json = r#"{"title": "Not Found", "detail": "Not Found"}"#;
+    let err = deserialize::Error::from_problem_json(json).unwrap();
+    assert_eq!(err.chain_len(), 1);
+
+    let problem: ProblemDetails = serde_json::from_str(r#"{"status": 500}"#).unwrap();
+    assert_eq!(problem.type_uri(), "about:blank");
+    assert_eq!(problem.into_error().msg(), "about:blank");
+
+    assert!(serde_json::from_str::<ProblemDetails>(r#"{"status": "teapot"}"#).is_err());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn read_own_problem() {
+    use errtools::{deserialize, MetadataValue};
+
+    let err = SecondError(RootError);
+    let json = serde_json::to_string(&Problem::from_error(&err, 500u16)).unwrap();
+    let de = deserialize::Error::from_problem_json(&json).unwrap();
+
+    let messages = de.chain().map(deserialize::Error::msg).collect::<Vec<_>>();
+    assert_eq!(messages, ["second error", "root cause"]);
+    assert_eq!(de.problem_type(), Some("about:blank"));
+    assert_eq!(de.http_status(), Some(500));
+    let detail = de.metadata().find(|(key, _)| *key == "detail");
+    assert_eq!(
+        detail,
+        Some((
+            "detail",
+            &MetadataValue::Str("second error: root cause".into())
+        ))
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn read_foreign_errors() {
+    use errtools::deserialize;
+    use errtools::problem::ProblemDetails;
+
+    let json = r##"{
+        "type": "https://example.net/validation-error",
+        "title": "Your request is not valid.",
+        "status": 422,
+        "errors": [
+            {"detail": "must be a positive integer", "pointer": "#/age"},
+            {"pointer": "#/name"},
+            "not an object"
+        ]
+    }"##;
+    let problem: ProblemDetails = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        problem.errors()[0],
+        json!({"detail": "must be a positive integer", "pointer": "#/age"})
+    );
+    assert_eq!(problem.errors().len(), 3);
+
+    let err = problem.into_error();
+    assert_eq!(err.msg(), "Your request is not valid.");
+    assert_eq!(
+        err.problem_type(),
+        Some("https://example.net/validation-error")
+    );
+    assert_eq!(err.http_status(), Some(422));
+    let errors = err
+        .errors()
+        .iter()
+        .map(deserialize::Error::msg)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            "must be a positive integer",
+            r##"{"pointer":"#/name"}"##,
+            r#""not an object""#
+        ]
+    );
+}
@@ -0,0 +1,31 @@
+#![cfg(feature = "proptest")]
+use errtools::arbitrary::ChainParams;
+use errtools::deserialize;
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn reserialize_json_fixpoint(err in any::<deserialize::Error>()) {
+        let json = serde_json::to_value(&err).unwrap();
+        let de: deserialize::Error = serde_json::from_value(json.clone()).unwrap();
+
+        prop_assert_eq!(&de, &err);
+        prop_assert_eq!(serde_json::to_value(&de).unwrap(), json);
+    }
+
+    #[test]
+    fn reserialize_bincode_fixpoint(err in any::<deserialize::Error>()) {
+        let bytes = bincode::serialize(&err).unwrap();
+        let de: deserialize::Error = bincode::deserialize(&bytes).unwrap();
+
+        prop_assert_eq!(&de, &err);
+        prop_assert_eq!(bincode::serialize(&de).unwrap(), bytes);
+    }
+
+    #[test]
+    fn generate_chain_len(
+        err in any_with::<deserialize::Error>(ChainParams::new().chain_len(3..=5))
+    ) {
+        prop_assert!((3..=5).contains(&err.chain_len()));
+    }
+}
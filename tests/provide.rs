@@ -0,0 +1,67 @@
+#![cfg(feature = "provide")]
+#![feature(backtrace)]
+#![feature(error_generic_member_access, provide_any)]
+
+use errtools::{BacktraceMode, ErrTools, HttpStatus, SerializeConfig, Wrapped};
+use std::any::Demand;
+use std::backtrace::Backtrace;
+use std::error::Error;
+use std::fmt;
+
+/// Only exposes its backtrace through `Error::provide`
+#[derive(Debug)]
+struct Provided(Backtrace);
+
+impl fmt::Display for Provided {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("provided root")
+    }
+}
+
+impl Error for Provided {
+    fn provide<'a>(&'a self, demand: &mut Demand<'a>) {
+        demand.provide_ref::<Backtrace>(&self.0);
+    }
+}
+
+#[test]
+fn serialize_provided_backtrace() {
+    let err = Provided(Backtrace::force_capture());
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+    assert!(json["backtrace"]
+        .as_str()
+        .unwrap()
+        .contains("serialize_provided_backtrace"));
+
+    let config = SerializeConfig::new().backtraces(BacktraceMode::Frames);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    assert!(!json["backtrace"].as_array().unwrap().is_empty());
+}
+
+/// Only exposes its HTTP status through `Error::provide`
+#[derive(Debug)]
+struct Throttled;
+
+impl fmt::Display for Throttled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("rate limit exceeded")
+    }
+}
+
+impl HttpStatus for Throttled {
+    fn http_status(&self) -> Option<u16> {
+        Some(429)
+    }
+}
+
+impl Error for Throttled {
+    fn provide<'a>(&'a self, demand: &mut Demand<'a>) {
+        demand.provide_ref::<dyn HttpStatus>(self);
+    }
+}
+
+#[test]
+fn provided_http_status() {
+    let err = Wrapped::from((Throttled, "unable to fetch feed".to_string()));
+    assert_eq!(err.http_status_in_chain(), Some(429));
+}
@@ -0,0 +1,80 @@
+use errtools::deserialize::{self, ReconstructibleError};
+use errtools::ErrTools;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+struct QuotaExceeded {
+    limit: u32,
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "quota of {} requests exceeded", self.limit)
+    }
+}
+
+impl Error for QuotaExceeded {}
+
+impl ReconstructibleError for QuotaExceeded {
+    fn from_parts(msg: &str) -> Option<Self> {
+        let limit = msg
+            .strip_prefix("quota of ")?
+            .strip_suffix(" requests exceeded")?;
+        Some(QuotaExceeded {
+            limit: limit.parse().ok()?,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Unregistered(QuotaExceeded);
+
+impl fmt::Display for Unregistered {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("request rejected")
+    }
+}
+
+impl Error for Unregistered {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+#[test]
+fn downcast_reconstructed_errors() {
+    errtools::registry::register_reconstructible::<QuotaExceeded>();
+    errtools::registry::register_type::<Unregistered>();
+
+    let err = Unregistered(QuotaExceeded { limit: 100 });
+    let json = serde_json::to_string(&err.serialize()).unwrap();
+    let de: deserialize::Error = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(
+        de.downcast_refchain::<QuotaExceeded>(),
+        Some(&QuotaExceeded { limit: 100 })
+    );
+    assert!(de.downcast_refchain::<Unregistered>().is_none());
+
+    let boxed: Box<dyn Error + Send + Sync> = de.into();
+    assert!(boxed.downcast_refchain::<QuotaExceeded>().is_some());
+
+    let bytes = bincode::serialize(&err.serialize()).unwrap();
+    let de: deserialize::Error = bincode::deserialize(&bytes).unwrap();
+    assert!(de.downcast_refchain::<QuotaExceeded>().is_some());
+}
+
+#[test]
+fn keep_generic_node_when_rejected() {
+    errtools::registry::register_reconstructible::<QuotaExceeded>();
+
+    let json = serde_json::json!({
+        "type_name": std::any::type_name::<QuotaExceeded>(),
+        "msg": "quota exceeded",
+    });
+    let de: deserialize::Error = serde_json::from_value(json).unwrap();
+
+    assert!(de.downcast_refchain::<QuotaExceeded>().is_none());
+    assert_eq!(de.msg(), "quota exceeded");
+}
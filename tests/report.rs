@@ -0,0 +1,402 @@
+use errtools::deserialize::Error;
+use errtools::report::{self, BacktracePreference, OneLine, ReportLayout, ReportOptions};
+use errtools::{ErrTools, MainReport, Report};
+
+fn chain(msgs: &[&str]) -> Error {
+    Error::builder()
+        .msg(msgs[0])
+        .causes(msgs[1..].iter().copied())
+        .build()
+}
+
+#[test]
+fn report_without_sources() {
+    let err = chain(&["unable to load config"]);
+    assert_eq!(format!("{:#}", err.report()), "unable to load config");
+}
+
+#[test]
+fn report_one_source() {
+    let err = chain(&["unable to load config", "permission denied"]);
+    assert_eq!(
+        format!("{:#}", err.report()),
+        "unable to load config\n\nCaused by:\n    0: permission denied"
+    );
+}
+
+#[test]
+fn report_three_sources() {
+    let err = chain(&[
+        "unable to sync account 42",
+        "request to upstream failed",
+        "upstream returned 503:\nservice unavailable\n\nretry after 30s",
+        "connection reset by peer",
+    ]);
+    let expected = "\
+unable to sync account 42
+
+Caused by:
+    0: request to upstream failed
+    1: upstream returned 503:
+       service unavailable
+
+       retry after 30s
+    2: connection reset by peer";
+    assert_eq!(format!("{:#}", err.report()), expected);
+
+    let dyn_err: &(dyn std::error::Error + 'static) = &err;
+    assert_eq!(format!("{:#}", Report::new(dyn_err)), expected);
+    assert_eq!(format!("{:#}", dyn_err.report()), expected);
+}
+
+#[test]
+fn one_line() {
+    let err = chain(&["unable to sync", "request failed: 503", "connection reset"]);
+    assert_eq!(
+        err.to_one_line().to_string(),
+        "unable to sync: request failed: 503: connection reset"
+    );
+
+    let err = chain(&["unable to sync"]);
+    assert_eq!(err.to_one_line().to_string(), "unable to sync");
+}
+
+#[test]
+fn one_line_skips_empty_messages() {
+    let err = chain(&["a", "", "b", ""]);
+    assert_eq!(err.to_one_line().to_string(), "a: b");
+
+    let err = chain(&["", "", "root"]);
+    assert_eq!(err.to_one_line().to_string(), "root");
+    assert_eq!(OneLine::new(&err, " | ").to_string(), "root");
+
+    let err = chain(&["a", "b"]);
+    assert_eq!(OneLine::new(&err, " | ").to_string(), "a | b");
+    assert_eq!(OneLine::new(&err, "").to_string(), "ab");
+}
+
+#[test]
+fn report_short_and_long_forms() {
+    let err = chain(&["unable to sync", "request failed", "connection reset"]);
+    assert_eq!(
+        err.report().to_string(),
+        "unable to sync: request failed: connection reset"
+    );
+    assert_eq!(err.report().to_string(), err.to_one_line().to_string());
+    assert_eq!(
+        format!("{:#}", err.report()),
+        "unable to sync\n\nCaused by:\n    0: request failed\n    1: connection reset"
+    );
+}
+
+#[test]
+fn report_deepest_backtrace() {
+    let err = Error::builder()
+        .msg("unable to sync")
+        .backtrace("   0: app::sync\n")
+        .source(
+            Error::builder().msg("request failed").source(
+                Error::builder()
+                    .msg("connection reset")
+                    .backtrace("   0: app::read\n"),
+            ),
+        )
+        .build();
+
+    assert_eq!(
+        err.report().to_string(),
+        "unable to sync: request failed: connection reset"
+    );
+    let report = err.report().backtrace(BacktracePreference::Always);
+    assert_eq!(
+        format!("{:#}", report),
+        "unable to sync\n\n\
+         Caused by:\n    0: request failed\n    1: connection reset\n\n\
+         Stack backtrace:\n   0: app::read\n"
+    );
+
+    let err = Error::builder()
+        .msg("unable to sync")
+        .backtrace("   0: app::sync\n")
+        .build();
+    let report = err.report().backtrace(BacktracePreference::Always);
+    assert_eq!(
+        format!("{:#}", report),
+        "unable to sync\n\nStack backtrace:\n   0: app::sync\n"
+    );
+}
+
+#[test]
+fn report_backtrace_preference() {
+    let err = Error::builder()
+        .msg("unable to sync")
+        .causes(["request failed"])
+        .backtrace("   0: app::sync\n")
+        .build();
+    let without = "unable to sync\n\nCaused by:\n    0: request failed";
+    let with = format!("{}\n\nStack backtrace:\n   0: app::sync\n", without);
+
+    let report = err.report().backtrace(BacktracePreference::Never);
+    assert_eq!(format!("{:#}", report), without);
+    let report = err.report().backtrace(BacktracePreference::Always);
+    assert_eq!(format!("{:#}", report), with);
+    let report = err.report().backtrace(BacktracePreference::Full);
+    assert_eq!(format!("{:#}", report), with);
+
+    let report = report.backtrace(BacktracePreference::Never);
+    assert_eq!(format!("{:#}", report), without);
+    assert_eq!(report.to_string(), "unable to sync: request failed");
+}
+
+#[test]
+fn numbered_aligns_indices() {
+    let msgs = [
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+        "eleven",
+    ];
+    let err = chain(&msgs);
+    assert_eq!(
+        report::numbered(&err).to_string(),
+        " 0: zero\n 1: one\n 2: two\n 3: three\n 4: four\n 5: five\n 6: six\n 7: seven\n \
+         8: eight\n 9: nine\n10: ten\n11: eleven"
+    );
+
+    let mut out = String::new();
+    report::write_numbered(&err, &mut out).unwrap();
+    assert_eq!(out, report::numbered(&err).to_string());
+}
+
+#[test]
+fn numbered_single_error() {
+    let err = chain(&["unable to load config"]);
+    assert_eq!(
+        report::numbered(&err).to_string(),
+        "0: unable to load config"
+    );
+}
+
+#[cfg(feature = "color")]
+#[test]
+fn report_forced_color() {
+    let err = Error::builder()
+        .msg("unable to sync")
+        .causes(["request failed"])
+        .backtrace("   0: app::sync\n")
+        .build();
+    let report = err
+        .report()
+        .backtrace(BacktracePreference::Always)
+        .color(true);
+
+    assert_eq!(
+        report.to_string(),
+        "\x1b[31munable to sync: request failed\x1b[0m"
+    );
+    assert_eq!(
+        format!("{:#}", report),
+        "\x1b[31munable to sync\x1b[0m\n\n\
+         \x1b[2mCaused by:\x1b[0m\n    0: \x1b[31mrequest failed\x1b[0m\n\n\
+         \x1b[2mStack backtrace:\x1b[0m\n   0: app::sync\n"
+    );
+}
+
+#[cfg(feature = "color")]
+#[test]
+fn report_uncolored_by_default() {
+    let err = chain(&["unable to sync", "request failed"]);
+
+    assert!(!err.report().to_string().contains('\x1b'));
+    assert!(!format!("{:#}", err.report()).contains('\x1b'));
+    assert!(!format!("{:?}", MainReport::from(err)).contains('\x1b'));
+}
+
+#[cfg(feature = "color")]
+#[test]
+fn report_forced_plain() {
+    let err = chain(&["unable to sync", "request failed", "connection reset"]);
+    let report = err.report().color(false);
+
+    assert_eq!(
+        report.to_string(),
+        "unable to sync: request failed: connection reset"
+    );
+    assert_eq!(
+        format!("{:#}", report),
+        "unable to sync\n\nCaused by:\n    0: request failed\n    1: connection reset"
+    );
+}
+
+#[test]
+fn main_report_debug() {
+    let err = chain(&["unable to sync", "request failed", "connection reset"]);
+    let expected = format!("{:#}", err.report());
+
+    let report = MainReport::from(err);
+    assert_eq!(format!("{:?}", report), expected);
+    assert_eq!(format!("{:?}", report), format!("{:#}", report.report()));
+}
+
+#[test]
+fn main_report_from_question_mark() {
+    fn run() -> Result<(), MainReport> {
+        Err("unable to sync")?;
+        Ok(())
+    }
+
+    assert_eq!(format!("{:?}", run().unwrap_err()), "unable to sync");
+}
+
+#[test]
+fn write_report_matches_display() {
+    let err = Error::builder()
+        .msg("unable to sync")
+        .causes([
+            "request failed",
+            "upstream returned 503:\nservice unavailable",
+        ])
+        .backtrace("   0: app::sync\n")
+        .build();
+    let report = err.report().backtrace(BacktracePreference::Always);
+    let options = ReportOptions::new().backtrace(BacktracePreference::Always);
+    #[cfg(feature = "color")]
+    let (report, options) = (report.color(false), options.color(false));
+
+    let cases = [
+        (options, format!("{:#}", report)),
+        (options.layout(ReportLayout::OneLine), report.to_string()),
+        (
+            options.layout(ReportLayout::OneLine).separator(" <- "),
+            OneLine::new(&err, " <- ").to_string(),
+        ),
+        (
+            options.layout(ReportLayout::Numbered),
+            report::numbered(&err).to_string(),
+        ),
+    ];
+
+    let mut out = String::new();
+    for (options, expected) in &cases {
+        out.clear();
+        report::write_report(&err, &mut out, options).unwrap();
+        assert_eq!(&out, expected);
+
+        let mut bytes = Vec::new();
+        report::write_report_io(&err, &mut bytes, options).unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), *expected);
+    }
+}
+
+#[test]
+fn write_report_io_error() {
+    struct Full;
+
+    impl std::io::Write for Full {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::WriteZero.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let err = chain(&["unable to sync", "request failed"]);
+    let error = report::write_report_io(&err, &mut Full, &ReportOptions::new()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::WriteZero);
+}
+
+#[test]
+fn diff_mismatch() {
+    let err = chain(&["unable to sync", "connection reset", "timed out"]);
+    let diff = report::diff(&err, &["unable to sync", "request failed", "timed out"]).unwrap();
+    assert_eq!(
+        diff.to_string(),
+        "  0: \"unable to sync\"\n\
+         ! 1: \"connection reset\", expected \"request failed\"\n  \
+         2: \"timed out\""
+    );
+
+    let diff = report::diff(
+        &err,
+        &["unable to sync", "connection reset", "timed out", "eof"],
+    );
+    assert_eq!(
+        diff.unwrap().to_string(),
+        "  0: \"unable to sync\"\n  \
+         1: \"connection reset\"\n  \
+         2: \"timed out\"\n\
+         - 3: missing, expected \"eof\""
+    );
+}
+
+#[test]
+fn diff_longer_chain() {
+    let msgs = [
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    ];
+    let err = chain(&msgs);
+    let diff = report::diff(&err, &msgs[..9]).unwrap();
+    let rendered = diff.to_string();
+    let lines = rendered.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 11);
+    assert_eq!(lines[0], "   0: \"zero\"");
+    assert_eq!(lines[9], "+  9: \"nine\", not expected");
+    assert_eq!(lines[10], "+ 10: \"ten\", not expected");
+
+    let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        report::assert_chain_eq(&err, &msgs[..9])
+    }))
+    .unwrap_err();
+    assert_eq!(
+        panic.downcast_ref::<String>().unwrap(),
+        &format!(
+            "error chain doesn't match the expected messages:\n{}",
+            rendered
+        )
+    );
+}
+
+#[test]
+fn diff_exact_match() {
+    let msgs = ["unable to sync", "request failed", "connection reset"];
+    let err = chain(&msgs);
+    assert_eq!(report::diff(&err, &msgs), None);
+    report::assert_chain_eq(&err, &msgs);
+}
+
+#[test]
+fn report_type_names() {
+    let err = Error::builder()
+        .type_name("app::SyncError")
+        .msg("unable to sync")
+        .source(
+            Error::builder().msg("request failed").source(
+                Error::builder()
+                    .type_name("std::io::Error")
+                    .msg("connection reset"),
+            ),
+        )
+        .build();
+
+    let report = err.report().type_names(true);
+    assert_eq!(
+        report.to_string(),
+        "app::SyncError: unable to sync: request failed: std::io::Error: connection reset"
+    );
+    assert_eq!(
+        format!("{:#}", report),
+        "app::SyncError: unable to sync\n\n\
+         Caused by:\n    \
+         0: request failed\n    \
+         1: std::io::Error: connection reset"
+    );
+
+    assert_eq!(
+        err.report().to_string(),
+        "unable to sync: request failed: connection reset"
+    );
+    assert_eq!(
+        format!("{:#}", report.type_names(false)),
+        format!("{:#}", err.report())
+    );
+}
@@ -0,0 +1,47 @@
+#![cfg(any(feature = "anyhow", feature = "eyre"))]
+
+use errtools::ReportTools;
+
+mod common;
+
+use common::RootError;
+
+#[cfg(feature = "anyhow")]
+#[test]
+fn anyhow_report_tools() {
+    let err = anyhow::Error::new(RootError)
+        .context("second error")
+        .context("outermost error");
+
+    assert!(err.downcast_refchain::<RootError>().is_some());
+    assert!(err.downcast_refchain::<std::io::Error>().is_none());
+    assert_eq!(err.chain_len(), 3);
+    assert_eq!(ReportTools::root_cause(&err).to_string(), "root cause");
+
+    let json = serde_json::to_value(err.serialize()).unwrap();
+    assert_eq!(json["msg"], "outermost error");
+    assert_eq!(json["source"]["msg"], "second error");
+    assert_eq!(json["source"]["source"]["msg"], "root cause");
+
+    let err = anyhow::anyhow!("root cause");
+    assert_eq!(err.chain_len(), 1);
+    assert!(err.downcast_refchain::<RootError>().is_none());
+}
+
+#[cfg(feature = "eyre")]
+#[test]
+fn eyre_report_tools() {
+    let err = eyre::Report::new(RootError)
+        .wrap_err("second error")
+        .wrap_err("outermost error");
+
+    assert!(err.downcast_refchain::<RootError>().is_some());
+    assert!(err.downcast_refchain::<std::io::Error>().is_none());
+    assert_eq!(err.chain_len(), 3);
+    assert_eq!(ReportTools::root_cause(&err).to_string(), "root cause");
+
+    let json = serde_json::to_value(err.serialize()).unwrap();
+    assert_eq!(json["msg"], "outermost error");
+    assert_eq!(json["source"]["msg"], "second error");
+    assert_eq!(json["source"]["source"]["msg"], "root cause");
+}
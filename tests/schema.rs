@@ -0,0 +1,146 @@
+#![cfg(feature = "schemars")]
+use errtools::schema::ErrorChain;
+use errtools::{
+    deserialize, BacktraceMode, ChainLayout, Coded, ErrTools, ErrorCollector, ErrorMetadata,
+    MetadataValue, SerializeConfig, Wrapped,
+};
+use jsonschema::JSONSchema;
+use schemars::schema_for;
+use serde_json::json;
+
+mod common;
+
+use common::{RootError, SecondError};
+
+#[derive(thiserror::Error, Debug)]
+#[error("unable to open the `{table}` table")]
+struct OpenError {
+    table: &'static str,
+    source: std::io::Error,
+}
+
+impl Coded for OpenError {
+    fn code(&self) -> Option<&str> {
+        Some("E_OPEN")
+    }
+}
+
+impl ErrorMetadata for OpenError {
+    fn metadata(&self) -> Vec<(&'static str, MetadataValue)> {
+        vec![("table", self.table.into()), ("readonly", true.into())]
+    }
+}
+
+fn compile<T: schemars::JsonSchema>() -> JSONSchema {
+    let schema = serde_json::to_value(&schema_for!(T)).unwrap();
+    JSONSchema::compile(&schema).unwrap()
+}
+
+fn assert_valid(schema: &JSONSchema, instance: &serde_json::Value) {
+    if let Err(errors) = schema.validate(instance) {
+        let errors = errors.map(|error| error.to_string()).collect::<Vec<_>>();
+        panic!("{} is invalid: {:?}", instance, errors);
+    }
+}
+
+#[test]
+fn serialized_chain_matches_schema() {
+    let schema = compile::<ErrorChain>();
+    let io_error = std::io::Error::from_raw_os_error(2);
+    let err = Wrapped::from((SecondError(RootError), "outermost error".to_string()));
+
+    assert_valid(&schema, &serde_json::to_value(&err.serialize()).unwrap());
+    assert_valid(
+        &schema,
+        &serde_json::to_value(&io_error.serialize()).unwrap(),
+    );
+
+    let configs = vec![
+        SerializeConfig::new().layout(ChainLayout::Flat),
+        SerializeConfig::new().backtraces(BacktraceMode::Frames),
+        SerializeConfig::new().max_depth(2).include_debug(true),
+    ];
+    for config in &configs {
+        let json = serde_json::to_value(&err.serialize_with(config)).unwrap();
+        assert_valid(&schema, &json);
+    }
+
+    assert!(!schema.is_valid(&json!({"msg": 7})));
+    assert!(!schema.is_valid(&json!({"msg": "bad source", "source": "nope"})));
+}
+
+#[test]
+fn every_written_field_matches_schema() {
+    errtools::registry::register_coded::<OpenError>();
+    errtools::registry::register_metadata::<OpenError>();
+
+    let schema = compile::<ErrorChain>();
+    let mut errors = ErrorCollector::new();
+    errors.push(OpenError {
+        table: "orders",
+        source: std::io::Error::from_raw_os_error(2),
+    });
+    errors.push(errtools::fs::read_to_string("/this/path/does/not/exist.toml").unwrap_err());
+    let mut err = Wrapped::from((
+        errors.finish().unwrap_err(),
+        "nightly export failed".to_string(),
+    ));
+    err.push_related(errtools::adhoc!("lock not released"));
+
+    let configs = vec![
+        SerializeConfig::new()
+            .include_debug(true)
+            .include_alternate(true),
+        SerializeConfig::new().backtraces(BacktraceMode::Frames),
+        SerializeConfig::new().layout(ChainLayout::Flat),
+        SerializeConfig::new().max_depth(1),
+    ];
+    let mut written = String::new();
+    for config in &configs {
+        let json = serde_json::to_string(&err.serialize_with(config)).unwrap();
+        assert_valid(&schema, &serde_json::from_str(&json).unwrap());
+
+        let mut parsed = deserialize::DeserializeConfig::new()
+            .deserialize_error(&mut serde_json::Deserializer::from_str(&json))
+            .unwrap();
+        parsed.mark_received("export-svc");
+        let reserialized = serde_json::to_string(&parsed).unwrap();
+        assert_valid(&schema, &serde_json::from_str(&reserialized).unwrap());
+
+        written.push_str(&json);
+        written.push_str(&reserialized);
+    }
+
+    for field in [
+        "code",
+        "meta",
+        "debug",
+        "display_alt",
+        "path",
+        "io_kind",
+        "os_code",
+        "errors",
+        "related",
+        "received_from",
+        "received_at",
+        "truncated",
+        "chain",
+    ] {
+        let key = format!("\"{}\":", field);
+        assert!(written.contains(&key), "{} was never written", field);
+    }
+}
+
+#[test]
+fn deserializable_chain_matches_schema() {
+    let schema = compile::<deserialize::Error>();
+    let err = SecondError(RootError);
+
+    assert_valid(&schema, &serde_json::to_value(&err.serialize()).unwrap());
+    assert_valid(&schema, &json!({"msg": "no source"}));
+    assert_valid(
+        &schema,
+        &json!({"chain": [{"msg": "outer"}, {"msg": "inner"}]}),
+    );
+    assert!(!schema.is_valid(&json!({"msg": 7})));
+}
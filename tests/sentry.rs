@@ -0,0 +1,80 @@
+#![cfg(feature = "sentry")]
+#![feature(backtrace)]
+
+use errtools::registry;
+use errtools::sentry::to_sentry_exception;
+use serde_json::json;
+use std::backtrace::Backtrace;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+struct Traced(Backtrace);
+
+impl fmt::Display for Traced {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("traced root")
+    }
+}
+
+impl Error for Traced {
+    fn backtrace(&self) -> Option<&Backtrace> {
+        Some(&self.0)
+    }
+}
+
+#[derive(Debug)]
+struct Outer(Traced);
+
+impl fmt::Display for Outer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("outer")
+    }
+}
+
+impl Error for Outer {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+#[test]
+fn two_level_chain() {
+    registry::register_type::<Outer>();
+    let err = Outer(Traced(Backtrace::force_capture()));
+    let mut value = serde_json::to_value(&to_sentry_exception(&err)).unwrap();
+
+    let frames = value["values"][0]["stacktrace"]["frames"]
+        .as_array()
+        .unwrap()
+        .clone();
+    assert!(!frames.is_empty());
+    assert!(frames.iter().all(|frame| frame["function"].is_string()));
+    // Sentry wants the innermost frame, frame 0 of the backtrace, last
+    let captured = (err.0).0.to_string();
+    let innermost = captured.lines().next().unwrap().trim_start();
+    assert_eq!(
+        frames.last().unwrap()["function"]
+            .as_str()
+            .map(|f| format!("0: {}", f)),
+        Some(innermost.to_string())
+    );
+
+    value["values"][0]["stacktrace"]["frames"] = json!([]);
+    assert_eq!(
+        value,
+        json!({
+            "values": [
+                {
+                    "type": "Error",
+                    "value": "traced root",
+                    "stacktrace": { "frames": [] },
+                },
+                {
+                    "type": "sentry::Outer",
+                    "value": "outer",
+                },
+            ]
+        })
+    );
+}
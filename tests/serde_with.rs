@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+mod common;
+
+use common::{RootError, SecondError};
+
+#[derive(Serialize, Deserialize)]
+struct JobResult {
+    ok: bool,
+    #[serde(with = "errtools::serde_box_error")]
+    error: Box<dyn Error + Send + Sync>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LocalJobResult {
+    ok: bool,
+    #[serde(with = "errtools::serde_box_error::local")]
+    error: Box<dyn Error>,
+}
+
+fn messages(error: &(dyn Error + 'static)) -> Vec<String> {
+    let mut messages = vec![error.to_string()];
+    let mut cur_error = error.source();
+    while let Some(error) = cur_error {
+        messages.push(error.to_string());
+        cur_error = error.source();
+    }
+    messages
+}
+
+#[test]
+fn round_trip_boxed_error_field() {
+    let result = JobResult {
+        ok: false,
+        error: Box::new(SecondError(RootError)),
+    };
+
+    let json = serde_json::to_string(&result).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["ok"], false);
+    assert_eq!(value["error"]["msg"], "second error");
+    assert_eq!(value["error"]["source"]["msg"], "root cause");
+
+    let result: JobResult = serde_json::from_str(&json).unwrap();
+    assert!(!result.ok);
+    assert_eq!(messages(&*result.error), ["second error", "root cause"]);
+}
+
+#[test]
+fn round_trip_local_boxed_error_field() {
+    let result = LocalJobResult {
+        ok: false,
+        error: Box::new(SecondError(RootError)),
+    };
+
+    let json = serde_json::to_string(&result).unwrap();
+    let result: LocalJobResult = serde_json::from_str(&json).unwrap();
+    assert_eq!(messages(&*result.error), ["second error", "root cause"]);
+}
+
+#[derive(Serialize, Deserialize)]
+struct Worker {
+    name: String,
+    #[serde(
+        default,
+        with = "errtools::serde_opt_box_error",
+        skip_serializing_if = "Option::is_none"
+    )]
+    last_error: Option<Box<dyn Error + Send + Sync>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BinaryWorker {
+    name: String,
+    #[serde(with = "errtools::serde_opt_box_error")]
+    last_error: Option<Box<dyn Error + Send + Sync>>,
+}
+
+fn opt_messages(error: &Option<Box<dyn Error + Send + Sync>>) -> Option<Vec<String>> {
+    error.as_deref().map(|error| messages(error))
+}
+
+fn fixtures() -> Vec<Option<Box<dyn Error + Send + Sync>>> {
+    vec![
+        None,
+        Some(Box::new(RootError)),
+        Some(Box::new(SecondError(RootError))),
+    ]
+}
+
+#[test]
+fn round_trip_optional_error_json() {
+    for last_error in fixtures() {
+        let expected = opt_messages(&last_error);
+        let worker = Worker {
+            name: "indexer".to_string(),
+            last_error,
+        };
+
+        let json = serde_json::to_string(&worker).unwrap();
+        assert_eq!(json.contains("last_error"), expected.is_some());
+
+        let worker: Worker = serde_json::from_str(&json).unwrap();
+        assert_eq!(worker.name, "indexer");
+        assert_eq!(opt_messages(&worker.last_error), expected);
+    }
+
+    let json = r#"{"name": "indexer", "last_error": null}"#;
+    let worker: Worker = serde_json::from_str(json).unwrap();
+    assert!(worker.last_error.is_none());
+}
+
+#[test]
+fn round_trip_optional_error_bincode() {
+    for last_error in fixtures() {
+        let expected = opt_messages(&last_error);
+        let worker = BinaryWorker {
+            name: "indexer".to_string(),
+            last_error,
+        };
+
+        let bytes = bincode::serialize(&worker).unwrap();
+        let worker: BinaryWorker = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(worker.name, "indexer");
+        assert_eq!(opt_messages(&worker.last_error), expected);
+    }
+}
+
+#[derive(Deserialize)]
+struct ReceivedJobResult {
+    ok: bool,
+    #[serde(deserialize_with = "errtools::deserialize::boxed")]
+    error: Box<dyn Error + Send + Sync>,
+}
+
+#[test]
+fn deserialize_with_boxed() {
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+    assert_send_sync::<errtools::deserialize::Error>();
+
+    let result = JobResult {
+        ok: false,
+        error: Box::new(SecondError(RootError)),
+    };
+    let json = serde_json::to_string(&result).unwrap();
+    let received: ReceivedJobResult = serde_json::from_str(&json).unwrap();
+
+    assert!(!received.ok);
+    assert_eq!(messages(&*received.error), ["second error", "root cause"]);
+    assert!(received.error.is::<errtools::deserialize::Error>());
+
+    let error = errtools::deserialize::Error::new("boom");
+    let local: Box<dyn Error> = error.clone().into();
+    let shared: Box<dyn Error + Send + Sync> = error.into();
+    assert_eq!(local.to_string(), shared.to_string());
+}
@@ -0,0 +1,39 @@
+use errtools::{ErrTools, Serializable};
+use serde::Serialize;
+
+mod common;
+
+use common::{RootError, SecondError};
+
+#[derive(Serialize)]
+struct Payload {
+    id: u32,
+}
+
+#[derive(Serialize)]
+struct Event {
+    payload: Payload,
+    error: Serializable<SecondError>,
+}
+
+#[test]
+fn embedded_in_derived_struct() {
+    let event = Event {
+        payload: Payload { id: 7 },
+        error: SecondError(RootError).into(),
+    };
+    let json = serde_json::to_value(&event).unwrap();
+
+    let expected = serde_json::to_value(&SecondError(RootError).serialize()).unwrap();
+    assert_eq!(json["payload"]["id"], 7);
+    assert_eq!(json["error"], expected);
+    assert_eq!(json["error"]["source"]["msg"], "root cause");
+}
+
+#[test]
+fn display_and_debug_forward() {
+    let err = Serializable::from(SecondError(RootError));
+
+    assert_eq!(err.to_string(), "second error");
+    assert_eq!(format!("{:?}", err), "SecondError(RootError)");
+}
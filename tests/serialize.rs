@@ -2,6 +2,10 @@ use errtools::ErrTools;
 use std::error::Error;
 use std::fmt;
 
+mod common;
+
+use common::{RootError, SecondError};
+
 #[test]
 fn serialize_eyre() {
     use eyre::{eyre, ErrReport};
@@ -21,38 +25,465 @@ fn serialize_anyhow() {
     println!("{}", json);
 }
 
+#[test]
+fn serialize_concrete() {
+    let err = SecondError(RootError);
+    let json = serde_json::to_string_pretty(&err.serialize()).unwrap();
+    println!("concrete serialization:\n{}\n", json);
+    let err: &dyn Error = &err;
+    let json = serde_json::to_string_pretty(&err.serialize()).unwrap();
+    println!("dyn serialization:\n{}", json);
+}
+
 #[derive(Debug)]
-struct RootError;
+struct RegisteredRoot;
 
-impl fmt::Display for RootError {
+impl fmt::Display for RegisteredRoot {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "root cause")
+        write!(f, "registered root cause")
     }
 }
 
-impl std::error::Error for RootError {}
+impl Error for RegisteredRoot {}
 
 #[derive(Debug)]
-struct SecondError(RootError);
+struct RegisteredSecond(RegisteredRoot);
 
-impl fmt::Display for SecondError {
+impl fmt::Display for RegisteredSecond {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "second error")
+        write!(f, "registered second error")
     }
 }
 
-impl std::error::Error for SecondError {
+impl Error for RegisteredSecond {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         Some(&self.0)
     }
 }
 
 #[test]
-fn serialize_concrete() {
+fn serialize_nested_type_names() {
+    // Registered fixtures of its own, so that the shared ones stay
+    // unregistered for the other tests
+    errtools::registry::register_type::<RegisteredRoot>();
+
+    let err = RegisteredSecond(RegisteredRoot);
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+    assert_eq!(json["type_name"], std::any::type_name::<RegisteredSecond>());
+    assert_eq!(
+        json["source"]["type_name"],
+        std::any::type_name::<RegisteredRoot>()
+    );
+
+    let err = std::io::Error::new(std::io::ErrorKind::Other, "disk full");
+    let err: &dyn Error = &err;
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+    assert_eq!(json["type_name"], std::any::type_name::<std::io::Error>());
+}
+
+#[test]
+fn serialize_with_config() {
+    use errtools::{BacktraceMode, SerializeConfig, Wrapped};
+
+    let line = line!() + 1;
+    let err = Wrapped::from((SecondError(RootError), "outermost error".to_string()));
+    let config = SerializeConfig::new()
+        .backtraces(BacktraceMode::Omit)
+        .max_depth(2)
+        .include_type_names(false);
+    let mut json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+
+    let location = json.as_object_mut().unwrap().remove("location").unwrap();
+    assert_eq!(location["file"], file!());
+    assert_eq!(location["line"], line);
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "v": 1,
+            "msg": "outermost error",
+            "source": {
+                "msg": "second error",
+                "source": {
+                    "msg": "… 1 more error truncated",
+                    "truncated": 1,
+                    "source": null,
+                },
+            },
+        })
+    );
+}
+
+#[test]
+fn serialize_flat() {
+    use errtools::{ChainLayout, SerializeConfig, Wrapped};
+
+    let err = Wrapped::from((SecondError(RootError), "outermost error".to_string()));
+    let nested = serde_json::to_value(&err.serialize()).unwrap();
+    let config = SerializeConfig::new().layout(ChainLayout::Flat);
+    let flat = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    println!("{}", serde_json::to_string_pretty(&flat).unwrap());
+
+    let chain = flat["chain"].as_array().unwrap();
+    assert_eq!(chain.len(), 3);
+
+    let mut node = &nested;
+    for entry in chain {
+        assert!(entry.get("source").is_none());
+        for field in &["type_name", "msg", "backtrace"] {
+            assert_eq!(entry.get(field), node.get(field));
+        }
+        node = &node["source"];
+    }
+    assert!(node.is_null());
+
+    assert_eq!(chain[0]["msg"], "outermost error");
+    assert_eq!(chain[2]["msg"], "root cause");
+}
+
+/// A linked list of errors, used to build arbitrarily deep chains
+#[derive(Debug)]
+struct Link(usize, Option<Box<Link>>);
+
+impl Link {
+    fn chain(len: usize) -> Link {
+        (1..len).fold(Link(0, None), |source, n| Link(n, Some(Box::new(source))))
+    }
+}
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "link {}", self.0)
+    }
+}
+
+impl std::error::Error for Link {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.1.as_deref().map(|link| link as &(dyn Error + 'static))
+    }
+}
+
+impl Drop for Link {
+    fn drop(&mut self) {
+        let mut next = self.1.take();
+        while let Some(mut link) = next {
+            next = link.1.take();
+        }
+    }
+}
+
+#[test]
+fn serialize_deep_chain() {
+    let err = Link::chain(50_000);
+    let json = serde_json::to_string(&err.serialize()).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    let mut node = &json;
+    let mut nested = 0;
+    while node.get("chain").is_none() {
+        assert_eq!(node["msg"], format!("link {}", 49_999 - nested));
+        nested += 1;
+        node = &node["source"];
+    }
+
+    let chain = node["chain"].as_array().unwrap();
+    assert_eq!(nested + chain.len(), 50_000);
+    assert_eq!(chain[0]["msg"], format!("link {}", 49_999 - nested));
+    assert_eq!(chain.last().unwrap()["msg"], "link 0");
+
+    // Formats that aren't human readable nest every error, so the chain is
+    // truncated where the fixed layout stops nesting
+    let bytes = bincode::serialize(&err.serialize()).unwrap();
+    let de: errtools::deserialize::Error = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(de.chain_len(), 257);
+    assert_eq!(de.get(255).unwrap().msg(), "link 49744");
+    assert_eq!(de.root().msg(), "… 49744 more errors truncated");
+}
+
+#[test]
+fn serialize_truncated() {
+    use errtools::{ChainLayout, SerializeConfig};
+
+    let err = Link::chain(20);
+    let config = SerializeConfig::new().max_depth(5);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+
+    let mut node = &json;
+    for n in 0..5 {
+        assert_eq!(node["msg"], format!("link {}", 19 - n));
+        assert!(node.get("truncated").is_none());
+        node = &node["source"];
+    }
+    assert_eq!(node["msg"], "… 15 more errors truncated");
+    assert_eq!(node["truncated"], 15);
+    assert!(node["source"].is_null());
+
+    let config = config.layout(ChainLayout::Flat);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    let chain = json["chain"].as_array().unwrap();
+    assert_eq!(chain.len(), 6);
+    assert_eq!(chain[4]["msg"], "link 15");
+    assert_eq!(chain[5]["truncated"], 15);
+
+    let de: errtools::deserialize::Error = serde_json::from_value(json).unwrap();
+    assert_eq!(de.root().truncated(), Some(15));
+}
+
+#[test]
+fn serialize_truncated_fixed_layout() {
+    use errtools::deserialize;
+    use errtools::SerializeConfig;
+
+    let err = Link::chain(20);
+    let config = SerializeConfig::new().max_depth(5);
+    let bytes = bincode::serialize(&err.serialize_with(&config)).unwrap();
+    let de: deserialize::Error = bincode::deserialize(&bytes).unwrap();
+
+    assert_eq!(de.chain_len(), 6);
+    for (n, error) in de.chain().take(5).enumerate() {
+        assert_eq!(error.msg(), format!("link {}", 19 - n));
+        assert_eq!(error.truncated(), None);
+    }
+    let marker = de.root();
+    assert_eq!(marker.msg(), "… 15 more errors truncated");
+    assert_eq!(marker.truncated(), Some(15));
+    assert_eq!(marker.type_name(), None);
+
+    let bytes = bincode::serialize(&de).unwrap();
+    let again: deserialize::Error = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(again.root().truncated(), Some(15));
+    let json = serde_json::to_value(&again).unwrap();
+    assert_eq!(
+        json["source"]["source"]["source"]["source"]["source"]["truncated"],
+        15
+    );
+}
+
+#[test]
+fn serialize_redacted() {
+    use errtools::{fs, SerializeConfig, Wrapped};
+    use std::borrow::Cow;
+
+    fn redact_digits(text: &str) -> Cow<'_, str> {
+        if text.bytes().any(|b| b.is_ascii_digit()) {
+            text.chars()
+                .map(|c| if c.is_ascii_digit() { '#' } else { c })
+                .collect::<String>()
+                .into()
+        } else {
+            text.into()
+        }
+    }
+
+    let config = SerializeConfig::new().redactor(redact_digits);
+
+    let source = fs::read_to_string("/home/user42/secrets.toml").unwrap_err();
+    let err = Wrapped::from((source, "unable to load user 42".to_string()));
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+
+    assert_eq!(json["msg"], "unable to load user ##");
+    assert_eq!(
+        json["source"]["msg"],
+        "failed to read file `/home/user##/secrets.toml`"
+    );
+    assert_eq!(json["source"]["path"], "/home/user##/secrets.toml");
+    assert!(!json.to_string().contains("42"));
+
+    assert_eq!(err.to_string(), "unable to load user 42");
+    assert_eq!(
+        err.source.path(),
+        std::path::Path::new("/home/user42/secrets.toml")
+    );
+}
+
+#[derive(Debug)]
+struct QuotaError;
+
+impl fmt::Display for QuotaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "quota exceeded")
+    }
+}
+
+impl Error for QuotaError {}
+
+impl errtools::Coded for QuotaError {
+    fn code(&self) -> Option<&str> {
+        Some("E_QUOTA")
+    }
+}
+
+#[test]
+fn serialize_redacted_fields() {
+    use errtools::{registry, SerializeConfig, Wrapped};
+    use std::borrow::Cow;
+
+    fn redact(text: &str) -> Cow<'_, str> {
+        match text {
+            file if file == file!() => "<file>".into(),
+            "E_QUOTA" => "<code>".into(),
+            "NotFound" => "<kind>".into(),
+            text => text.into(),
+        }
+    }
+
+    registry::register_coded::<QuotaError>();
+    let config = SerializeConfig::new().redactor(redact);
+
+    let err = Wrapped::from((QuotaError, "upload rejected".to_string()));
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    assert_eq!(json["location"]["file"], "<file>");
+    assert!(json["location"]["line"].is_u64());
+    assert_eq!(json["source"]["code"], "<code>");
+
+    let err = std::io::Error::from(std::io::ErrorKind::NotFound);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    assert_eq!(json["io_kind"], "<kind>");
+}
+
+#[test]
+fn serialize_capped_messages() {
+    use errtools::{SerializeConfig, Wrapped};
+
+    let body = format!("upstream responded: {}", "€".repeat(100));
+    let err = Wrapped::from((SecondError(RootError), body));
+    let config = SerializeConfig::new().max_msg_len(21);
+    let json = serde_json::to_string(&err.serialize_with(&config)).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    // "upstream responded: " is 20 bytes and each '€' is 3 bytes
+    assert_eq!(json["msg"], "upstream responded: …[+300 bytes]");
+    assert_eq!(json["source"]["msg"], "second error");
+}
+
+#[test]
+fn serialize_debug_field() {
+    use errtools::SerializeConfig;
+
     let err = SecondError(RootError);
-    let json = serde_json::to_string_pretty(&err.serialize()).unwrap();
-    println!("concrete serialization:\n{}\n", json);
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+    assert!(json.get("debug").is_none());
+
+    let config = SerializeConfig::new().include_debug(true);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    assert_eq!(json["debug"], "SecondError(RootError)");
+    assert_eq!(json["source"]["debug"], "RootError");
+}
+
+#[test]
+fn serialize_alternate_display() {
+    use errtools::{SerializeConfig, Wrapped};
+
+    let err = Wrapped::from((SecondError(RootError), "outermost error".to_string()));
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+    assert!(json.get("display_alt").is_none());
+
+    let config = SerializeConfig::new().include_alternate(true);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    assert_eq!(json["display_alt"], format!("{:#}", err));
+    assert!(json["display_alt"].as_str().unwrap().contains("Caused by:"));
+    assert!(json["source"].get("display_alt").is_none());
+    assert!(json["source"]["source"].get("display_alt").is_none());
+}
+
+/// Counts how often it is formatted with `{:#}`
+#[derive(Debug, Default)]
+struct CountingAlternate(std::sync::atomic::AtomicUsize);
+
+impl fmt::Display for CountingAlternate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            return write!(f, "counting, in detail");
+        }
+        write!(f, "counting")
+    }
+}
+
+impl std::error::Error for CountingAlternate {}
+
+#[test]
+fn serialize_alternate_display_once() {
+    use errtools::{ChainLayout, SerializeConfig};
+    use std::sync::atomic::Ordering;
+
+    let err = CountingAlternate::default();
+    let config = SerializeConfig::new().include_alternate(true);
+    let json = serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    assert_eq!(json["display_alt"], "counting, in detail");
+    assert_eq!(err.0.load(Ordering::Relaxed), 1);
+
+    let config = config.layout(ChainLayout::Flat);
+    serde_json::to_value(&err.serialize_with(&config)).unwrap();
+    assert_eq!(err.0.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn serialize_io_error_details() {
+    use errtools::Wrapped;
+    use std::io;
+
+    let err = Wrapped::from((
+        io::Error::from_raw_os_error(2),
+        "unable to open config".to_string(),
+    ));
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+    assert!(json.get("io_kind").is_none());
+    assert_eq!(json["source"]["io_kind"], "NotFound");
+    assert_eq!(json["source"]["os_code"], 2);
+
+    let err: &dyn Error = &io::Error::new(io::ErrorKind::InvalidData, "bad header");
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+    assert_eq!(json["io_kind"], "InvalidData");
+    assert!(json.get("os_code").is_none());
+}
+
+#[test]
+fn display_serializeable() {
+    let err = SecondError(RootError);
+    assert_eq!(err.serialize().to_string(), "second error: root cause");
+
     let err: &dyn Error = &err;
-    let json = serde_json::to_string_pretty(&err.serialize()).unwrap();
-    println!("dyn serialization:\n{}", json);
+    assert_eq!(err.serialize().to_string(), "second error: root cause");
+}
+
+#[test]
+fn debug_serializeable() {
+    let err = SecondError(RootError);
+    assert_eq!(
+        format!("{:?}", err.serialize()),
+        "0: second error\n    \
+         type: serialize::common::SecondError\n    \
+         backtrace: none\n\
+         1: root cause\n    \
+         type: unknown\n    \
+         backtrace: none"
+    );
+
+    let err = std::io::Error::new(std::io::ErrorKind::Other, "disk full");
+    let err: &dyn Error = &err;
+    assert!(format!("{:?}", err.serialize())
+        .starts_with("0: disk full\n    type: std::io::error::Error\n"));
+}
+
+#[test]
+fn construct_wrappers_directly() {
+    use errtools::{serializable, SerializeableConcreteError, SerializeableError};
+
+    let err = SecondError(RootError);
+    let expected = serde_json::to_string(&err.serialize()).unwrap();
+    let concrete = SerializeableConcreteError::new(&err);
+    let copy = concrete;
+    assert_eq!(serde_json::to_string(&concrete).unwrap(), expected);
+    assert_eq!(serde_json::to_string(&copy).unwrap(), expected);
+    assert!(std::ptr::eq(concrete.inner(), &err));
+
+    let dyn_err: &dyn Error = &err;
+    let expected = serde_json::to_string(&dyn_err.serialize()).unwrap();
+    let wrapper = SerializeableError::new(dyn_err);
+    assert_eq!(serde_json::to_string(&wrapper).unwrap(), expected);
+    assert_eq!(
+        serde_json::to_string(&serializable(dyn_err)).unwrap(),
+        expected
+    );
+    assert_eq!(wrapper.inner().to_string(), "second error");
 }
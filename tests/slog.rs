@@ -0,0 +1,109 @@
+#![cfg(feature = "slog")]
+
+use errtools::deserialize::Error;
+use errtools::ErrTools;
+use slog::{error, o, Drain, Key, Logger, OwnedKVList, Record, SerdeValue, KV};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Keeps every key of every record, with `serde` values as JSON
+#[derive(Clone, Default)]
+struct Capture {
+    serde: bool,
+    values: Arc<Mutex<Vec<(String, serde_json::Value)>>>,
+}
+
+impl Drain for Capture {
+    type Ok = ();
+    type Err = slog::Never;
+
+    fn log(&self, record: &Record<'_>, _: &OwnedKVList) -> Result<(), slog::Never> {
+        let mut serializer = self.clone();
+        record.kv().serialize(record, &mut serializer).unwrap();
+        Ok(())
+    }
+}
+
+impl slog::Serializer for Capture {
+    fn emit_arguments(&mut self, key: Key, value: &fmt::Arguments<'_>) -> slog::Result {
+        let value = serde_json::Value::String(value.to_string());
+        self.values.lock().unwrap().push((key.to_string(), value));
+        Ok(())
+    }
+
+    fn emit_serde(&mut self, key: Key, value: &dyn SerdeValue) -> slog::Result {
+        if !self.serde {
+            return value.serialize_fallback(key, self);
+        }
+
+        let value = serde_json::to_value(value.as_serde()).unwrap();
+        self.values.lock().unwrap().push((key.to_string(), value));
+        Ok(())
+    }
+}
+
+fn capture(serde: bool, f: impl FnOnce(&Logger)) -> Vec<(String, serde_json::Value)> {
+    let drain = Capture {
+        serde,
+        ..Capture::default()
+    };
+    f(&Logger::root(drain.clone().fuse(), o!()));
+    let values = drain.values.lock().unwrap();
+    values.clone()
+}
+
+fn err() -> Error {
+    Error::builder()
+        .msg("unable to sync")
+        .causes(["request failed", "connection reset"])
+        .build()
+}
+
+#[test]
+fn slog_value_serde() {
+    let err = err();
+    let values = capture(true, |log| {
+        error!(log, "sync failed"; "err" => err.slog_value(), "attempt" => 3);
+    });
+
+    let expected = serde_json::to_value(err.to_field_map()).unwrap();
+    assert_eq!(values[0].0, "attempt");
+    assert_eq!(values[1], ("err".to_string(), expected.clone()));
+    assert_eq!(expected["error.msg"], "unable to sync");
+    assert_eq!(expected["error.type"], "errtools::deserialize::Error");
+    assert_eq!(expected["error.source.msg"], "request failed");
+    assert_eq!(expected["error.source.source.msg"], "connection reset");
+    assert_eq!(expected["error.root.msg"], "connection reset");
+    assert_eq!(expected["error.chain_len"], "3");
+
+    let dyn_err: &(dyn std::error::Error + 'static) = &err;
+    let values = capture(true, |log| {
+        error!(log, "sync failed"; "err" => errtools::slog::Chain::new(dyn_err));
+    });
+    let expected = serde_json::to_value(dyn_err.to_field_map()).unwrap();
+    assert_eq!(values, [("err".to_string(), expected)]);
+}
+
+#[test]
+fn slog_value_fallback() {
+    let err = err();
+    let values = capture(false, |log| {
+        error!(log, "sync failed"; "err" => err.slog_value());
+    });
+
+    assert_eq!(
+        values,
+        [(
+            "err".to_string(),
+            serde_json::Value::from("unable to sync: request failed: connection reset")
+        )]
+    );
+}
+
+#[test]
+fn slog_value_sendable() {
+    let err = err();
+    let sendable = err.slog_value().to_sendable();
+    let json = serde_json::to_value(sendable.as_serde()).unwrap();
+    assert_eq!(json, serde_json::to_value(err.to_field_map()).unwrap());
+}
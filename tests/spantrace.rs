@@ -0,0 +1,42 @@
+#![cfg(feature = "spantrace")]
+
+use errtools::ErrTools;
+use tracing_error::{ErrorLayer, InstrumentError, TracedError};
+use tracing_subscriber::prelude::*;
+
+mod common;
+
+use common::RootError;
+
+#[tracing::instrument]
+fn load_config(path: &str) -> TracedError<RootError> {
+    RootError.in_current_span()
+}
+
+fn traced_error() -> TracedError<RootError> {
+    let subscriber = tracing_subscriber::registry().with(ErrorLayer::default());
+    tracing::subscriber::with_default(subscriber, || load_config("app.toml"))
+}
+
+#[test]
+fn serialize_span_trace() {
+    let err = traced_error();
+    let json = serde_json::to_value(&err.serialize()).unwrap();
+
+    assert!(json.get("span_trace").is_none());
+    let span_trace = json["source"]["span_trace"].as_str().unwrap();
+    assert!(span_trace.contains("load_config"));
+    assert!(span_trace.contains("app.toml"));
+
+    let owned = serde_json::to_value(&err.serialize().to_owned()).unwrap();
+    assert_eq!(json, owned);
+}
+
+#[test]
+fn debug_span_trace() {
+    let err = traced_error();
+    let debug = format!("{:?}", err.serialize());
+
+    assert!(debug.contains("\n    span trace:\n"));
+    assert!(debug.contains("load_config"));
+}
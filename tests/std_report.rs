@@ -0,0 +1,136 @@
+#![cfg(feature = "std-report")]
+#![feature(backtrace, error_reporter)]
+
+use errtools::deserialize::Error;
+use errtools::report::BacktracePreference;
+use errtools::ErrTools;
+use std::backtrace::Backtrace;
+use std::fmt;
+
+/// The message, sources, and backtrace of a multi-line report, either the
+/// crate's or the standard library's
+type Fields = (String, Vec<String>, Option<String>);
+
+fn fields(report: &str) -> Fields {
+    let (chain, backtrace) = match report.split_once("\n\nStack backtrace:\n") {
+        Some((chain, backtrace)) => (chain, Some(backtrace.trim_end().to_string())),
+        None => (report, None),
+    };
+    let (msg, causes) = chain.split_once("\n\nCaused by:\n").unwrap_or((chain, ""));
+
+    let mut sources: Vec<String> = vec![];
+    for line in causes.lines().map(str::trim_start) {
+        match line.split_once(": ") {
+            Some((index, msg)) if index.parse::<usize>().is_ok() => sources.push(msg.to_string()),
+            _ if sources.is_empty() => sources.push(line.to_string()),
+            _ => {
+                let source = sources.last_mut().unwrap();
+                source.push('\n');
+                source.push_str(line);
+            }
+        }
+    }
+
+    (msg.to_string(), sources, backtrace)
+}
+
+#[test]
+fn std_report_fields_match() {
+    let chains: &[&[&str]] = &[
+        &["unable to load config"],
+        &["unable to load config", "permission denied"],
+        &[
+            "unable to sync account 42",
+            "upstream returned 503:\nservice unavailable\n\nretry after 30s",
+            "connection reset by peer",
+        ],
+    ];
+
+    for msgs in chains {
+        let err = Error::builder()
+            .msg(msgs[0])
+            .causes(msgs[1..].iter().copied())
+            .build();
+        let ours = format!("{:#}", err.report().backtrace(BacktracePreference::Never));
+        let std = std::error::Report::new(&err).pretty(true).to_string();
+
+        let expected = (
+            msgs[0].to_string(),
+            msgs[1..]
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            None,
+        );
+        assert_eq!(fields(&ours), expected);
+        assert_eq!(fields(&std), expected);
+        assert_eq!(
+            err.report().to_string(),
+            std::error::Report::new(&err).to_string()
+        );
+    }
+}
+
+#[test]
+fn std_report_differences() {
+    let err = Error::builder()
+        .msg("unable to load config")
+        .causes(["permission denied"])
+        .build();
+    assert_eq!(
+        format!("{:#}", err.report().backtrace(BacktracePreference::Never)),
+        "unable to load config\n\nCaused by:\n    0: permission denied"
+    );
+    assert_eq!(
+        std::error::Report::new(&err).pretty(true).to_string(),
+        "unable to load config\n\nCaused by:\n      permission denied"
+    );
+}
+
+#[derive(Debug)]
+struct Captured(Backtrace);
+
+impl fmt::Display for Captured {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("connection reset")
+    }
+}
+
+impl std::error::Error for Captured {
+    fn backtrace(&self) -> Option<&Backtrace> {
+        Some(&self.0)
+    }
+}
+
+#[test]
+fn std_report_backtraces() {
+    let err = Captured(Backtrace::force_capture());
+
+    let ours = format!("{:#}", err.report().backtrace(BacktracePreference::Always));
+    let std = std::error::Report::new(&err)
+        .pretty(true)
+        .show_backtrace(true)
+        .to_string();
+    let (ours, std) = (fields(&ours), fields(&std));
+    assert_eq!((&ours.0, &ours.1), (&std.0, &std.1));
+    assert!(ours.2.unwrap().contains("std_report_backtraces"));
+    assert!(std.2.unwrap().contains("std_report_backtraces"));
+
+    let converted = std::error::Report::from(err.report().backtrace(BacktracePreference::Never));
+    assert_eq!(converted.to_string(), "connection reset");
+    let converted = std::error::Report::from(err.report().backtrace(BacktracePreference::Always));
+    assert!(converted.to_string().contains("\n\nStack backtrace:\n"));
+}
+
+#[test]
+fn std_report_from_err_tools() {
+    let err = Error::builder()
+        .msg("unable to load config")
+        .causes(["unable to read file", "permission denied"])
+        .build();
+    let expected = std::error::Report::new(&err).pretty(true).to_string();
+    assert_eq!(err.std_report().to_string(), expected);
+
+    let dyn_err: &(dyn std::error::Error + 'static) = &err;
+    assert_eq!(dyn_err.std_report().to_string(), expected);
+}
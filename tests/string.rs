@@ -0,0 +1,58 @@
+use errtools::{ErrTools, ResultStrExt, StringError, WrapErr};
+use std::error::Error;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+enum ScriptError {
+    #[error("{msg}")]
+    Failed {
+        source: Box<dyn Error + Send + Sync + 'static>,
+        msg: String,
+    },
+}
+
+impl<E> From<(E, String)> for ScriptError
+where
+    E: Error + Send + Sync + 'static,
+{
+    fn from((source, msg): (E, String)) -> Self {
+        let source = Box::new(source);
+        ScriptError::Failed { source, msg }
+    }
+}
+
+fn run_script() -> Result<(), String> {
+    Err("undefined variable `x`".to_string())
+}
+
+fn run_static() -> Result<(), &'static str> {
+    Err("interpreter crashed")
+}
+
+#[test]
+fn wrap_string_error() {
+    let err = run_script()
+        .err_as_error()
+        .wrap_err::<_, ScriptError>("unable to run script")
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "unable to run script");
+
+    let root = err.source().unwrap();
+    assert_eq!(root.to_string(), "undefined variable `x`");
+    assert!(root.source().is_none());
+
+    let root = err.downcast_refchain::<StringError>().unwrap();
+    assert_eq!(root.as_str(), "undefined variable `x`");
+}
+
+#[test]
+fn wrap_static_str_error() {
+    let err = run_static()
+        .err_as_error()
+        .wrap_err_with::<_, _, ScriptError>(|| "unable to start interpreter")
+        .unwrap_err();
+
+    let root = err.source().unwrap();
+    assert_eq!(root.to_string(), "interpreter crashed");
+}
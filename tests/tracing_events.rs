@@ -0,0 +1,130 @@
+#![cfg(feature = "tracing")]
+
+use errtools::deserialize::Error;
+use errtools::tracing::AsTraceValue;
+use errtools::ErrTools;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::prelude::*;
+
+type Fields = Vec<(String, String)>;
+
+/// Keeps the level and fields of every event
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<(Level, Fields)>>>);
+
+impl<S: Subscriber> Layer<S> for Capture {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        let mut fields = FieldVisitor(vec![]);
+        event.record(&mut fields);
+        let level = *event.metadata().level();
+        self.0.lock().unwrap().push((level, fields.0));
+    }
+}
+
+struct FieldVisitor(Fields);
+
+impl Visit for FieldVisitor {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+fn capture(f: impl FnOnce()) -> Vec<(Level, Fields)> {
+    let capture = Capture::default();
+    let subscriber = tracing_subscriber::registry().with(capture.clone());
+    tracing::subscriber::with_default(subscriber, f);
+    let events = capture.0.lock().unwrap();
+    events.clone()
+}
+
+fn fields(expected: &[(&str, &str)]) -> Fields {
+    expected
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+fn chain() -> Error {
+    Error::builder()
+        .type_name("app::SyncError")
+        .msg("unable to sync")
+        .causes(["request failed", "connection reset"])
+        .build()
+}
+
+#[test]
+fn event_fields() {
+    let err = chain();
+    let events = capture(|| errtools::tracing::event(&err));
+
+    assert_eq!(
+        events,
+        vec![(
+            Level::ERROR,
+            fields(&[
+                ("error.msg", "unable to sync"),
+                ("error.type", "app::SyncError"),
+                ("error.chain_len", "3"),
+                ("error.root.msg", "connection reset"),
+                (
+                    "error.chain",
+                    "unable to sync: request failed: connection reset"
+                ),
+            ])
+        )]
+    );
+}
+
+#[test]
+fn trace_error_at_level() {
+    let err = Error::new("unable to sync");
+    let events = capture(|| err.trace_error(Level::WARN));
+
+    assert_eq!(
+        events,
+        vec![(
+            Level::WARN,
+            fields(&[
+                ("error.msg", "unable to sync"),
+                ("error.chain_len", "1"),
+                ("error.root.msg", "unable to sync"),
+                ("error.chain", "unable to sync"),
+            ])
+        )]
+    );
+}
+
+#[test]
+fn as_trace_value() {
+    let err = chain();
+    let events = capture(|| {
+        tracing::info!(error = %AsTraceValue(&err), report = ?AsTraceValue(&err));
+    });
+
+    assert_eq!(
+        events,
+        vec![(
+            Level::INFO,
+            fields(&[
+                ("error", "unable to sync: request failed: connection reset"),
+                (
+                    "report",
+                    "unable to sync\n\nCaused by:\n    0: request failed\n    1: connection reset"
+                ),
+            ])
+        )]
+    );
+}
@@ -0,0 +1,45 @@
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use errtools::deserialize::Error;
+use errtools::AdhocError;
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn get(value: &JsValue, key: &str) -> JsValue {
+    Reflect::get(value, &JsValue::from_str(key)).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn chain_to_js_value() {
+    let err = Error::builder()
+        .msg("unable to sync")
+        .causes(["connection reset"])
+        .build();
+    let value = errtools::wasm::to_js_value(&err);
+
+    assert!(value.is_object());
+    assert_eq!(
+        get(&value, "msg").as_string().as_deref(),
+        Some("unable to sync")
+    );
+    let source = get(&value, "source");
+    assert_eq!(
+        get(&source, "msg").as_string().as_deref(),
+        Some("connection reset")
+    );
+    assert!(get(&source, "source").is_null());
+
+    let received: Error = serde_wasm_bindgen::from_value(value).unwrap();
+    assert_eq!(received.messages(), ["unable to sync", "connection reset"]);
+}
+
+#[wasm_bindgen_test]
+fn backtrace_is_null() {
+    let value = errtools::wasm::to_js_value(&AdhocError::new("unable to sync"));
+    assert_eq!(
+        get(&value, "msg").as_string().as_deref(),
+        Some("unable to sync")
+    );
+    assert!(get(&value, "backtrace").is_null());
+}
@@ -0,0 +1,140 @@
+use errtools::{deserialize, ErrTools, SerializeConfig, WrapErr, Wrapped};
+use std::borrow::Cow;
+use std::error::Error;
+use std::io;
+
+fn read_config() -> Result<String, Wrapped<io::Error>> {
+    std::fs::read_to_string("/this/path/does/not/exist.toml").wrap_err("unable to read config")
+}
+
+#[test]
+fn static_source_access() {
+    let err = read_config().unwrap_err();
+
+    assert_eq!(err.to_string(), "unable to read config");
+    assert_eq!(err.source.kind(), io::ErrorKind::NotFound);
+    assert_eq!(err.into_inner().kind(), io::ErrorKind::NotFound);
+}
+
+#[test]
+fn chain_downcast() {
+    let err = read_config().unwrap_err();
+
+    let io_error = err.downcast_refchain::<io::Error>().unwrap();
+    assert_eq!(io_error.kind(), io::ErrorKind::NotFound);
+    assert!(err.downcast_refchain::<Wrapped<io::Error>>().is_some());
+}
+
+#[test]
+fn serialize_inner_type_name() {
+    let err = read_config().unwrap_err();
+    let json = serde_json::to_value(&err).unwrap();
+
+    assert_eq!(json["msg"], "unable to read config");
+    assert_eq!(
+        json["source"]["type_name"],
+        std::any::type_name::<io::Error>()
+    );
+}
+
+fn commit() -> Result<(), io::Error> {
+    Err(io::Error::new(io::ErrorKind::Other, "disk full"))
+}
+
+fn rollback_error() -> Wrapped<io::Error> {
+    Wrapped::from((
+        io::Error::new(io::ErrorKind::Other, "connection reset"),
+        "rollback failed".to_string(),
+    ))
+}
+
+#[test]
+fn related_errors_display() {
+    let err = commit()
+        .wrap_err_related("commit failed", rollback_error())
+        .unwrap_err()
+        .with_related(errtools::adhoc!("lock not released"));
+
+    assert_eq!(err.to_string(), "commit failed");
+    assert_eq!(err.related().count(), 2);
+    assert_eq!(
+        format!("{:#}", err),
+        "commit failed\n\
+         \n\
+         Caused by:\n    \
+             0: disk full\n\
+         \n\
+         Related:\n    \
+             0: rollback failed: connection reset\n    \
+             1: lock not released"
+    );
+}
+
+#[test]
+fn related_errors_round_trip() {
+    let mut err = commit()
+        .wrap_err_related("commit failed", rollback_error())
+        .unwrap_err();
+    err.push_related(errtools::adhoc!("lock not released"));
+
+    let json = serde_json::to_string(&err).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(json["msg"], "commit failed");
+    assert_eq!(json["source"]["msg"], "disk full");
+    assert_eq!(json["related"][0]["msg"], "rollback failed");
+    assert_eq!(json["related"][0]["source"]["msg"], "connection reset");
+    assert_eq!(json["related"][1]["msg"], "lock not released");
+    assert!(json["related"][1]["source"].is_null());
+}
+
+fn redact_digits(text: &str) -> Cow<'_, str> {
+    Cow::Owned(text.replace(|c: char| c.is_ascii_digit(), "#"))
+}
+
+#[test]
+fn related_errors_through_serialize() {
+    let err = commit()
+        .wrap_err_related("commit 42 failed", rollback_error())
+        .unwrap_err()
+        .with_related(errtools::adhoc!("lock 7 not released"));
+    let outer: Wrapped<Wrapped<io::Error>> =
+        Err::<(), _>(err).wrap_err("request failed").unwrap_err();
+
+    let json = serde_json::to_value(&outer.serialize()).unwrap();
+    let related = &json["source"]["related"];
+    assert_eq!(related[0]["msg"], "rollback failed");
+    assert_eq!(related[0]["source"]["msg"], "connection reset");
+    assert_eq!(related[1]["msg"], "lock 7 not released");
+    assert!(json.get("related").is_none());
+
+    let dyn_err: &dyn Error = &outer;
+    assert_eq!(
+        serde_json::to_value(&dyn_err.serialize()).unwrap()["source"]["related"],
+        *related
+    );
+    assert_eq!(
+        serde_json::to_value(&outer.serialize().to_owned()).unwrap(),
+        json
+    );
+
+    let config = SerializeConfig::new().redactor(redact_digits);
+    let redacted = serde_json::to_value(&outer.serialize_with(&config)).unwrap();
+    assert_eq!(redacted["source"]["msg"], "commit ## failed");
+    assert_eq!(
+        redacted["source"]["related"][1]["msg"],
+        "lock # not released"
+    );
+
+    let de: deserialize::Error = serde_json::from_value(json).unwrap();
+    let related = de.get(1).unwrap().related();
+    assert_eq!(
+        related[0].messages(),
+        ["rollback failed", "connection reset"]
+    );
+    assert_eq!(related[1].msg(), "lock 7 not released");
+
+    let bytes = bincode::serialize(&outer.serialize()).unwrap();
+    let de: deserialize::Error = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(de.get(1).unwrap().related().len(), 2);
+}
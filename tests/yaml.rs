@@ -0,0 +1,65 @@
+#![feature(backtrace)]
+
+use errtools::{deserialize, ErrTools};
+use std::backtrace::Backtrace;
+use std::error::Error;
+use std::fmt;
+
+mod common;
+
+use common::{RootError, SecondError};
+
+#[derive(Debug)]
+struct Traced(RootError, Backtrace);
+
+impl fmt::Display for Traced {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "traced error")
+    }
+}
+
+impl Error for Traced {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        Some(&self.1)
+    }
+}
+
+fn messages(error: &dyn Error) -> Vec<String> {
+    std::iter::successors(Some(error), |&error| error.source())
+        .map(ToString::to_string)
+        .collect()
+}
+
+fn round_trip(error: &(dyn Error + 'static)) -> (String, deserialize::Error) {
+    let yaml = serde_yaml::to_string(&error.serialize()).unwrap();
+    let de = serde_yaml::from_str(&yaml).unwrap();
+    (yaml, de)
+}
+
+#[test]
+fn round_trip_root_only() {
+    let (yaml, de) = round_trip(&RootError);
+    assert!(yaml.contains("source: null"));
+    assert_eq!(messages(&de), ["root cause"]);
+    assert!(de.source().is_none());
+}
+
+#[test]
+fn round_trip_two_deep() {
+    let err = SecondError(RootError);
+    let (yaml, de) = round_trip(&err);
+    assert!(yaml.contains("  source: null"));
+    assert_eq!(messages(&de), messages(&err));
+}
+
+#[test]
+fn round_trip_multiline_backtrace() {
+    let err = Traced(RootError, Backtrace::force_capture());
+    let (yaml, de) = round_trip(&err);
+    assert!(yaml.contains("backtrace: |"));
+    assert_eq!(messages(&de), messages(&err));
+}